@@ -60,11 +60,14 @@
 //! Then it converts the value back on the way out.
 //!
 //! ggez should handle all of this for you.  `graphics::Color` is
-//! explicitly a sRGB-corrected color, all textures including the
-//! final render target are sRGB-enabled, and when you provide
-//! a linear color to something like `graphics::Mesh` it turns it
-//! into sRGB for you to match everything else.  The purpose of this
-//! example is to show that this actually *works* correctly!
+//! explicitly a sRGB-corrected color.  With `WindowSetup::srgb` on,
+//! all textures including the final render target are sRGB-enabled,
+//! and the colors you give to `DrawParam`, `graphics::clear()` and
+//! `graphics::Mesh` are made linear before they reach the shaders to
+//! match everything else.  Images holding data rather than pictures,
+//! like normal maps, can be loaded as `ColorSpace::Linear` with
+//! `Image::new_with_options()`.  The purpose of this example is to
+//! show that this actually *works* correctly!
 
 use ggez;
 use ggez::event;
@@ -155,7 +158,9 @@ pub fn main() -> GameResult {
         path::PathBuf::from("./resources")
     };
 
-    let cb = ggez::ContextBuilder::new("super_simple", "ggez").add_resource_path(resource_dir);
+    let cb = ggez::ContextBuilder::new("super_simple", "ggez")
+        .window_setup(ggez::conf::WindowSetup::default().srgb(true))
+        .add_resource_path(resource_dir);
     let (ctx, event_loop) = &mut cb.build()?;
     let state = &mut MainState::new(ctx)?;
     event::run(ctx, event_loop, state)
@@ -25,9 +25,8 @@ use std::path;
 type Isometry3 = na::Isometry3<f32>;
 type Point3 = na::Point3<f32>;
 type Vector3 = na::Vector3<f32>;
-// ColorFormat and DepthFormat are hardwired into ggez's drawing code,
-// and there isn't a way to easily change them, so for the moment we just have
-// to know what they are and use the same settings.
+// These have to match the screen's formats, which are sRGB when
+// `WindowSetup::srgb` is on, as it is in `main()` below.
 type ColorFormat = gfx::format::Srgba8;
 type DepthFormat = gfx::format::DepthStencil;
 
@@ -271,7 +270,9 @@ pub fn main() -> GameResult {
         path::PathBuf::from("./resources")
     };
 
-    let cb = ggez::ContextBuilder::new("cube", "ggez").add_resource_path(resource_dir);
+    let cb = ggez::ContextBuilder::new("cube", "ggez")
+        .window_setup(ggez::conf::WindowSetup::default().srgb(true))
+        .add_resource_path(resource_dir);
 
     let (ctx, events_loop) = &mut cb.build()?;
     let state = &mut MainState::new(ctx);
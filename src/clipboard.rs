@@ -0,0 +1,186 @@
+//! Reading and writing text on the system clipboard, for copy and
+//! paste in text fields.
+//!
+//! The clipboard of the platform is used where there is one: X11 or
+//! Wayland on Linux and the BSDs, Windows and macOS.  Elsewhere, or when
+//! [`ModuleConf::clipboard`](../conf/struct.ModuleConf.html#structfield.clipboard)
+//! is off, nothing is ever on the clipboard.  Tests can swap in a
+//! [`MemoryClipboardContext`](struct.MemoryClipboardContext.html) with
+//! [`set_clipboard_context()`](fn.set_clipboard_context.html) to keep
+//! the user's clipboard out of it.
+
+use std::fmt;
+
+use crate::context::Context;
+use crate::error::{GameError, GameResult};
+
+/// Trait object defining a clipboard context.
+pub trait ClipboardContext {
+    /// Returns the text on the clipboard.
+    fn get_text(&mut self) -> GameResult<String>;
+
+    /// Puts text on the clipboard.
+    fn set_text(&mut self, text: &str) -> GameResult;
+
+    /// Whether this is a real clipboard, rather than one that never
+    /// holds anything.
+    fn is_available(&self) -> bool {
+        true
+    }
+}
+
+/// A clipboard context using the platform's clipboard through
+/// `copypasta`.
+#[cfg(not(any(target_os = "android", target_os = "ios")))]
+pub(crate) struct SystemClipboardContext {
+    provider: Box<dyn copypasta::ClipboardProvider>,
+}
+
+#[cfg(not(any(target_os = "android", target_os = "ios")))]
+impl fmt::Debug for SystemClipboardContext {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "<SystemClipboardContext: {:p}>", self)
+    }
+}
+
+#[cfg(not(any(target_os = "android", target_os = "ios")))]
+impl SystemClipboardContext {
+    /// Connects to the clipboard of the display server `window` is on.
+    pub(crate) fn new(window: &winit::window::Window) -> GameResult<Self> {
+        #[cfg(any(
+            target_os = "linux",
+            target_os = "dragonfly",
+            target_os = "freebsd",
+            target_os = "netbsd",
+            target_os = "openbsd"
+        ))]
+        {
+            use winit::platform::unix::WindowExtUnix;
+            if let Some(display) = window.wayland_display() {
+                // The clipboard borrows the window's display connection,
+                // which the window keeps open.  Safe since `Context`
+                // declares `clipboard_context` before `gfx_context`, so
+                // the clipboard is dropped first, and the windows made
+                // by `recreate_context()` share the same connection.
+                #[allow(unsafe_code)]
+                let (_, clipboard) = unsafe {
+                    copypasta::wayland_clipboard::create_clipboards_from_external(display)
+                };
+                return Ok(SystemClipboardContext {
+                    provider: Box::new(clipboard),
+                });
+            }
+        }
+        let _ = window;
+        let clipboard = copypasta::ClipboardContext::new().map_err(|e| {
+            GameError::ClipboardError(format!("Couldn't open the clipboard: {}", e))
+        })?;
+        Ok(SystemClipboardContext {
+            provider: Box::new(clipboard),
+        })
+    }
+}
+
+#[cfg(not(any(target_os = "android", target_os = "ios")))]
+impl ClipboardContext for SystemClipboardContext {
+    fn get_text(&mut self) -> GameResult<String> {
+        self.provider
+            .get_contents()
+            .map_err(|e| GameError::ClipboardError(e.to_string()))
+    }
+
+    fn set_text(&mut self, text: &str) -> GameResult {
+        self.provider
+            .set_contents(text.to_string())
+            .map_err(|e| GameError::ClipboardError(e.to_string()))
+    }
+}
+
+/// A structure that implements [`ClipboardContext`](trait.ClipboardContext.html)
+/// but does nothing: the clipboard is always empty and text put on it
+/// is dropped.  A stub for when the clipboard module is disabled or
+/// there is no clipboard to use.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NullClipboardContext {}
+
+impl ClipboardContext for NullClipboardContext {
+    fn get_text(&mut self) -> GameResult<String> {
+        Ok(String::new())
+    }
+
+    fn set_text(&mut self, _text: &str) -> GameResult {
+        Ok(())
+    }
+
+    fn is_available(&self) -> bool {
+        false
+    }
+}
+
+/// A structure that implements [`ClipboardContext`](trait.ClipboardContext.html)
+/// with a clipboard of its own, which only the game sees.  Useful for
+/// tests.
+#[derive(Debug, Clone, Default)]
+pub struct MemoryClipboardContext {
+    text: String,
+}
+
+impl MemoryClipboardContext {
+    /// Creates an empty clipboard.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl ClipboardContext for MemoryClipboardContext {
+    fn get_text(&mut self) -> GameResult<String> {
+        Ok(self.text.clone())
+    }
+
+    fn set_text(&mut self, text: &str) -> GameResult {
+        self.text = text.to_string();
+        Ok(())
+    }
+}
+
+/// Returns the text on the clipboard, or an empty string if there is
+/// none.
+pub fn get_text(ctx: &mut Context) -> GameResult<String> {
+    ctx.clipboard_context.get_text()
+}
+
+/// Puts text on the clipboard.
+pub fn set_text(ctx: &mut Context, text: &str) -> GameResult {
+    ctx.clipboard_context.set_text(text)
+}
+
+/// Returns whether the clipboard is a real one, rather than the stub
+/// used when it is disabled or unavailable.
+pub fn is_available(ctx: &Context) -> bool {
+    ctx.clipboard_context.is_available()
+}
+
+/// Replaces the clipboard context, e.g. with a
+/// [`MemoryClipboardContext`](struct.MemoryClipboardContext.html) in
+/// tests.
+pub fn set_clipboard_context(ctx: &mut Context, clipboard: Box<dyn ClipboardContext>) {
+    ctx.clipboard_context = clipboard;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn headless_test_memory_clipboard() {
+        let mut clipboard = MemoryClipboardContext::new();
+        assert_eq!(clipboard.get_text().unwrap(), "");
+        clipboard.set_text("level 1-2").unwrap();
+        assert_eq!(clipboard.get_text().unwrap(), "level 1-2");
+
+        let mut null = NullClipboardContext::default();
+        null.set_text("level 1-2").unwrap();
+        assert_eq!(null.get_text().unwrap(), "");
+        assert!(!null.is_available());
+    }
+}
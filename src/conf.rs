@@ -16,6 +16,7 @@ use std::io;
 
 use serde::{Serialize, Deserialize};
 use crate::error::GameResult;
+use crate::graphics;
 
 /// Possible fullscreen modes.
 #[derive(Debug, Copy, Clone, Serialize, Deserialize, PartialEq, Eq)]
@@ -23,13 +24,97 @@ pub enum FullscreenType {
     /// Windowed mode.
     Windowed,
     /// True fullscreen, which used to be preferred 'cause it can have
-    /// small performance benefits over windowed fullscreen.
+    /// small performance benefits over windowed fullscreen.  Changes
+    /// the monitor's video mode to `WindowMode::video_mode`, or the
+    /// fastest one of the window's size.
     True,
     /// Windowed fullscreen, generally preferred over real fullscreen
     /// these days 'cause it plays nicer with multiple monitors.
     Desktop,
 }
 
+/// How screen coordinates are fitted to the window, see
+/// [`WindowMode::scaling`](struct.WindowMode.html#structfield.scaling).
+///
+/// The modes with a fixed virtual resolution keep the same screen
+/// coordinates whatever the window size and DPI scale factor are, and
+/// mouse positions are converted into them.
+#[derive(Debug, Copy, Clone, Serialize, Deserialize, PartialEq, SmartDefault)]
+#[serde(tag = "type")]
+pub enum ScalingMode {
+    /// Screen coordinates are the window's size in logical pixels,
+    /// which are physical pixels divided by the DPI scale factor.
+    #[default]
+    Window,
+    /// Screen coordinates are `width` by `height`, scaled to fit the
+    /// window while keeping their aspect ratio, with black bars on two
+    /// sides if the window's aspect ratio is different.
+    Letterbox {
+        /// Virtual width
+        width: f32,
+        /// Virtual height
+        height: f32,
+    },
+    /// Like `Letterbox`, but only scaled by whole numbers when the
+    /// window is big enough, so that pixel art stays sharp.
+    IntegerScale {
+        /// Virtual width
+        width: f32,
+        /// Virtual height
+        height: f32,
+    },
+    /// Screen coordinates are `width` by `height`, stretched to fill
+    /// the whole window.
+    Stretch {
+        /// Virtual width
+        width: f32,
+        /// Virtual height
+        height: f32,
+    },
+    /// Everything drawn to the screen is really drawn to a `width` by
+    /// `height` canvas, which is then scaled up like `IntegerScale`
+    /// with `FilterMode::Nearest`.  Unlike the other modes, nothing can
+    /// be drawn between the virtual pixels, so pixel art stays truly
+    /// pixelated when moved, rotated or scaled.
+    PixelPerfect {
+        /// Virtual width
+        width: u16,
+        /// Virtual height
+        height: u16,
+    },
+}
+
+impl ScalingMode {
+    /// Returns the fixed virtual resolution, or `None` for
+    /// `ScalingMode::Window`.
+    pub fn virtual_size(self) -> Option<(f32, f32)> {
+        match self {
+            ScalingMode::Window => None,
+            ScalingMode::Letterbox { width, height }
+            | ScalingMode::IntegerScale { width, height }
+            | ScalingMode::Stretch { width, height } => Some((width, height)),
+            ScalingMode::PixelPerfect { width, height } => {
+                Some((f32::from(width), f32::from(height)))
+            }
+        }
+    }
+}
+
+/// A resolution, refresh rate and color depth a monitor can be set
+/// to in `FullscreenType::True`, as listed by
+/// [`graphics::video_modes()`](../graphics/fn.video_modes.html).
+#[derive(Debug, Copy, Clone, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct VideoMode {
+    /// Width in pixels
+    pub width: u32,
+    /// Height in pixels
+    pub height: u32,
+    /// Refresh rate in Hz
+    pub refresh_rate: u16,
+    /// Bits per pixel
+    pub bit_depth: u16,
+}
+
 /// A builder structure containing window settings
 /// that can be set at runtime and changed with [`graphics::set_mode()`](../graphics/fn.set_mode.html).
 ///
@@ -49,6 +134,10 @@ pub enum FullscreenType {
 ///     min_height: 0.0,
 ///     max_height: 0.0,
 ///     resizable: false,
+///     monitor: None,
+///     video_mode: None,
+///     scaling: ScalingMode::Window,
+///     border_color: ggez::graphics::BLACK,
 /// }
 /// # , WindowMode::default());}
 /// ```
@@ -84,6 +173,31 @@ pub struct WindowMode {
     /// Whether or not the window is resizable
     #[default = false]
     pub resizable: bool,
+    /// The monitor to go fullscreen on, as an index into
+    /// [`graphics::monitors()`](../graphics/fn.monitors.html); `None`
+    /// means the one the window is on, as does a monitor that isn't
+    /// connected anymore.
+    #[default(None)]
+    #[serde(default)]
+    pub monitor: Option<usize>,
+    /// The video mode to use in `FullscreenType::True`; `None` means
+    /// the fastest one whose size is `width` by `height`.
+    #[default(None)]
+    #[serde(default)]
+    pub video_mode: Option<VideoMode>,
+    /// How screen coordinates are fitted to the window.
+    #[default(ScalingMode::Window)]
+    #[serde(default)]
+    pub scaling: ScalingMode,
+    /// The color of the bars around the screen coordinates when
+    /// `scaling` keeps their aspect ratio.
+    #[default(graphics::BLACK)]
+    #[serde(default = "default_border_color")]
+    pub border_color: graphics::Color,
+}
+
+fn default_border_color() -> graphics::Color {
+    graphics::BLACK
 }
 
 impl WindowMode {
@@ -131,6 +245,30 @@ impl WindowMode {
         self.resizable = resizable;
         self
     }
+
+    /// Set the monitor to go fullscreen on.
+    pub fn monitor(mut self, monitor: Option<usize>) -> Self {
+        self.monitor = monitor;
+        self
+    }
+
+    /// Set the video mode for true fullscreen.
+    pub fn video_mode(mut self, video_mode: Option<VideoMode>) -> Self {
+        self.video_mode = video_mode;
+        self
+    }
+
+    /// Set how screen coordinates are fitted to the window.
+    pub fn scaling(mut self, scaling: ScalingMode) -> Self {
+        self.scaling = scaling;
+        self
+    }
+
+    /// Set the color of the bars around the screen coordinates.
+    pub fn border_color(mut self, border_color: graphics::Color) -> Self {
+        self.border_color = border_color;
+        self
+    }
 }
 
 /// A builder structure containing window settings
@@ -146,7 +284,8 @@ impl WindowMode {
 ///     samples: NumSamples::Zero,
 ///     vsync: true,
 ///     icon: "".to_owned(),
-///     srgb: true,
+///     srgb: false,
+///     drag_and_drop: false,
 /// }
 /// # , WindowSetup::default()); }
 /// ```
@@ -167,9 +306,19 @@ pub struct WindowSetup {
     #[default(String::new())]
     pub icon: String,
     /// Whether or not to enable sRGB (gamma corrected color)
-    /// handling on the display.
+    /// handling on the display.  When on, the screen, canvases and
+    /// images use sRGB formats, so shaders and blending work on linear
+    /// colors.  See `graphics::ColorSpace` for images holding data.
     #[default = false]
     pub srgb: bool,
+    /// Whether files can be dropped on the window on Windows, which
+    /// other platforms always allow.  It's off by default because it
+    /// makes the window thread a single-threaded COM apartment, while
+    /// the `cpal` audio backend needs a multithreaded one on the same
+    /// thread, so sound breaks with it on.
+    #[serde(default)]
+    #[default = false]
+    pub drag_and_drop: bool,
 }
 
 impl WindowSetup {
@@ -202,6 +351,12 @@ impl WindowSetup {
         self.srgb = active;
         self
     }
+
+    /// Set whether files can be dropped on the window on Windows.
+    pub fn drag_and_drop(mut self, active: bool) -> Self {
+        self.drag_and_drop = active;
+        self
+    }
 }
 
 /// Possible backends.
@@ -326,6 +481,7 @@ impl NumSamples {
 /// ModuleConf {
 ///     gamepad: true,
 ///     audio: true,
+///     clipboard: true,
 /// }
 /// # , ModuleConf::default()); }
 /// ```
@@ -346,6 +502,16 @@ pub struct ModuleConf {
     /// The audio module.
     #[default = true]
     pub audio: bool,
+
+    /// The clipboard module.  When off, or when the platform's
+    /// clipboard can't be opened, the clipboard is always empty.
+    #[serde(default = "default_clipboard")]
+    #[default = true]
+    pub clipboard: bool,
+}
+
+fn default_clipboard() -> bool {
+    true
 }
 
 impl ModuleConf {
@@ -360,6 +526,66 @@ impl ModuleConf {
         self.audio = audio;
         self
     }
+
+    /// Sets whether or not to enable the clipboard module.
+    pub fn clipboard(mut self, clipboard: bool) -> Self {
+        self.clipboard = clipboard;
+        self
+    }
+}
+
+/// Settings of the frame limiter, which waits at the end of
+/// [`graphics::present()`](../graphics/fn.present.html) of the main
+/// window so that frames don't come faster than a target rate, even
+/// without vsync.
+///
+/// Defaults:
+///
+/// ```rust
+/// # use ggez::conf::*;
+/// # fn main() { assert_eq!(
+/// FrameLimit {
+///     target_fps: 0.0,
+///     background_fps: 0.0,
+///     spin_ms: 1.0,
+/// }
+/// # , FrameLimit::default()); }
+/// ```
+#[derive(Debug, Copy, Clone, SmartDefault, Serialize, Deserialize, PartialEq)]
+pub struct FrameLimit {
+    /// The most frames per second to run at; 0 means no limit.
+    #[default = 0.0]
+    pub target_fps: f64,
+    /// The most frames per second to run at while none of the game's
+    /// windows is focused, to save power in the background; 0 means
+    /// the same as `target_fps`.
+    #[default = 0.0]
+    pub background_fps: f64,
+    /// How many milliseconds before a frame is due to stop sleeping and
+    /// spin instead, since the OS may wake a sleeping thread late.
+    /// Higher values are more accurate but use more CPU.
+    #[default = 1.0]
+    pub spin_ms: f64,
+}
+
+impl FrameLimit {
+    /// Set the most frames per second to run at; 0 means no limit.
+    pub fn target_fps(mut self, target_fps: f64) -> Self {
+        self.target_fps = target_fps;
+        self
+    }
+
+    /// Set the most frames per second to run at in the background.
+    pub fn background_fps(mut self, background_fps: f64) -> Self {
+        self.background_fps = background_fps;
+        self
+    }
+
+    /// Set how many milliseconds to spin instead of sleeping.
+    pub fn spin_ms(mut self, spin_ms: f64) -> Self {
+        self.spin_ms = spin_ms;
+        self
+    }
 }
 
 /// A structure containing configuration data
@@ -375,6 +601,7 @@ impl ModuleConf {
 ///     window_setup: WindowSetup::default(),
 ///     backend: Backend::default(),
 ///     modules: ModuleConf::default(),
+///     frame_limit: FrameLimit::default(),
 /// }
 /// # , Conf::default()); }
 /// ```
@@ -388,6 +615,9 @@ pub struct Conf {
     pub backend: Backend,
     /// Which modules to enable.
     pub modules: ModuleConf,
+    /// Frame rate limiting
+    #[serde(default)]
+    pub frame_limit: FrameLimit,
 }
 
 impl Conf {
@@ -413,4 +643,10 @@ impl Conf {
         self.modules = modules;
         self
     }
+
+    /// Sets the frame limiter settings
+    pub fn frame_limit(mut self, frame_limit: FrameLimit) -> Self {
+        self.frame_limit = frame_limit;
+        self
+    }
 }
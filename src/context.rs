@@ -3,17 +3,19 @@ use std::fmt;
 use std::path;
 #[cfg(debug_assertions)]
 use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time;
 
 /// We re-export winit so it's easy for people to use the same version as we are
 /// without having to mess around figuring it out.
 pub use winit;
 
+use crate::clipboard;
 use crate::conf;
 use crate::error::GameResult;
 use crate::event::winit_event;
 use crate::filesystem::Filesystem;
 use crate::graphics::{self, FilterMode, Point2};
-use crate::input::{gamepad, keyboard, mouse};
+use crate::input::{gamepad, keyboard, mouse, touch};
 use crate::timer;
 use glutin::platform::ContextTraitExt;
 
@@ -43,6 +45,10 @@ use glutin::platform::ContextTraitExt;
 pub struct Context {
     /// Filesystem state
     pub filesystem: Filesystem,
+    /// Clipboard context.  Declared before the graphics context so it
+    /// is dropped first, as a Wayland clipboard uses the window's
+    /// display connection.
+    pub clipboard_context: Box<dyn clipboard::ClipboardContext>,
     /// Graphics state
     pub(crate) gfx_context: crate::graphics::context::GraphicsContext,
     /// Timer state
@@ -51,6 +57,8 @@ pub struct Context {
     pub keyboard_context: keyboard::KeyboardContext,
     /// Mouse context
     pub mouse_context: mouse::MouseContext,
+    /// Touch context
+    pub touch_context: touch::TouchContext,
     /// Gamepad context
     pub gamepad_context: Box<dyn gamepad::GamepadContext>,
 
@@ -66,7 +74,7 @@ pub struct Context {
     /// Compiles to nothing in release mode, and so
     /// vanishes; meanwhile we get dead-code warnings.
     #[allow(dead_code)]
-    debug_id: DebugId,
+    pub(crate) debug_id: DebugId,
 }
 
 impl fmt::Debug for Context {
@@ -91,6 +99,7 @@ impl Context {
             debug_id,
         )?;
         let mouse_context = mouse::MouseContext::new();
+        let touch_context = touch::TouchContext::new();
         let keyboard_context = keyboard::KeyboardContext::new();
         let gamepad_context: Box<dyn gamepad::GamepadContext> = if conf.modules.gamepad {
             let gp: Box<dyn gamepad::GamepadContext> = if let Ok(ctx) = gamepad::GilrsGamepadContext::new() {
@@ -102,8 +111,9 @@ impl Context {
         } else {
             Box::new(gamepad::NullGamepadContext::default())
         };
+        let clipboard_context = new_clipboard_context(&conf, &graphics_context);
 
-        let ctx = Context {
+        let mut ctx = Context {
             conf,
             filesystem: fs,
             gfx_context: graphics_context,
@@ -111,10 +121,13 @@ impl Context {
             timer_context,
             keyboard_context,
             gamepad_context,
+            clipboard_context,
             mouse_context,
+            touch_context,
 
             debug_id,
         };
+        graphics::canvas::update_pixel_canvas(&mut ctx)?;
 
         Ok(ctx)
     }
@@ -128,21 +141,56 @@ impl Context {
     /// you receive before processing them yourself.
     pub fn process_event<'a>(&mut self, event: &winit::event::Event<'a, ()>) {
         match event {
-            winit_event::Event::WindowEvent { event, .. } => match event {
+            winit_event::Event::WindowEvent { window_id, event } => match event {
                 winit_event::WindowEvent::Resized(physical_size) => {
-                    self.gfx_context.window.resize(*physical_size);
-                    self.gfx_context.resize_viewport();
+                    self.gfx_context.resize_window(*window_id, *physical_size);
+                    if *window_id == self.gfx_context.main_window {
+                        self.gfx_context.minimized =
+                            physical_size.width == 0 || physical_size.height == 0;
+                    }
                 }
-                winit_event::WindowEvent::CursorMoved {
-                    position: logical_position,
-                    ..
-                } => {
-                    self.mouse_context.set_last_position(Point2::new(
-                        logical_position.x as f32,
-                        logical_position.y as f32,
-                    ));
+                winit_event::WindowEvent::ScaleFactorChanged { .. } => {
+                    if *window_id == self.gfx_context.active_window() {
+                        self.gfx_context.update_scaling();
+                    }
+                }
+                winit_event::WindowEvent::CursorMoved { position, .. } => {
+                    // Other windows have screen coordinates of their own.
+                    if *window_id == self.gfx_context.active_window() {
+                        let position = self
+                            .gfx_context
+                            .window_to_screen(position.x as f32, position.y as f32);
+                        self.mouse_context.set_last_position(position);
+                        self.mouse_context.set_cursor_in_window(true);
+                    }
+                }
+                winit_event::WindowEvent::Focused(focused) => {
+                    self.timer_context.set_window_focused(*window_id, *focused);
+                }
+                winit_event::WindowEvent::CursorEntered { .. } => {
+                    if *window_id == self.gfx_context.active_window() {
+                        self.mouse_context.set_cursor_in_window(true);
+                    }
                 }
-                winit_event::WindowEvent::MouseInput { button, state, .. } => {
+                winit_event::WindowEvent::CursorLeft { .. } => {
+                    if *window_id == self.gfx_context.active_window() {
+                        self.mouse_context.set_cursor_in_window(false);
+                    }
+                }
+                winit_event::WindowEvent::Touch(touch) => {
+                    if *window_id == self.gfx_context.active_window() {
+                        let touch = touch::Touch::from_winit(&self.gfx_context, touch);
+                        self.touch_context.handle_touch(touch, time::Instant::now());
+                        if self.touch_context.drove_mouse() {
+                            self.emulate_mouse(&touch);
+                        }
+                    }
+                }
+                // Like the cursor, the buttons and keys are those of the
+                // active window.
+                winit_event::WindowEvent::MouseInput { button, state, .. }
+                    if *window_id == self.gfx_context.active_window() =>
+                {
                     let pressed = match state {
                         winit_event::ElementState::Pressed => true,
                         winit_event::ElementState::Released => false,
@@ -158,7 +206,7 @@ impl Context {
                         ..
                     },
                     ..
-                } => {
+                } if *window_id == self.gfx_context.active_window() => {
                     let pressed = match state {
                         winit_event::ElementState::Pressed => true,
                         winit_event::ElementState::Released => false,
@@ -175,9 +223,65 @@ impl Context {
                         .set_last_delta(Point2::new(*x as f32, *y as f32));
                 }
             }
+            winit_event::Event::MainEventsCleared => {
+                self.touch_context.update(time::Instant::now());
+            }
+            winit_event::Event::Suspended => {
+                if let Err(e) = graphics::suspend(self) {
+                    warn!("Couldn't suspend the graphics: {}", e);
+                }
+            }
+            winit_event::Event::Resumed => {
+                if let Err(e) = graphics::resume(self) {
+                    warn!("Couldn't resume the graphics: {}", e);
+                }
+            }
             _ => (),
         };
     }
+
+    /// Moves the mouse and presses its left button along with the
+    /// primary touch.
+    fn emulate_mouse(&mut self, touch: &touch::Touch) {
+        let position = Point2::from(touch.position);
+        match touch.phase {
+            winit_event::TouchPhase::Started => {
+                self.mouse_context.set_last_position(position);
+                self.mouse_context
+                    .set_button(mouse::MouseButton::Left, true);
+            }
+            winit_event::TouchPhase::Moved => {
+                let last = Point2::from(mouse::position(self));
+                self.mouse_context
+                    .set_last_delta(Point2::from(position - last));
+                self.mouse_context.set_last_position(position);
+            }
+            winit_event::TouchPhase::Ended | winit_event::TouchPhase::Cancelled => {
+                self.mouse_context.set_last_position(position);
+                self.mouse_context
+                    .set_button(mouse::MouseButton::Left, false);
+            }
+        }
+    }
+}
+
+/// Opens the platform's clipboard if the clipboard module is enabled,
+/// falling back to one that is always empty.
+fn new_clipboard_context(
+    conf: &conf::Conf,
+    gfx: &graphics::context::GraphicsContext,
+) -> Box<dyn clipboard::ClipboardContext> {
+    #[cfg(not(any(target_os = "android", target_os = "ios")))]
+    {
+        if conf.modules.clipboard {
+            match clipboard::SystemClipboardContext::new(gfx.window.window()) {
+                Ok(clipboard) => return Box::new(clipboard),
+                Err(e) => warn!("Clipboard unavailable: {}", e),
+            }
+        }
+    }
+    let _ = (conf, gfx);
+    Box::new(clipboard::NullClipboardContext::default())
 }
 
 /// A builder object for creating a [`Context`](struct.Context.html).
@@ -224,6 +328,12 @@ impl ContextBuilder {
         self
     }
 
+    /// Sets the frame limiter settings.
+    pub fn frame_limit(mut self, frame_limit: conf::FrameLimit) -> Self {
+        self.conf.frame_limit = frame_limit;
+        self
+    }
+
     /// Sets all the config options, overriding any previous
     /// ones from [`window_setup()`](#method.window_setup),
     /// [`window_mode()`](#method.window_mode), and
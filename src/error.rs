@@ -39,6 +39,8 @@ pub enum GameError {
     ShaderProgramError(gfx::shade::ProgramError),
     /// Something went wrong with the `gilrs` gamepad-input library.
     GamepadError(String),
+    /// Something went wrong reading or writing the clipboard.
+    ClipboardError(String),
     /// Something went wrong with the `lyon` shape-tesselation library.
     LyonError(String),
     /// Something went wrong while parsing something.
@@ -15,19 +15,22 @@ use gilrs;
 pub use gilrs::Axis;
 /// A button of some device (gamepad, joystick...).
 pub use gilrs::Button;
-use winit::{self, dpi};
+use std::path::Path;
+
 /// A mouse button.
 pub use winit::event::MouseButton;
 /// `winit` event loop.
 pub use winit::event_loop::EventLoop;
+use winit::event_loop::EventLoopWindowTarget;
 
 use crate::context::Context;
 use crate::error::GameResult;
 pub use crate::input::gamepad::GamepadId;
 pub use crate::input::keyboard::{KeyCode, KeyMods};
+pub use crate::input::touch::{Gesture, Touch};
 
 use self::winit_event::*;
-use crate::graphics::window;
+pub use crate::graphics::WindowId;
 
 // TODO LATER: I kinda hate all these re-exports.  I kinda hate
 // a lot of the details of the `EventHandler` and input now though,
@@ -139,9 +142,70 @@ pub trait EventHandler {
         false
     }
 
+    /// A finger touched, moved on or left the touch screen.  Touches
+    /// can also be read at any time from the
+    /// [`touch`](../input/touch/index.html) module, which can turn the
+    /// primary one into mouse events too.
+    fn touch_event(&mut self, _ctx: &mut Context, _touch: Touch) {}
+
+    /// A gesture was recognized from the touches: a tap, long press,
+    /// swipe, pinch or two-finger pan.
+    fn gesture_event(&mut self, _ctx: &mut Context, _gesture: Gesture) {}
+
     /// Called when the user resizes the window, or when it is resized
     /// via [`graphics::set_mode()`](../graphics/fn.set_mode.html).
     fn resize_event(&mut self, _ctx: &mut Context, _width: f32, _height: f32) {}
+
+    /// Called when the window's DPI scale factor changes, e.g. because
+    /// it was moved to another monitor.  The screen coordinates have
+    /// already been refitted by then, unless they were set with
+    /// [`graphics::set_screen_coordinates()`](../graphics/fn.set_screen_coordinates.html).
+    fn scale_factor_changed_event(&mut self, _ctx: &mut Context, _scale_factor: f64) {}
+
+    /// Called when a file is dropped on the window.  The path is on the
+    /// real filesystem, not in the game's
+    /// [virtual one](../filesystem/index.html), so read it with
+    /// `std::fs`.  Dropping several files calls this once for each.
+    /// On Windows, files can only be dropped if
+    /// [`WindowSetup::drag_and_drop`](../conf/struct.WindowSetup.html#structfield.drag_and_drop)
+    /// is on.
+    fn file_dropped_event(&mut self, _ctx: &mut Context, _path: &Path) {}
+
+    /// Called when a file is dragged over the window, before it is
+    /// dropped or the drag is cancelled.
+    fn file_hovered_event(&mut self, _ctx: &mut Context, _path: &Path) {}
+
+    /// Called when files dragged over the window are taken away again
+    /// without being dropped.
+    fn file_hover_cancelled_event(&mut self, _ctx: &mut Context) {}
+
+    /// Called when the window is moved, with the position of its top
+    /// left corner on the desktop in physical pixels, as returned by
+    /// [`graphics::window_position()`](../graphics/fn.window_position.html).
+    fn window_moved_event(&mut self, _ctx: &mut Context, _x: i32, _y: i32) {}
+
+    /// Called when the window is minimized or restored, as far as
+    /// [`graphics::is_minimized()`](../graphics/fn.is_minimized.html)
+    /// can tell, which is only on Windows.  Games can pause while it's
+    /// minimized.
+    fn minimized_event(&mut self, _ctx: &mut Context, _minimized: bool) {}
+
+    /// Called for every event of the windows opened with
+    /// [`graphics::create_window()`](../graphics/fn.create_window.html),
+    /// instead of the callbacks above, which are only called for the
+    /// main window.
+    fn window_event(&mut self, _ctx: &mut Context, _window: WindowId, _event: &WindowEvent) {}
+
+    /// Called when the app goes to the background, as on Android,
+    /// where nothing is shown until it resumes.  A good time to pause
+    /// and save.
+    fn suspend_event(&mut self, _ctx: &mut Context) {}
+
+    /// Called when the app comes back from the background.  If the GL
+    /// context was lost meanwhile, it has been recreated with
+    /// [`graphics::recreate_context()`](../graphics/fn.recreate_context.html)
+    /// by then.
+    fn resume_event(&mut self, _ctx: &mut Context) {}
 }
 
 /// Terminates the [`ggez::event::run()`](fn.run.html) loop by setting
@@ -151,140 +215,572 @@ pub fn quit(ctx: &mut Context) {
     ctx.continuing = false;
 }
 
-/*
-/// Runs the game's main loop, calling event callbacks on the given state
-/// object as events occur.
+/// Feeds an event to the `Context` with
+/// [`Context::process_event()`](../struct.Context.html#method.process_event),
+/// then calls the matching callbacks of `state`.  Once the events of a
+/// loop iteration are cleared, it ticks the timer, hands on gamepad
+/// events and calls [`update()`](trait.EventHandler.html#tymethod.update)
+/// and [`draw()`](trait.EventHandler.html#tymethod.draw), so calling
+/// this on every event is all an event loop has to do.
 ///
-/// It does not try to do any type of framerate limiting.  See the
-/// documentation for the [`timer`](../timer/index.html) module for more info.
-pub fn run<S>(ctx: &'static mut Context, events_loop: &mut EventLoop<()>, state: &'static mut S) -> GameResult
-    where
-        S: EventHandler,
+/// If the GL context was lost while the app was suspended, it is
+/// recreated on `event_loop` when the app resumes, before
+/// [`resume_event()`](trait.EventHandler.html#method.resume_event)
+/// is called.
+pub fn handle_event<S>(
+    ctx: &mut Context,
+    event_loop: &EventLoopWindowTarget<()>,
+    state: &mut S,
+    event: &Event<()>,
+) -> GameResult
+where
+    S: EventHandler,
 {
-    use crate::input::{keyboard, mouse};
+    use crate::graphics;
+    use crate::input::{keyboard, mouse, touch};
 
-    // If you are writing your own event loop, make sure
-    // you include `timer_context.tick()` and
-    // `ctx.process_event()` calls.  These update ggez's
-    // internal state however necessary.
-    while ctx.continuing {
-        events_loop.run_return(|event, _target, _flow| {
-            ctx.timer_context.tick();
-            ctx.process_event(&event);
-            match event {
-                Event::WindowEvent { event, .. } => match event {
-                    WindowEvent::Resized(logical_size) => {
-                        // let actual_size = logical_size;
-                        state.resize_event(
-                            ctx,
-                            logical_size.width as f32,
-                            logical_size.height as f32,
-                        );
-                    }
-                    WindowEvent::CloseRequested => {
-                        if !state.quit_event(ctx) {
-                            quit(ctx);
-                        }
-                    }
-                    WindowEvent::Focused(gained) => {
-                        state.focus_event(ctx, gained);
-                    }
-                    WindowEvent::ReceivedCharacter(ch) => {
-                        state.text_input_event(ctx, ch);
-                    }
-                    WindowEvent::KeyboardInput {
-                        input:
-                        KeyboardInput {
-                            state: ElementState::Pressed,
-                            virtual_keycode: Some(keycode),
-                            modifiers,
-                            ..
-                        },
+    // Minimizing only shows as a resize, so compare before and after.
+    let was_minimized = graphics::is_minimized(ctx);
+    ctx.process_event(event);
+    match event {
+        Event::WindowEvent { window_id, event } if *window_id != graphics::main_window(ctx) => {
+            state.window_event(ctx, *window_id, event);
+        }
+        Event::WindowEvent { event, .. } => match event {
+            WindowEvent::Resized(size) => {
+                state.resize_event(ctx, size.width as f32, size.height as f32);
+                let minimized = graphics::is_minimized(ctx);
+                if minimized != was_minimized {
+                    state.minimized_event(ctx, minimized);
+                }
+            }
+            WindowEvent::Moved(position) => {
+                state.window_moved_event(ctx, position.x, position.y);
+            }
+            WindowEvent::ScaleFactorChanged { scale_factor, .. } => {
+                state.scale_factor_changed_event(ctx, *scale_factor);
+            }
+            WindowEvent::CloseRequested => {
+                if !state.quit_event(ctx) {
+                    quit(ctx);
+                }
+            }
+            WindowEvent::Focused(gained) => {
+                state.focus_event(ctx, *gained);
+            }
+            WindowEvent::DroppedFile(path) => {
+                state.file_dropped_event(ctx, path);
+            }
+            WindowEvent::HoveredFile(path) => {
+                state.file_hovered_event(ctx, path);
+            }
+            WindowEvent::HoveredFileCancelled => {
+                state.file_hover_cancelled_event(ctx);
+            }
+            WindowEvent::ReceivedCharacter(ch) => {
+                state.text_input_event(ctx, *ch);
+            }
+            WindowEvent::KeyboardInput {
+                input:
+                    KeyboardInput {
+                        state: element_state,
+                        virtual_keycode: Some(keycode),
                         ..
-                    } => {
+                    },
+                ..
+            } => {
+                let keymods = keyboard::active_mods(ctx);
+                match element_state {
+                    ElementState::Pressed => {
                         let repeat = keyboard::is_key_repeated(ctx);
-                        state.key_down_event(ctx, keycode, modifiers.into(), repeat);
-                    }
-                    WindowEvent::KeyboardInput {
-                        input:
-                        KeyboardInput {
-                            state: ElementState::Released,
-                            virtual_keycode: Some(keycode),
-                            modifiers,
-                            ..
-                        },
-                        ..
-                    } => {
-                        state.key_up_event(ctx, keycode, modifiers.into());
+                        state.key_down_event(ctx, *keycode, keymods, repeat);
                     }
-                    WindowEvent::MouseWheel { delta, .. } => {
-                        let (x, y) = match delta {
-                            MouseScrollDelta::LineDelta(x, y) => (x, y),
-                            MouseScrollDelta::PixelDelta(dpi::LogicalPosition { x, y }) => {
-                                (x as f32, y as f32)
-                            }
-                        };
-                        state.mouse_wheel_event(ctx, x, y);
+                    ElementState::Released => {
+                        state.key_up_event(ctx, *keycode, keymods);
                     }
-                    WindowEvent::MouseInput {
-                        state: element_state,
-                        button,
-                        ..
-                    } => {
-                        let position = mouse::position(ctx);
-                        match element_state {
-                            ElementState::Pressed => {
-                                state.mouse_button_down_event(ctx, button, position.x, position.y)
-                            }
-                            ElementState::Released => {
-                                state.mouse_button_up_event(ctx, button, position.x, position.y)
-                            }
-                        }
+                }
+            }
+            WindowEvent::MouseWheel { delta, .. } => {
+                let (x, y) = match delta {
+                    MouseScrollDelta::LineDelta(x, y) => (*x, *y),
+                    MouseScrollDelta::PixelDelta(position) => {
+                        (position.x as f32, position.y as f32)
                     }
-                    WindowEvent::CursorMoved { .. } => {
-                        let position = mouse::position(ctx);
-                        let delta = mouse::delta(ctx);
-                        state.mouse_motion_event(ctx, position.x, position.y, delta.x, delta.y);
+                };
+                state.mouse_wheel_event(ctx, x, y);
+            }
+            WindowEvent::MouseInput {
+                state: element_state,
+                button,
+                ..
+            } => {
+                let position = mouse::position(ctx);
+                match element_state {
+                    ElementState::Pressed => {
+                        state.mouse_button_down_event(ctx, *button, position.x, position.y)
                     }
-                    _x => {
-                        // trace!("ignoring window event {:?}", x);
+                    ElementState::Released => {
+                        state.mouse_button_up_event(ctx, *button, position.x, position.y)
                     }
-                },
-                Event::DeviceEvent { event, .. } => match event {
-                    _ => (),
-                },
-                Event::Resumed => (),
-                Event::Suspended => (),
-                Event::RedrawRequested(win) => {
-                    if win == ctx.gfx_context.window.window().id() {
-                        state.draw(ctx).unwrap();
+                }
+            }
+            WindowEvent::CursorMoved { .. } => {
+                let position = mouse::position(ctx);
+                let delta = mouse::delta(ctx);
+                state.mouse_motion_event(ctx, position.x, position.y, delta.x, delta.y);
+            }
+            WindowEvent::Touch(touch) => {
+                let touch = Touch::from_winit(&ctx.gfx_context, touch);
+                state.touch_event(ctx, touch);
+                // Only the active window's touches are tracked.
+                let tracked = ctx.gfx_context.active_window() == graphics::main_window(ctx);
+                if tracked && ctx.touch_context.drove_mouse() {
+                    let position = mouse::position(ctx);
+                    match touch.phase {
+                        TouchPhase::Started => state.mouse_button_down_event(
+                            ctx,
+                            MouseButton::Left,
+                            position.x,
+                            position.y,
+                        ),
+                        TouchPhase::Moved => {
+                            let delta = mouse::delta(ctx);
+                            state.mouse_motion_event(ctx, position.x, position.y, delta.x, delta.y);
+                        }
+                        TouchPhase::Ended | TouchPhase::Cancelled => state.mouse_button_up_event(
+                            ctx,
+                            MouseButton::Left,
+                            position.x,
+                            position.y,
+                        ),
                     }
                 }
-                Event::MainEventsCleared => {
-                    // Handle gamepad events if necessary.
-                    if ctx.conf.modules.gamepad {
-                        while let Some(gilrs::Event { id, event, .. }) = ctx.gamepad_context.next_event() {
-                            match event {
-                                gilrs::EventType::ButtonPressed(button, _) => {
-                                    state.gamepad_button_down_event(ctx, button, GamepadId(id));
-                                }
-                                gilrs::EventType::ButtonReleased(button, _) => {
-                                    state.gamepad_button_up_event(ctx, button, GamepadId(id));
-                                }
-                                gilrs::EventType::AxisChanged(axis, value, _) => {
-                                    state.gamepad_axis_event(ctx, axis, value, GamepadId(id));
-                                }
-                                _ => {}
-                            }
+            }
+            _ => (),
+        },
+        Event::MainEventsCleared => {
+            ctx.timer_context.tick();
+            if ctx.conf.modules.gamepad {
+                while let Some(gilrs::Event { id, event, .. }) = ctx.gamepad_context.next_event() {
+                    match event {
+                        gilrs::EventType::ButtonPressed(button, _) => {
+                            state.gamepad_button_down_event(ctx, button, GamepadId(id));
                         }
+                        gilrs::EventType::ButtonReleased(button, _) => {
+                            state.gamepad_button_up_event(ctx, button, GamepadId(id));
+                        }
+                        gilrs::EventType::AxisChanged(axis, value, _) => {
+                            state.gamepad_axis_event(ctx, axis, value, GamepadId(id));
+                        }
+                        _ => {}
                     }
-                    state.update(ctx).unwrap();
-                    window(ctx).window().request_redraw();
                 }
-                _ => {}
+            }
+            while let Some(gesture) = touch::next_gesture(ctx) {
+                state.gesture_event(ctx, gesture);
+            }
+            state.update(ctx)?;
+            state.draw(ctx)?;
+        }
+        Event::Suspended => {
+            state.suspend_event(ctx);
+        }
+        Event::Resumed => {
+            if graphics::is_context_lost(ctx) {
+                if let Err(e) = graphics::recreate_context(ctx, event_loop) {
+                    error!("Couldn't recreate the graphics context: {}", e);
+                }
+            }
+            state.resume_event(ctx);
+        }
+        _ => (),
+    }
+    Ok(())
+}
+
+/*
+/// Runs the game's main loop, calling event callbacks on the given state
+/// object as events occur.
+///
+/// It does not try to do any type of framerate limiting.  See the
+/// documentation for the [`timer`](../timer/index.html) module for more info.
+pub fn run<S>(ctx: &'static mut Context, events_loop: &mut EventLoop<()>, state: &'static mut S) -> GameResult
+    where
+        S: EventHandler,
+{
+    // If you are writing your own event loop, make sure you call
+    // `handle_event()`, or at least `ctx.process_event()` and
+    // `timer_context.tick()`.  These update ggez's internal state
+    // however necessary.
+    while ctx.continuing {
+        events_loop.run_return(|event, target, _flow| {
+            if let Err(e) = handle_event(ctx, target, state, &event) {
+                error!("Error in the event loop: {}", e);
+                quit(ctx);
             }
         });
     }
 
     Ok(())
 }*/
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graphics;
+    use crate::graphics::Rect;
+    use crate::ContextBuilder;
+
+    /// Records the callbacks that were called.
+    #[derive(Debug, Default)]
+    struct Recorder {
+        calls: Vec<String>,
+    }
+
+    impl EventHandler for Recorder {
+        fn update(&mut self, _ctx: &mut Context) -> GameResult {
+            self.calls.push("update".to_string());
+            Ok(())
+        }
+
+        fn draw(&mut self, _ctx: &mut Context) -> GameResult {
+            self.calls.push("draw".to_string());
+            Ok(())
+        }
+
+        fn resize_event(&mut self, _ctx: &mut Context, width: f32, height: f32) {
+            self.calls.push(format!("resize {}x{}", width, height));
+        }
+
+        fn scale_factor_changed_event(&mut self, _ctx: &mut Context, scale_factor: f64) {
+            self.calls.push(format!("scale factor {}", scale_factor));
+        }
+
+        fn window_event(&mut self, _ctx: &mut Context, _window: WindowId, event: &WindowEvent) {
+            self.calls.push(format!("window event {:?}", event));
+        }
+
+        fn file_dropped_event(&mut self, _ctx: &mut Context, path: &Path) {
+            self.calls.push(format!("dropped {}", path.display()));
+        }
+
+        fn file_hovered_event(&mut self, _ctx: &mut Context, path: &Path) {
+            self.calls.push(format!("hovered {}", path.display()));
+        }
+
+        fn file_hover_cancelled_event(&mut self, _ctx: &mut Context) {
+            self.calls.push("hover cancelled".to_string());
+        }
+
+        fn window_moved_event(&mut self, _ctx: &mut Context, x: i32, y: i32) {
+            self.calls.push(format!("moved {},{}", x, y));
+        }
+
+        fn minimized_event(&mut self, _ctx: &mut Context, minimized: bool) {
+            self.calls.push(format!("minimized {}", minimized));
+        }
+
+        fn mouse_button_down_event(
+            &mut self,
+            _ctx: &mut Context,
+            button: MouseButton,
+            _x: f32,
+            _y: f32,
+        ) {
+            self.calls.push(format!("mouse down {:?}", button));
+        }
+
+        fn mouse_button_up_event(
+            &mut self,
+            _ctx: &mut Context,
+            button: MouseButton,
+            _x: f32,
+            _y: f32,
+        ) {
+            self.calls.push(format!("mouse up {:?}", button));
+        }
+
+        fn mouse_motion_event(&mut self, _ctx: &mut Context, _x: f32, _y: f32, _dx: f32, _dy: f32) {
+            self.calls.push("mouse motion".to_string());
+        }
+
+        fn touch_event(&mut self, _ctx: &mut Context, touch: Touch) {
+            self.calls
+                .push(format!("touch {} {:?}", touch.id, touch.phase));
+        }
+
+        fn gesture_event(&mut self, _ctx: &mut Context, gesture: Gesture) {
+            let name = match gesture {
+                Gesture::Tap { .. } => "tap",
+                Gesture::LongPress { .. } => "long press",
+                Gesture::Swipe { .. } => "swipe",
+                Gesture::Pinch { .. } => "pinch",
+                Gesture::Pan { .. } => "pan",
+            };
+            self.calls.push(format!("gesture {}", name));
+        }
+
+        fn suspend_event(&mut self, _ctx: &mut Context) {
+            self.calls.push("suspend".to_string());
+        }
+
+        fn resume_event(&mut self, ctx: &mut Context) {
+            let lost = graphics::is_context_lost(ctx);
+            self.calls.push(format!("resume, context lost {}", lost));
+        }
+    }
+
+    /// Test threads aren't the main thread, which winit wants by default.
+    fn event_loop() -> EventLoop<()> {
+        #[cfg(any(
+            target_os = "linux",
+            target_os = "dragonfly",
+            target_os = "freebsd",
+            target_os = "netbsd",
+            target_os = "openbsd"
+        ))]
+        {
+            use winit::platform::unix::EventLoopExtUnix;
+            EventLoop::new_any_thread()
+        }
+        #[cfg(windows)]
+        {
+            use winit::platform::windows::EventLoopExtWindows;
+            EventLoop::new_any_thread()
+        }
+        #[cfg(not(any(
+            target_os = "linux",
+            target_os = "dragonfly",
+            target_os = "freebsd",
+            target_os = "netbsd",
+            target_os = "openbsd",
+            windows
+        )))]
+        EventLoop::new()
+    }
+
+    #[test]
+    fn test_handle_window_events() {
+        let event_loop = event_loop();
+        let ctx = &mut ContextBuilder::new("ggez_unit_tests")
+            .build(&event_loop)
+            .unwrap();
+        let state = &mut Recorder::default();
+        let window_id = graphics::main_window(ctx);
+
+        let custom = Rect::new(0.0, 0.0, 320.0, 240.0);
+        graphics::set_screen_coordinates(ctx, custom).unwrap();
+        let resized = Event::WindowEvent {
+            window_id,
+            event: WindowEvent::Resized(winit::dpi::PhysicalSize::new(400, 300)),
+        };
+        handle_event(ctx, &event_loop, state, &resized).unwrap();
+        let mut size = winit::dpi::PhysicalSize::new(800, 600);
+        let scale_factor_changed = Event::WindowEvent {
+            window_id,
+            event: WindowEvent::ScaleFactorChanged {
+                scale_factor: 2.0,
+                new_inner_size: &mut size,
+            },
+        };
+        handle_event(ctx, &event_loop, state, &scale_factor_changed).unwrap();
+        handle_event(ctx, &event_loop, state, &Event::MainEventsCleared).unwrap();
+
+        assert_eq!(
+            state.calls,
+            vec!["resize 400x300", "scale factor 2", "update", "draw"]
+        );
+        // Custom screen coordinates survive the window changing.
+        assert_eq!(graphics::screen_coordinates(ctx), custom);
+    }
+
+    #[test]
+    fn test_handle_other_window_events() {
+        let event_loop = event_loop();
+        let ctx = &mut ContextBuilder::new("ggez_unit_tests")
+            .build(&event_loop)
+            .unwrap();
+        let state = &mut Recorder::default();
+        let mode = crate::conf::WindowMode::default();
+        let other = graphics::create_window(ctx, &event_loop, "other", mode).unwrap();
+
+        // Closing another window doesn't quit.
+        let close = |window_id| Event::WindowEvent {
+            window_id,
+            event: WindowEvent::CloseRequested,
+        };
+        handle_event(ctx, &event_loop, state, &close(other)).unwrap();
+        assert_eq!(state.calls, vec!["window event CloseRequested"]);
+        assert!(ctx.continuing);
+
+        handle_event(ctx, &event_loop, state, &close(graphics::main_window(ctx))).unwrap();
+        assert_eq!(state.calls.len(), 1);
+        assert!(!ctx.continuing);
+    }
+
+    #[test]
+    fn test_focus_of_several_windows() {
+        let event_loop = event_loop();
+        let ctx = &mut ContextBuilder::new("ggez_unit_tests")
+            .build(&event_loop)
+            .unwrap();
+        let state = &mut Recorder::default();
+        let mode = crate::conf::WindowMode::default();
+        let main = graphics::main_window(ctx);
+        let other = graphics::create_window(ctx, &event_loop, "other", mode).unwrap();
+        let focus = |window_id, focused| Event::WindowEvent {
+            window_id,
+            event: WindowEvent::Focused(focused),
+        };
+
+        // Moving the focus to another window of the game, in either
+        // order, keeps the game focused.
+        handle_event(ctx, &event_loop, state, &focus(main, true)).unwrap();
+        handle_event(ctx, &event_loop, state, &focus(other, true)).unwrap();
+        handle_event(ctx, &event_loop, state, &focus(main, false)).unwrap();
+        assert!(ctx.timer_context.focused);
+        handle_event(ctx, &event_loop, state, &focus(other, false)).unwrap();
+        assert!(!ctx.timer_context.focused);
+
+        handle_event(ctx, &event_loop, state, &focus(other, true)).unwrap();
+        graphics::close_window(ctx, other).unwrap();
+        assert!(!ctx.timer_context.focused);
+    }
+
+    #[test]
+    fn test_handle_suspend_and_resume() {
+        let event_loop = event_loop();
+        let ctx = &mut ContextBuilder::new("ggez_unit_tests")
+            .build(&event_loop)
+            .unwrap();
+        let state = &mut Recorder::default();
+
+        handle_event(ctx, &event_loop, state, &Event::Suspended).unwrap();
+        assert!(graphics::is_suspended(ctx));
+        handle_event(ctx, &event_loop, state, &Event::Resumed).unwrap();
+        assert!(!graphics::is_suspended(ctx));
+        assert_eq!(graphics::context_generation(ctx), 0);
+
+        // A context lost meanwhile is recreated before the game hears
+        // of the resume.
+        handle_event(ctx, &event_loop, state, &Event::Suspended).unwrap();
+        ctx.gfx_context.lifecycle.context_lost = true;
+        handle_event(ctx, &event_loop, state, &Event::Resumed).unwrap();
+        assert_eq!(graphics::context_generation(ctx), 1);
+        assert_eq!(
+            state.calls,
+            vec![
+                "suspend",
+                "resume, context lost false",
+                "suspend",
+                "resume, context lost false"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_handle_file_and_window_events() {
+        let event_loop = event_loop();
+        let ctx = &mut ContextBuilder::new("ggez_unit_tests")
+            .build(&event_loop)
+            .unwrap();
+        let state = &mut Recorder::default();
+        let window_id = graphics::main_window(ctx);
+        let window_event = |event| Event::WindowEvent { window_id, event };
+        let path = std::path::PathBuf::from("level.txt");
+
+        let events = vec![
+            WindowEvent::HoveredFile(path.clone()),
+            WindowEvent::HoveredFileCancelled,
+            WindowEvent::DroppedFile(path),
+            WindowEvent::Moved(winit::dpi::PhysicalPosition::new(10, 20)),
+            // Minimizing resizes the window to nothing.
+            WindowEvent::Resized(winit::dpi::PhysicalSize::new(0, 0)),
+            WindowEvent::Resized(winit::dpi::PhysicalSize::new(0, 0)),
+            WindowEvent::Resized(winit::dpi::PhysicalSize::new(400, 300)),
+        ];
+        for event in events {
+            handle_event(ctx, &event_loop, state, &window_event(event)).unwrap();
+        }
+
+        assert_eq!(
+            state.calls,
+            vec![
+                "hovered level.txt",
+                "hover cancelled",
+                "dropped level.txt",
+                "moved 10,20",
+                "resize 0x0",
+                "minimized true",
+                "resize 0x0",
+                "resize 400x300",
+                "minimized false"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_handle_touch_events() {
+        use crate::input::touch;
+
+        let event_loop = event_loop();
+        let ctx = &mut ContextBuilder::new("ggez_unit_tests")
+            .build(&event_loop)
+            .unwrap();
+        let state = &mut Recorder::default();
+        let window_id = graphics::main_window(ctx);
+        touch::set_mouse_emulation(ctx, true);
+
+        // Safe since the id is only compared, never used to reach a
+        // device.
+        #[allow(unsafe_code)]
+        let device_id = unsafe { winit::event::DeviceId::dummy() };
+        let touch_event = |id, x, phase| Event::WindowEvent {
+            window_id,
+            event: WindowEvent::Touch(winit::event::Touch {
+                device_id,
+                phase,
+                location: winit::dpi::PhysicalPosition::new(x, 10.0),
+                force: None,
+                id,
+            }),
+        };
+        let events = vec![
+            touch_event(0, 10.0, TouchPhase::Started),
+            touch_event(1, 50.0, TouchPhase::Started),
+            touch_event(0, 12.0, TouchPhase::Moved),
+            touch_event(1, 50.0, TouchPhase::Ended),
+            touch_event(0, 12.0, TouchPhase::Ended),
+        ];
+        for event in &events {
+            handle_event(ctx, &event_loop, state, event).unwrap();
+        }
+        assert!(!crate::input::mouse::button_pressed(ctx, MouseButton::Left));
+
+        // A tap with a single finger is passed on before the update.
+        handle_event(ctx, &event_loop, state, &events[0]).unwrap();
+        handle_event(ctx, &event_loop, state, &events[4]).unwrap();
+        handle_event(ctx, &event_loop, state, &Event::MainEventsCleared).unwrap();
+
+        assert_eq!(
+            state.calls,
+            vec![
+                "touch 0 Started",
+                "mouse down Left",
+                "touch 1 Started",
+                "touch 0 Moved",
+                "mouse motion",
+                "touch 1 Ended",
+                "touch 0 Ended",
+                "mouse up Left",
+                "touch 0 Started",
+                "mouse down Left",
+                "touch 0 Ended",
+                "mouse up Left",
+                "gesture tap",
+                "update",
+                "draw"
+            ]
+        );
+    }
+}
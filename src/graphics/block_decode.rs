@@ -0,0 +1,848 @@
+//! CPU decompression of block compressed textures, for when the GPU
+//! can't sample them directly.
+//!
+//! Covers BC1 to BC5, BC7 and the ETC2/EAC family.  ASTC and BC6H have
+//! no decoder here, so KTX files in those formats only load on GPUs that
+//! support them.
+
+use crate::error::{GameError, GameResult};
+use crate::graphics::CompressedFormat;
+
+/// Returns `true` if `format` can be decompressed by
+/// [`decode()`](fn.decode.html).
+pub(crate) fn can_decode(format: CompressedFormat) -> bool {
+    match format {
+        CompressedFormat::Bc6h | CompressedFormat::Astc(_, _) => false,
+        _ => true,
+    }
+}
+
+/// Decompresses one mipmap level into RGBA bytes, row by row from the
+/// top.  Single and two channel formats come out the way the GPU would
+/// sample them, as red, or red and green, with full alpha.
+pub(crate) fn decode(
+    format: CompressedFormat,
+    width: u16,
+    height: u16,
+    data: &[u8],
+) -> GameResult<Vec<u8>> {
+    if !can_decode(format) {
+        return Err(GameError::ResourceLoadError(format!(
+            "The GPU doesn't support {:?} textures, and they can't be decompressed on the CPU",
+            format
+        )));
+    }
+    let (width, height) = (usize::from(width), usize::from(height));
+    let blocks_x = (width + 3) / 4;
+    let blocks_y = (height + 3) / 4;
+    let block_bytes = format.block_bytes();
+    if data.len() < blocks_x * blocks_y * block_bytes {
+        return Err(GameError::ResourceLoadError(format!(
+            "Not enough data for a {}x{} {:?} texture",
+            width, height, format
+        )));
+    }
+
+    let mut rgba = vec![0; width * height * 4];
+    for (i, block) in data
+        .chunks_exact(block_bytes)
+        .take(blocks_x * blocks_y)
+        .enumerate()
+    {
+        let pixels = decode_block(format, block);
+        let (bx, by) = (i % blocks_x * 4, i / blocks_x * 4);
+        for y in 0..4 {
+            for x in 0..4 {
+                if bx + x < width && by + y < height {
+                    let offset = ((by + y) * width + bx + x) * 4;
+                    rgba[offset..offset + 4].copy_from_slice(&pixels[y * 4 + x]);
+                }
+            }
+        }
+    }
+    Ok(rgba)
+}
+
+/// Decodes one block into its 16 pixels, row by row.
+fn decode_block(format: CompressedFormat, block: &[u8]) -> [[u8; 4]; 16] {
+    match format {
+        CompressedFormat::Bc1Rgb => decode_bc1(block, false, false),
+        CompressedFormat::Bc1Rgba => decode_bc1(block, false, true),
+        CompressedFormat::Bc2 => {
+            let mut pixels = decode_bc1(&block[8..], true, false);
+            for (i, pixel) in pixels.iter_mut().enumerate() {
+                let alpha = block[i / 2] >> (4 * (i % 2)) & 0x0f;
+                pixel[3] = alpha * 17;
+            }
+            pixels
+        }
+        CompressedFormat::Bc3 => {
+            let mut pixels = decode_bc1(&block[8..], true, false);
+            let alpha = decode_bc4(block);
+            for (pixel, &alpha) in pixels.iter_mut().zip(alpha.iter()) {
+                pixel[3] = alpha;
+            }
+            pixels
+        }
+        CompressedFormat::Bc4 => {
+            let red = decode_bc4(block);
+            let mut pixels = [[0, 0, 0, 255]; 16];
+            for (pixel, &red) in pixels.iter_mut().zip(red.iter()) {
+                pixel[0] = red;
+            }
+            pixels
+        }
+        CompressedFormat::Bc5 => {
+            let red = decode_bc4(block);
+            let green = decode_bc4(&block[8..]);
+            let mut pixels = [[0, 0, 0, 255]; 16];
+            for (i, pixel) in pixels.iter_mut().enumerate() {
+                pixel[0] = red[i];
+                pixel[1] = green[i];
+            }
+            pixels
+        }
+        CompressedFormat::Bc7 => decode_bc7(block),
+        CompressedFormat::Etc2Rgb => decode_etc2(block, false),
+        CompressedFormat::Etc2RgbA1 => decode_etc2(block, true),
+        CompressedFormat::Etc2Rgba => {
+            let mut pixels = decode_etc2(&block[8..], false);
+            let alpha = decode_eac(block);
+            for (pixel, &alpha) in pixels.iter_mut().zip(alpha.iter()) {
+                pixel[3] = alpha;
+            }
+            pixels
+        }
+        CompressedFormat::EacR11 => {
+            let red = decode_eac(block);
+            let mut pixels = [[0, 0, 0, 255]; 16];
+            for (pixel, &red) in pixels.iter_mut().zip(red.iter()) {
+                pixel[0] = red;
+            }
+            pixels
+        }
+        CompressedFormat::EacRg11 => {
+            let red = decode_eac(block);
+            let green = decode_eac(&block[8..]);
+            let mut pixels = [[0, 0, 0, 255]; 16];
+            for (i, pixel) in pixels.iter_mut().enumerate() {
+                pixel[0] = red[i];
+                pixel[1] = green[i];
+            }
+            pixels
+        }
+        CompressedFormat::Bc6h | CompressedFormat::Astc(_, _) => {
+            unreachable!("Checked by can_decode()")
+        }
+    }
+}
+
+// BC1 to BC5
+
+fn rgb565(color: u16) -> [u8; 4] {
+    let r = (color >> 11 & 0x1f) as u8;
+    let g = (color >> 5 & 0x3f) as u8;
+    let b = (color & 0x1f) as u8;
+    [r << 3 | r >> 2, g << 2 | g >> 4, b << 3 | b >> 2, 255]
+}
+
+/// Decodes a BC1 color block.  BC2 and BC3 color blocks always use four
+/// colors; BC1 blocks switch to three colors and black when the first
+/// endpoint isn't larger, and that black is transparent if `alpha`.
+fn decode_bc1(block: &[u8], always_four_colors: bool, alpha: bool) -> [[u8; 4]; 16] {
+    let c0 = u16::from_le_bytes([block[0], block[1]]);
+    let c1 = u16::from_le_bytes([block[2], block[3]]);
+    let (e0, e1) = (rgb565(c0), rgb565(c1));
+    let mix = |a: u8, b: u8, wa: u16, wb: u16| {
+        ((u16::from(a) * wa + u16::from(b) * wb) / (wa + wb)) as u8
+    };
+    let lerp = |w0: u16, w1: u16| {
+        [
+            mix(e0[0], e1[0], w0, w1),
+            mix(e0[1], e1[1], w0, w1),
+            mix(e0[2], e1[2], w0, w1),
+            255,
+        ]
+    };
+    let palette = if always_four_colors || c0 > c1 {
+        [e0, e1, lerp(2, 1), lerp(1, 2)]
+    } else if alpha {
+        [e0, e1, lerp(1, 1), [0, 0, 0, 0]]
+    } else {
+        [e0, e1, lerp(1, 1), [0, 0, 0, 255]]
+    };
+    let indices = u32::from_le_bytes([block[4], block[5], block[6], block[7]]);
+    let mut pixels = [[0; 4]; 16];
+    for (i, pixel) in pixels.iter_mut().enumerate() {
+        *pixel = palette[(indices >> (2 * i) & 0x03) as usize];
+    }
+    pixels
+}
+
+/// Decodes a BC4 block, which is also the alpha block of BC3.
+fn decode_bc4(block: &[u8]) -> [u8; 16] {
+    let (a0, a1) = (u32::from(block[0]), u32::from(block[1]));
+    let mut palette = [0u8; 8];
+    palette[0] = a0 as u8;
+    palette[1] = a1 as u8;
+    if a0 > a1 {
+        for (i, value) in (2..).zip(palette.iter_mut().skip(2)) {
+            *value = (((8 - i) * a0 + (i - 1) * a1) / 7) as u8;
+        }
+    } else {
+        for (i, value) in (2..).zip(palette.iter_mut().take(6).skip(2)) {
+            *value = (((6 - i) * a0 + (i - 1) * a1) / 5) as u8;
+        }
+        palette[6] = 0;
+        palette[7] = 255;
+    }
+    let mut index_bytes = [0u8; 8];
+    index_bytes[..6].copy_from_slice(&block[2..8]);
+    let indices = u64::from_le_bytes(index_bytes);
+    let mut values = [0; 16];
+    for (i, value) in values.iter_mut().enumerate() {
+        *value = palette[(indices >> (3 * i) & 0x07) as usize];
+    }
+    values
+}
+
+// BC7
+
+/// Per mode: subsets, partition bits, rotation bits, index selection
+/// bits, color bits, alpha bits, endpoint p-bits, shared p-bits,
+/// index bits and secondary index bits.
+const BC7_MODES: [[u8; 10]; 8] = [
+    [3, 4, 0, 0, 4, 0, 1, 0, 3, 0],
+    [2, 6, 0, 0, 6, 0, 0, 1, 3, 0],
+    [3, 6, 0, 0, 5, 0, 0, 0, 2, 0],
+    [2, 6, 0, 0, 7, 0, 1, 0, 2, 0],
+    [1, 0, 2, 1, 5, 6, 0, 0, 2, 3],
+    [1, 0, 2, 0, 7, 8, 0, 0, 2, 2],
+    [1, 0, 0, 0, 7, 7, 1, 0, 4, 0],
+    [2, 6, 0, 0, 5, 5, 1, 0, 2, 0],
+];
+
+const BC7_WEIGHTS_2: [u32; 4] = [0, 21, 43, 64];
+const BC7_WEIGHTS_3: [u32; 8] = [0, 9, 18, 27, 37, 46, 55, 64];
+const BC7_WEIGHTS_4: [u32; 16] = [0, 4, 9, 13, 17, 21, 26, 30, 34, 38, 43, 47, 51, 55, 60, 64];
+
+/// The two subset partitions, one bit per pixel for the subset.
+const BC7_PARTITIONS_2: [u16; 64] = [
+    0xcccc, 0x8888, 0xeeee, 0xecc8, 0xc880, 0xfeec, 0xfec8, 0xec80, 0xc800, 0xffec, 0xfe80, 0xe800,
+    0xffe8, 0xff00, 0xfff0, 0xf000, 0xf710, 0x008e, 0x7100, 0x08ce, 0x008c, 0x7310, 0x3100, 0x8cce,
+    0x088c, 0x3110, 0x6666, 0x366c, 0x17e8, 0x0ff0, 0x718e, 0x399c, 0xaaaa, 0xf0f0, 0x5a5a, 0x33cc,
+    0x3c3c, 0x55aa, 0x9696, 0xa55a, 0x73ce, 0x13c8, 0x324c, 0x3bdc, 0x6996, 0xc33c, 0x9966, 0x0660,
+    0x0272, 0x04e4, 0x4e40, 0x2720, 0xc936, 0x936c, 0x39c6, 0x639c, 0x9336, 0x9cc6, 0x817e, 0xe718,
+    0xccf0, 0x0fcc, 0x7744, 0xee22,
+];
+
+/// The three subset partitions, one entry per pixel.
+const BC7_PARTITIONS_3: [[u8; 16]; 64] = [
+    [0, 0, 1, 1, 0, 0, 1, 1, 0, 2, 2, 1, 2, 2, 2, 2],
+    [0, 0, 0, 1, 0, 0, 1, 1, 2, 2, 1, 1, 2, 2, 2, 1],
+    [0, 0, 0, 0, 2, 0, 0, 1, 2, 2, 1, 1, 2, 2, 1, 1],
+    [0, 2, 2, 2, 0, 0, 2, 2, 0, 0, 1, 1, 0, 1, 1, 1],
+    [0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 2, 2, 1, 1, 2, 2],
+    [0, 0, 1, 1, 0, 0, 1, 1, 0, 0, 2, 2, 0, 0, 2, 2],
+    [0, 0, 2, 2, 0, 0, 2, 2, 1, 1, 1, 1, 1, 1, 1, 1],
+    [0, 0, 1, 1, 0, 0, 1, 1, 2, 2, 1, 1, 2, 2, 1, 1],
+    [0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2],
+    [0, 0, 0, 0, 1, 1, 1, 1, 1, 1, 1, 1, 2, 2, 2, 2],
+    [0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 2, 2, 2, 2],
+    [0, 0, 1, 2, 0, 0, 1, 2, 0, 0, 1, 2, 0, 0, 1, 2],
+    [0, 1, 1, 2, 0, 1, 1, 2, 0, 1, 1, 2, 0, 1, 1, 2],
+    [0, 1, 2, 2, 0, 1, 2, 2, 0, 1, 2, 2, 0, 1, 2, 2],
+    [0, 0, 1, 1, 0, 1, 1, 2, 1, 1, 2, 2, 1, 2, 2, 2],
+    [0, 0, 1, 1, 2, 0, 0, 1, 2, 2, 0, 0, 2, 2, 2, 0],
+    [0, 0, 0, 1, 0, 0, 1, 1, 0, 1, 1, 2, 1, 1, 2, 2],
+    [0, 1, 1, 1, 0, 0, 1, 1, 2, 0, 0, 1, 2, 2, 0, 0],
+    [0, 0, 0, 0, 1, 1, 2, 2, 1, 1, 2, 2, 1, 1, 2, 2],
+    [0, 0, 2, 2, 0, 0, 2, 2, 0, 0, 2, 2, 1, 1, 1, 1],
+    [0, 1, 1, 1, 0, 1, 1, 1, 0, 2, 2, 2, 0, 2, 2, 2],
+    [0, 0, 0, 1, 0, 0, 0, 1, 2, 2, 2, 1, 2, 2, 2, 1],
+    [0, 0, 0, 0, 0, 0, 1, 1, 0, 1, 2, 2, 0, 1, 2, 2],
+    [0, 0, 0, 0, 1, 1, 0, 0, 2, 2, 1, 0, 2, 2, 1, 0],
+    [0, 1, 2, 2, 0, 1, 2, 2, 0, 0, 1, 1, 0, 0, 0, 0],
+    [0, 0, 1, 2, 0, 0, 1, 2, 1, 1, 2, 2, 2, 2, 2, 2],
+    [0, 1, 1, 0, 1, 2, 2, 1, 1, 2, 2, 1, 0, 1, 1, 0],
+    [0, 0, 0, 0, 0, 1, 1, 0, 1, 2, 2, 1, 1, 2, 2, 1],
+    [0, 0, 2, 2, 1, 1, 0, 2, 1, 1, 0, 2, 0, 0, 2, 2],
+    [0, 1, 1, 0, 0, 1, 1, 0, 2, 0, 0, 2, 2, 2, 2, 2],
+    [0, 0, 1, 1, 0, 1, 2, 2, 0, 1, 2, 2, 0, 0, 1, 1],
+    [0, 0, 0, 0, 2, 0, 0, 0, 2, 2, 1, 1, 2, 2, 2, 1],
+    [0, 0, 0, 0, 0, 0, 0, 2, 1, 1, 2, 2, 1, 2, 2, 2],
+    [0, 2, 2, 2, 0, 0, 2, 2, 0, 0, 1, 2, 0, 0, 1, 1],
+    [0, 0, 1, 1, 0, 0, 1, 2, 0, 0, 2, 2, 0, 2, 2, 2],
+    [0, 1, 2, 0, 0, 1, 2, 0, 0, 1, 2, 0, 0, 1, 2, 0],
+    [0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 0, 0, 0, 0],
+    [0, 1, 2, 0, 1, 2, 0, 1, 2, 0, 1, 2, 0, 1, 2, 0],
+    [0, 1, 2, 0, 2, 0, 1, 2, 1, 2, 0, 1, 0, 1, 2, 0],
+    [0, 0, 1, 1, 2, 2, 0, 0, 1, 1, 2, 2, 0, 0, 1, 1],
+    [0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 0, 0, 0, 0, 1, 1],
+    [0, 1, 0, 1, 0, 1, 0, 1, 2, 2, 2, 2, 2, 2, 2, 2],
+    [0, 0, 0, 0, 0, 0, 0, 0, 2, 1, 2, 1, 2, 1, 2, 1],
+    [0, 0, 2, 2, 1, 1, 2, 2, 0, 0, 2, 2, 1, 1, 2, 2],
+    [0, 0, 2, 2, 0, 0, 1, 1, 0, 0, 2, 2, 0, 0, 1, 1],
+    [0, 2, 2, 0, 1, 2, 2, 1, 0, 2, 2, 0, 1, 2, 2, 1],
+    [0, 1, 0, 1, 2, 2, 2, 2, 2, 2, 2, 2, 0, 1, 0, 1],
+    [0, 0, 0, 0, 2, 1, 2, 1, 2, 1, 2, 1, 2, 1, 2, 1],
+    [0, 1, 0, 1, 0, 1, 0, 1, 0, 1, 0, 1, 2, 2, 2, 2],
+    [0, 2, 2, 2, 0, 1, 1, 1, 0, 2, 2, 2, 0, 1, 1, 1],
+    [0, 0, 0, 2, 1, 1, 1, 2, 0, 0, 0, 2, 1, 1, 1, 2],
+    [0, 0, 0, 0, 2, 1, 1, 2, 2, 1, 1, 2, 2, 1, 1, 2],
+    [0, 2, 2, 2, 0, 1, 1, 1, 0, 1, 1, 1, 0, 2, 2, 2],
+    [0, 0, 0, 2, 1, 1, 1, 2, 1, 1, 1, 2, 0, 0, 0, 2],
+    [0, 1, 1, 0, 0, 1, 1, 0, 0, 1, 1, 0, 2, 2, 2, 2],
+    [0, 0, 0, 0, 0, 0, 0, 0, 2, 1, 1, 2, 2, 1, 1, 2],
+    [0, 1, 1, 0, 0, 1, 1, 0, 2, 2, 2, 2, 2, 2, 2, 2],
+    [0, 0, 2, 2, 0, 0, 1, 1, 0, 0, 1, 1, 0, 0, 2, 2],
+    [0, 0, 2, 2, 1, 1, 2, 2, 1, 1, 2, 2, 0, 0, 2, 2],
+    [0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 2, 1, 1, 2],
+    [0, 0, 0, 2, 0, 0, 0, 1, 0, 0, 0, 2, 0, 0, 0, 1],
+    [0, 2, 2, 2, 1, 2, 2, 2, 0, 2, 2, 2, 1, 2, 2, 2],
+    [0, 1, 0, 1, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2],
+    [0, 1, 1, 1, 2, 0, 1, 1, 2, 2, 0, 1, 2, 2, 2, 0],
+];
+
+/// The pixel of the second subset whose index is stored with one bit
+/// less, for two subset partitions.
+const BC7_ANCHORS_2: [u8; 64] = [
+    15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 2, 8, 2, 2, 8, 8, 15, 2, 8,
+    2, 2, 8, 8, 2, 2, 15, 15, 6, 8, 2, 8, 15, 15, 2, 8, 2, 2, 2, 15, 15, 6, 6, 2, 6, 8, 15, 15, 2,
+    2, 15, 15, 15, 15, 15, 2, 2, 15,
+];
+
+/// The anchor pixels of the second and third subsets, for three subset
+/// partitions.
+const BC7_ANCHORS_3: [[u8; 64]; 2] = [
+    [
+        3, 3, 15, 15, 8, 3, 15, 15, 8, 8, 6, 6, 6, 5, 3, 3, 3, 3, 8, 15, 3, 3, 6, 10, 5, 8, 8, 6,
+        8, 5, 15, 15, 8, 15, 3, 5, 6, 10, 8, 15, 15, 3, 15, 5, 15, 15, 15, 15, 3, 15, 5, 5, 5, 8,
+        5, 10, 5, 10, 8, 13, 15, 12, 3, 3,
+    ],
+    [
+        15, 8, 8, 3, 15, 15, 3, 8, 15, 15, 15, 15, 15, 15, 15, 8, 15, 8, 15, 3, 15, 8, 15, 8, 3,
+        15, 6, 10, 15, 15, 10, 8, 15, 3, 15, 10, 10, 8, 9, 10, 6, 15, 8, 15, 3, 6, 6, 8, 15, 3, 15,
+        15, 15, 15, 15, 15, 15, 15, 15, 15, 3, 15, 15, 8,
+    ],
+];
+
+/// Reads little-endian bit fields from a 128-bit block.
+struct BitReader {
+    bits: u128,
+}
+
+impl BitReader {
+    fn read(&mut self, count: u8) -> u8 {
+        let value = (self.bits & ((1 << count) - 1)) as u8;
+        self.bits >>= count;
+        value
+    }
+}
+
+/// The subset of pixel `i` in the given partition.
+fn bc7_subset(subsets: u8, partition: usize, i: usize) -> usize {
+    match subsets {
+        2 => (BC7_PARTITIONS_2[partition] >> i & 1) as usize,
+        3 => usize::from(BC7_PARTITIONS_3[partition][i]),
+        _ => 0,
+    }
+}
+
+/// Returns `true` if pixel `i` stores its index with one bit less.
+fn bc7_is_anchor(subsets: u8, partition: usize, i: usize) -> bool {
+    i == 0
+        || match subsets {
+            2 => usize::from(BC7_ANCHORS_2[partition]) == i,
+            3 => {
+                usize::from(BC7_ANCHORS_3[0][partition]) == i
+                    || usize::from(BC7_ANCHORS_3[1][partition]) == i
+            }
+            _ => false,
+        }
+}
+
+fn bc7_interpolate(e0: u8, e1: u8, weight: u32) -> u8 {
+    (((64 - weight) * u32::from(e0) + weight * u32::from(e1) + 32) >> 6) as u8
+}
+
+fn bc7_weights(bits: u8) -> &'static [u32] {
+    match bits {
+        2 => &BC7_WEIGHTS_2,
+        3 => &BC7_WEIGHTS_3,
+        _ => &BC7_WEIGHTS_4,
+    }
+}
+
+fn decode_bc7(block: &[u8]) -> [[u8; 4]; 16] {
+    let mut raw = [0u8; 16];
+    raw.copy_from_slice(&block[..16]);
+    let mut reader = BitReader {
+        bits: u128::from_le_bytes(raw),
+    };
+    let mode = match (0..8).find(|_| reader.read(1) == 1) {
+        Some(mode) => mode,
+        // Reserved mode; decoders output transparent black.
+        None => return [[0; 4]; 16],
+    };
+    let [subsets, partition_bits, rotation_bits, selection_bits, color_bits, alpha_bits, endpoint_pbits, shared_pbits, index_bits, index_bits_2] =
+        BC7_MODES[mode];
+    let partition = usize::from(reader.read(partition_bits));
+    let rotation = reader.read(rotation_bits);
+    let selection = reader.read(selection_bits);
+
+    let endpoint_count = usize::from(subsets) * 2;
+    let mut endpoints = [[0u8, 0, 0, 255]; 6];
+    for channel in 0..3 {
+        for endpoint in endpoints.iter_mut().take(endpoint_count) {
+            endpoint[channel] = reader.read(color_bits);
+        }
+    }
+    if alpha_bits > 0 {
+        for endpoint in endpoints.iter_mut().take(endpoint_count) {
+            endpoint[3] = reader.read(alpha_bits);
+        }
+    }
+
+    // P-bits add one more least significant bit to every channel.
+    let mut pbits = [0u8; 6];
+    if endpoint_pbits > 0 {
+        for pbit in pbits.iter_mut().take(endpoint_count) {
+            *pbit = reader.read(1);
+        }
+    } else if shared_pbits > 0 {
+        for subset in 0..usize::from(subsets) {
+            let pbit = reader.read(1);
+            pbits[subset * 2] = pbit;
+            pbits[subset * 2 + 1] = pbit;
+        }
+    }
+    let has_pbits = endpoint_pbits > 0 || shared_pbits > 0;
+    let expand = |value: u8, bits: u8, pbit: u8| {
+        let (value, bits) = if has_pbits {
+            (value << 1 | pbit, bits + 1)
+        } else {
+            (value, bits)
+        };
+        if bits >= 8 {
+            value
+        } else {
+            value << (8 - bits) | value >> (2 * bits - 8)
+        }
+    };
+    for (endpoint, &pbit) in endpoints.iter_mut().zip(pbits.iter()).take(endpoint_count) {
+        for channel in endpoint.iter_mut().take(3) {
+            *channel = expand(*channel, color_bits, pbit);
+        }
+        if alpha_bits > 0 {
+            endpoint[3] = expand(endpoint[3], alpha_bits, pbit);
+        }
+    }
+
+    let mut indices = [0u8; 16];
+    for (i, index) in indices.iter_mut().enumerate() {
+        let anchor = bc7_is_anchor(subsets, partition, i);
+        *index = reader.read(index_bits - anchor as u8);
+    }
+    let mut indices_2 = [0u8; 16];
+    if index_bits_2 > 0 {
+        for (i, index) in indices_2.iter_mut().enumerate() {
+            *index = reader.read(index_bits_2 - (i == 0) as u8);
+        }
+    }
+
+    let mut pixels = [[0u8; 4]; 16];
+    for (i, pixel) in pixels.iter_mut().enumerate() {
+        let subset = bc7_subset(subsets, partition, i);
+        let (e0, e1) = (endpoints[subset * 2], endpoints[subset * 2 + 1]);
+        // With two index sets, the selection bit picks which one is used
+        // for color and which one for alpha.
+        let (color_index, color_weights, alpha_index, alpha_weights) = if index_bits_2 == 0 {
+            let weights = bc7_weights(index_bits);
+            (indices[i], weights, indices[i], weights)
+        } else if selection == 0 {
+            (
+                indices[i],
+                bc7_weights(index_bits),
+                indices_2[i],
+                bc7_weights(index_bits_2),
+            )
+        } else {
+            (
+                indices_2[i],
+                bc7_weights(index_bits_2),
+                indices[i],
+                bc7_weights(index_bits),
+            )
+        };
+        let weight = color_weights[usize::from(color_index)];
+        for (channel, (&c0, &c1)) in pixel.iter_mut().zip(e0.iter().zip(e1.iter())).take(3) {
+            *channel = bc7_interpolate(c0, c1, weight);
+        }
+        pixel[3] = bc7_interpolate(e0[3], e1[3], alpha_weights[usize::from(alpha_index)]);
+        if rotation > 0 {
+            pixel.swap(3, usize::from(rotation - 1));
+        }
+    }
+    pixels
+}
+
+// ETC2 and EAC
+
+const ETC_MODIFIERS: [[i32; 4]; 8] = [
+    [2, 8, -2, -8],
+    [5, 17, -5, -17],
+    [9, 29, -9, -29],
+    [13, 42, -13, -42],
+    [18, 60, -18, -60],
+    [24, 80, -24, -80],
+    [33, 106, -33, -106],
+    [47, 183, -47, -183],
+];
+
+const ETC_DISTANCES: [i32; 8] = [3, 6, 11, 16, 23, 32, 41, 64];
+
+const EAC_MODIFIERS: [[i32; 8]; 16] = [
+    [-3, -6, -9, -15, 2, 5, 8, 14],
+    [-3, -7, -10, -13, 2, 6, 9, 12],
+    [-2, -5, -8, -13, 1, 4, 7, 12],
+    [-2, -4, -6, -13, 1, 3, 5, 12],
+    [-3, -6, -8, -12, 2, 5, 7, 11],
+    [-3, -7, -9, -11, 2, 6, 8, 10],
+    [-4, -7, -8, -11, 3, 6, 7, 10],
+    [-3, -5, -8, -11, 2, 4, 7, 10],
+    [-2, -6, -8, -10, 1, 5, 7, 9],
+    [-2, -5, -8, -10, 1, 4, 7, 9],
+    [-2, -4, -8, -10, 1, 3, 7, 9],
+    [-2, -5, -7, -10, 1, 4, 6, 9],
+    [-3, -4, -7, -10, 2, 3, 6, 9],
+    [-1, -2, -3, -10, 0, 1, 2, 9],
+    [-4, -6, -8, -9, 3, 5, 7, 8],
+    [-3, -5, -7, -9, 2, 4, 6, 8],
+];
+
+fn clamp_u8(value: i32) -> u8 {
+    value.max(0).min(255) as u8
+}
+
+fn extend_4(value: u8) -> i32 {
+    i32::from(value << 4 | value)
+}
+
+fn extend_5(value: u8) -> i32 {
+    i32::from(value << 3 | value >> 2)
+}
+
+fn extend_6(value: u8) -> i32 {
+    i32::from(value << 2 | value >> 4)
+}
+
+fn extend_7(value: u8) -> i32 {
+    i32::from(value << 1 | value >> 6)
+}
+
+fn add_rgb(color: [i32; 3], offset: i32) -> [u8; 4] {
+    [
+        clamp_u8(color[0] + offset),
+        clamp_u8(color[1] + offset),
+        clamp_u8(color[2] + offset),
+        255,
+    ]
+}
+
+/// Decodes an ETC2 RGB block, which may also be a plain ETC1 block.
+/// With `punchthrough` it is an RGB block with 1-bit alpha instead.
+fn decode_etc2(block: &[u8], punchthrough: bool) -> [[u8; 4]; 16] {
+    let b = block;
+    // For punchthrough alpha blocks this bit says whether the block is
+    // fully opaque, and the differential mode is always used.
+    let diff_bit = b[3] & 0x02 != 0;
+    let (differential, opaque) = if punchthrough {
+        (true, diff_bit)
+    } else {
+        (diff_bit, true)
+    };
+    let flip = b[3] & 0x01 != 0;
+    let pixel_bits = u32::from_be_bytes([b[4], b[5], b[6], b[7]]);
+    // Pixels are stored column by column.
+    let pixel_index = |x: usize, y: usize| {
+        let k = x * 4 + y;
+        ((pixel_bits >> (k + 16) & 1) << 1 | (pixel_bits >> k & 1)) as usize
+    };
+
+    let mut pixels = [[0u8; 4]; 16];
+    if differential {
+        let r = i32::from(b[0] >> 3) + i32::from(((b[0] & 0x07) << 5) as i8 >> 5);
+        let g = i32::from(b[1] >> 3) + i32::from(((b[1] & 0x07) << 5) as i8 >> 5);
+        let bl = i32::from(b[2] >> 3) + i32::from(((b[2] & 0x07) << 5) as i8 >> 5);
+        if r < 0 || r > 31 {
+            return decode_etc2_t_or_h(b, true, opaque, pixel_index);
+        } else if g < 0 || g > 31 {
+            return decode_etc2_t_or_h(b, false, opaque, pixel_index);
+        } else if bl < 0 || bl > 31 {
+            return decode_etc2_planar(b);
+        }
+    }
+
+    let bases = if differential {
+        let base = [b[0] >> 3, b[1] >> 3, b[2] >> 3];
+        let delta = |i: usize| ((b[i] & 0x07) << 5) as i8 >> 5;
+        let second = [
+            (base[0] as i8 + delta(0)) as u8,
+            (base[1] as i8 + delta(1)) as u8,
+            (base[2] as i8 + delta(2)) as u8,
+        ];
+        [
+            [extend_5(base[0]), extend_5(base[1]), extend_5(base[2])],
+            [
+                extend_5(second[0]),
+                extend_5(second[1]),
+                extend_5(second[2]),
+            ],
+        ]
+    } else {
+        [
+            [
+                extend_4(b[0] >> 4),
+                extend_4(b[1] >> 4),
+                extend_4(b[2] >> 4),
+            ],
+            [
+                extend_4(b[0] & 0x0f),
+                extend_4(b[1] & 0x0f),
+                extend_4(b[2] & 0x0f),
+            ],
+        ]
+    };
+    let tables = [
+        ETC_MODIFIERS[usize::from(b[3] >> 5)],
+        ETC_MODIFIERS[usize::from(b[3] >> 2 & 0x07)],
+    ];
+    for y in 0..4 {
+        for x in 0..4 {
+            let subblock = if flip { y / 2 } else { x / 2 };
+            let index = pixel_index(x, y);
+            pixels[y * 4 + x] = if !opaque && index == 2 {
+                [0, 0, 0, 0]
+            } else if !opaque && index == 0 {
+                // Without opaque pixels, the small modifiers are dropped.
+                add_rgb(bases[subblock], 0)
+            } else {
+                add_rgb(bases[subblock], tables[subblock][index])
+            };
+        }
+    }
+    pixels
+}
+
+/// Decodes the T (`t_mode`) and H modes of ETC2.
+fn decode_etc2_t_or_h<F>(b: &[u8], t_mode: bool, opaque: bool, pixel_index: F) -> [[u8; 4]; 16]
+where
+    F: Fn(usize, usize) -> usize,
+{
+    let paint = if t_mode {
+        let c1 = [
+            extend_4((b[0] >> 1 & 0x0c) | (b[0] & 0x03)),
+            extend_4(b[1] >> 4),
+            extend_4(b[1] & 0x0f),
+        ];
+        let c2 = [
+            extend_4(b[2] >> 4),
+            extend_4(b[2] & 0x0f),
+            extend_4(b[3] >> 4),
+        ];
+        let d = ETC_DISTANCES[usize::from((b[3] >> 1 & 0x06) | (b[3] & 0x01))];
+        [
+            add_rgb(c1, 0),
+            add_rgb(c2, d),
+            add_rgb(c2, 0),
+            add_rgb(c2, -d),
+        ]
+    } else {
+        let c1 = [
+            b[0] >> 3 & 0x0f,
+            (b[0] & 0x07) << 1 | (b[1] >> 4 & 0x01),
+            (b[1] & 0x08) | (b[1] & 0x03) << 1 | b[2] >> 7,
+        ];
+        let c2 = [
+            b[2] >> 3 & 0x0f,
+            (b[2] & 0x07) << 1 | b[3] >> 7,
+            b[3] >> 3 & 0x0f,
+        ];
+        let value = |c: [u8; 3]| u32::from(c[0]) << 8 | u32::from(c[1]) << 4 | u32::from(c[2]);
+        let index = (b[3] & 0x04) | (b[3] & 0x01) << 1 | (value(c1) >= value(c2)) as u8;
+        let d = ETC_DISTANCES[usize::from(index)];
+        let c1 = [extend_4(c1[0]), extend_4(c1[1]), extend_4(c1[2])];
+        let c2 = [extend_4(c2[0]), extend_4(c2[1]), extend_4(c2[2])];
+        [
+            add_rgb(c1, d),
+            add_rgb(c1, -d),
+            add_rgb(c2, d),
+            add_rgb(c2, -d),
+        ]
+    };
+    let mut pixels = [[0u8; 4]; 16];
+    for y in 0..4 {
+        for x in 0..4 {
+            let index = pixel_index(x, y);
+            pixels[y * 4 + x] = if !opaque && index == 2 {
+                [0, 0, 0, 0]
+            } else {
+                paint[index]
+            };
+        }
+    }
+    pixels
+}
+
+/// Decodes the planar mode of ETC2, which is always opaque.
+fn decode_etc2_planar(b: &[u8]) -> [[u8; 4]; 16] {
+    let origin = [
+        extend_6(b[0] >> 1 & 0x3f),
+        extend_7((b[0] & 0x01) << 6 | (b[1] >> 1 & 0x3f)),
+        extend_6((b[1] & 0x01) << 5 | (b[2] & 0x18) | (b[2] & 0x03) << 1 | b[3] >> 7),
+    ];
+    let horizontal = [
+        extend_6((b[3] >> 2 & 0x1f) << 1 | (b[3] & 0x01)),
+        extend_7(b[4] >> 1),
+        extend_6((b[4] & 0x01) << 5 | b[5] >> 3),
+    ];
+    let vertical = [
+        extend_6((b[5] & 0x07) << 3 | b[6] >> 5),
+        extend_7((b[6] & 0x1f) << 2 | b[7] >> 6),
+        extend_6(b[7] & 0x3f),
+    ];
+    let mut pixels = [[0u8; 4]; 16];
+    for y in 0..4 {
+        for x in 0..4 {
+            let channel = |c: usize| {
+                clamp_u8(
+                    (x as i32 * (horizontal[c] - origin[c])
+                        + y as i32 * (vertical[c] - origin[c])
+                        + 4 * origin[c]
+                        + 2)
+                        >> 2,
+                )
+            };
+            pixels[y * 4 + x] = [channel(0), channel(1), channel(2), 255];
+        }
+    }
+    pixels
+}
+
+/// Decodes an EAC block, the alpha half of ETC2 RGBA and the channels
+/// of R11 and RG11, into 8-bit values.
+fn decode_eac(b: &[u8]) -> [u8; 16] {
+    let base = i32::from(b[0]);
+    let multiplier = i32::from(b[1] >> 4);
+    let table = EAC_MODIFIERS[usize::from(b[1] & 0x0f)];
+    let mut index_bytes = [0u8; 8];
+    index_bytes[2..].copy_from_slice(&b[2..8]);
+    let indices = u64::from_be_bytes(index_bytes);
+    let mut values = [0u8; 16];
+    for y in 0..4 {
+        for x in 0..4 {
+            // Stored column by column, from the most significant bits.
+            let k = x * 4 + y;
+            let index = (indices >> (45 - 3 * k) & 0x07) as usize;
+            values[y * 4 + x] = clamp_u8(base + table[index] * multiplier);
+        }
+    }
+    values
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn headless_test_bc1() {
+        // Pure red and pure blue endpoints; the first row uses every
+        // palette entry, the rest stay red.
+        let block = [0x00, 0xf8, 0x1f, 0x00, 0b1110_0100, 0, 0, 0];
+        let pixels = decode_block(CompressedFormat::Bc1Rgb, &block);
+        assert_eq!(pixels[0], [255, 0, 0, 255]);
+        assert_eq!(pixels[1], [0, 0, 255, 255]);
+        assert_eq!(pixels[2], [170, 0, 85, 255]);
+        assert_eq!(pixels[3], [85, 0, 170, 255]);
+        assert_eq!(pixels[15], [255, 0, 0, 255]);
+
+        // Swapped endpoints switch to three colors and transparency.
+        let block = [0x1f, 0x00, 0x00, 0xf8, 0b1110_0100, 0, 0, 0];
+        let pixels = decode_block(CompressedFormat::Bc1Rgba, &block);
+        assert_eq!(pixels[2], [127, 0, 127, 255]);
+        assert_eq!(pixels[3], [0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn headless_test_bc4() {
+        // Index 0 is the first endpoint, index 1 the second, and with
+        // a0 <= a1 indices 6 and 7 are 0 and 255.
+        let indices: u64 = 1 << 3 | 6 << 6 | 7 << 9;
+        let mut block = [100, 200, 0, 0, 0, 0, 0, 0];
+        block[2..].copy_from_slice(&indices.to_le_bytes()[..6]);
+        let values = decode_bc4(&block);
+        assert_eq!(&values[..4], &[100, 200, 0, 255]);
+    }
+
+    #[test]
+    fn headless_test_bc7_tables() {
+        for partition in 0..64 {
+            assert_eq!(bc7_subset(2, partition, 0), 0);
+            assert_eq!(bc7_subset(3, partition, 0), 0);
+            let anchor = usize::from(BC7_ANCHORS_2[partition]);
+            assert_eq!(bc7_subset(2, partition, anchor), 1);
+            let anchor = usize::from(BC7_ANCHORS_3[0][partition]);
+            assert_eq!(bc7_subset(3, partition, anchor), 1);
+            let anchor = usize::from(BC7_ANCHORS_3[1][partition]);
+            assert_eq!(bc7_subset(3, partition, anchor), 2);
+        }
+    }
+
+    #[test]
+    fn headless_test_bc7_mode_6() {
+        let mut bits = 0u128;
+        let mut pos = 0;
+        let mut write = |value: u128, count: u32| {
+            bits |= value << pos;
+            pos += count;
+        };
+        // Mode 6, then each channel's two 7-bit endpoints: 0 and 127.
+        write(1 << 6, 7);
+        for _ in 0..4 {
+            write(0, 7);
+            write(127, 7);
+        }
+        // P-bits 0 and 1 give endpoints of 0 and 255.
+        write(0, 1);
+        write(1, 1);
+        // Index 0 for the anchor pixel, then 15 and 8.
+        write(0, 3);
+        write(15, 4);
+        write(8, 4);
+        let pixels = decode_bc7(&bits.to_le_bytes());
+        assert_eq!(pixels[0], [0, 0, 0, 0]);
+        assert_eq!(pixels[1], [255, 255, 255, 255]);
+        assert_eq!(pixels[2], [135, 135, 135, 135]);
+    }
+
+    #[test]
+    fn headless_test_etc1_individual() {
+        // Individual mode with base colors 0x8 and 0x4 in every channel,
+        // codeword 0 for both halves and every pixel using index 0 (+2).
+        let block = [0x84, 0x84, 0x84, 0x00, 0, 0, 0, 0];
+        let pixels = decode_block(CompressedFormat::Etc2Rgb, &block);
+        assert_eq!(pixels[0], [0x8a, 0x8a, 0x8a, 255]);
+        assert_eq!(pixels[3], [0x46, 0x46, 0x46, 255]);
+    }
+
+    #[test]
+    fn headless_test_eac() {
+        // Base 128, multiplier 1, table 0; the first pixel uses index 7
+        // (+14), the rest index 0 (-3).
+        let block = [128, 0x10, 0b1110_0000, 0, 0, 0, 0, 0];
+        let values = decode_eac(&block);
+        assert_eq!(values[0], 142);
+        assert_eq!(values[1], 125);
+    }
+}
@@ -12,6 +12,61 @@ use crate::error::*;
 use crate::graphics::*;
 use crate::Context;
 
+/// A floating point format for an HDR [`Canvas`](type.Canvas.html),
+/// whose colors aren't limited to between 0 and 1.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum HdrFormat {
+    /// 16-bit float red, green, blue and alpha; the usual choice.
+    Rgba16F,
+    /// 32-bit float red, green, blue and alpha, for when the precision
+    /// of 16 bits isn't enough.
+    Rgba32F,
+    /// Packed 11-bit float red and green and 10-bit float blue, with
+    /// no alpha, at half the size of `Rgba16F`.
+    Rg11B10F,
+}
+
+impl HdrFormat {
+    pub(crate) fn format(self) -> gfx::format::Format {
+        let surface = match self {
+            HdrFormat::Rgba16F => gfx::format::SurfaceType::R16_G16_B16_A16,
+            HdrFormat::Rgba32F => gfx::format::SurfaceType::R32_G32_B32_A32,
+            HdrFormat::Rg11B10F => gfx::format::SurfaceType::R11_G11_B10,
+        };
+        gfx::format::Format(surface, gfx::format::ChannelType::Float)
+    }
+}
+
+/// Which `HdrFormat`s the GL context can render to.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) struct HdrSupport {
+    half: bool,
+    full: bool,
+}
+
+impl HdrSupport {
+    pub(crate) fn from_info(info: &gfx_device_gl::Info) -> Self {
+        let version = &info.version;
+        let ext = |name: &str| info.extensions.contains(name);
+        // Desktop GL 3.0 can render to all float formats; GLES only
+        // with extensions, which GLES 3.2 made part of the core.
+        let desktop = !version.is_embedded && version.major >= 3;
+        let es32 = version.is_embedded && (version.major, version.minor) >= (3, 2);
+        let full = desktop || es32 || ext("GL_EXT_color_buffer_float");
+        HdrSupport {
+            half: full || ext("GL_EXT_color_buffer_half_float"),
+            full,
+        }
+    }
+
+    fn supports(self, format: HdrFormat) -> bool {
+        match format {
+            HdrFormat::Rgba16F => self.half,
+            HdrFormat::Rgba32F | HdrFormat::Rg11B10F => self.full,
+        }
+    }
+}
+
 /// A generic canvas independent of graphics backend. This type should
 /// never need to be used directly; use [`graphics::Canvas`](type.Canvas.html)
 /// instead.
@@ -20,7 +75,7 @@ pub struct CanvasGeneric<Spec>
 where
     Spec: BackendSpec,
 {
-    target: RawRenderTargetView<Spec::Resources>,
+    pub(crate) target: RawRenderTargetView<Spec::Resources>,
     image: Image,
     debug_id: DebugId,
 }
@@ -50,6 +105,45 @@ impl Canvas {
         Canvas::new_format(ctx, width, height, samples, ctx.gfx_context.color_format())
     }
 
+    /// Create a new `Canvas` whose image has room for a full chain of
+    /// mipmaps.  They are not kept up to date automatically: call
+    /// [`generate_mipmaps()`](#method.generate_mipmaps) after rendering
+    /// to the canvas and before drawing it scaled down with a mipmapped
+    /// [`FilterMode`](enum.FilterMode.html).
+    pub fn new_mipmapped(
+        ctx: &mut Context,
+        width: u16,
+        height: u16,
+        samples: conf::NumSamples,
+    ) -> GameResult<Canvas> {
+        let color_format = ctx.gfx_context.color_format();
+        Canvas::make(ctx, width, height, samples, color_format, true)
+    }
+
+    /// Create a new floating point `Canvas` for rendering in high
+    /// dynamic range, e.g. adding up the light of many sources.  Use a
+    /// [`Tonemapper`](struct.Tonemapper.html) to draw it to the screen.
+    ///
+    /// With [`WindowSetup::srgb`](../conf/struct.WindowSetup.html#structfield.srgb)
+    /// on, the canvas holds linear colors, which is what tonemapping
+    /// expects.  Returns an error if the GPU can't render to `format`;
+    /// `HdrFormat::Rgba16F` is the most widely supported.
+    pub fn new_hdr(
+        ctx: &mut Context,
+        width: u16,
+        height: u16,
+        samples: conf::NumSamples,
+        format: HdrFormat,
+    ) -> GameResult<Canvas> {
+        if !ctx.gfx_context.hdr_support.supports(format) {
+            return Err(GameError::RenderError(format!(
+                "This GPU can't render to {:?} canvases",
+                format
+            )));
+        }
+        Canvas::make(ctx, width, height, samples, format.format(), false)
+    }
+
     /// Create a new `Canvas` with the given size, number of samples and specified format.
     pub fn new_format(
         ctx: &mut Context,
@@ -57,24 +151,57 @@ impl Canvas {
         height: u16,
         samples: conf::NumSamples,
         color_format: gfx::format::Format,
+    ) -> GameResult<Canvas> {
+        Canvas::make(ctx, width, height, samples, color_format, false)
+    }
+
+    fn make(
+        ctx: &mut Context,
+        width: u16,
+        height: u16,
+        samples: conf::NumSamples,
+        color_format: gfx::format::Format,
+        mipmaps: bool,
     ) -> GameResult<Canvas> {
         let debug_id = DebugId::get(ctx);
         let aa = match samples {
             conf::NumSamples::One => AaMode::Single,
             s => AaMode::Multi(s as u8),
         };
-        let kind = Kind::D2(width, height, aa);
-        let levels = 1;
-        let color_format = ctx.gfx_context.color_format();
+        let levels = if mipmaps {
+            crate::graphics::image::mipmap_count(width, height)
+        } else {
+            1
+        };
         let factory = &mut ctx.gfx_context.factory;
         let texture_create_info = gfx::texture::Info {
-            kind,
+            kind: Kind::D2(width, height, AaMode::Single),
             levels,
             format: color_format.0,
             bind: Bind::SHADER_RESOURCE | Bind::RENDER_TARGET | Bind::TRANSFER_SRC,
             usage: Usage::Data,
         };
         let tex = factory.create_texture_raw(texture_create_info, Some(color_format.1), None)?;
+        // A multisampled texture can't be sampled like a normal one, so
+        // multisampled canvases render into a separate texture that gets
+        // resolved into the single-sampled one whenever it is read.
+        let multisample_tex = match aa {
+            AaMode::Single => None,
+            _ => {
+                let multisample_create_info = gfx::texture::Info {
+                    kind: Kind::D2(width, height, aa),
+                    levels: 1,
+                    format: color_format.0,
+                    bind: Bind::SHADER_RESOURCE | Bind::RENDER_TARGET | Bind::TRANSFER_SRC,
+                    usage: Usage::Data,
+                };
+                Some(factory.create_texture_raw(
+                    multisample_create_info,
+                    Some(color_format.1),
+                    None,
+                )?)
+            }
+        };
         let resource_desc = gfx::texture::ResourceDesc {
             channel: color_format.1,
             layer: None,
@@ -88,7 +215,10 @@ impl Canvas {
             level: 0,
             layer: None,
         };
-        let target = factory.view_texture_as_render_target_raw(&tex, render_desc)?;
+        let target = factory.view_texture_as_render_target_raw(
+            multisample_tex.as_ref().unwrap_or(&tex),
+            render_desc,
+        )?;
         Ok(Canvas {
             target,
             image: Image {
@@ -98,6 +228,9 @@ impl Canvas {
                 blend_mode: None,
                 width,
                 height,
+                multisample_source: multisample_tex
+                    .map(crate::graphics::image::MultisampleSource::new),
+                compressed: None,
                 debug_id,
             },
             debug_id,
@@ -113,10 +246,45 @@ impl Canvas {
     }
 
     /// Gets the backend `Image` that is being rendered to.
+    ///
+    /// For a multisampled canvas this is the single-sampled image that
+    /// the samples are resolved into.  That happens automatically once
+    /// the canvas is unset with [`set_canvas()`](fn.set_canvas.html),
+    /// and before the image is used while it is still set; to use the
+    /// image in ways ggez doesn't know of call [`resolve()`](#method.resolve)
+    /// first.
     pub fn image(&self) -> &Image {
         &self.image
     }
 
+    /// Returns `true` if this is a floating point canvas made with
+    /// [`new_hdr()`](#method.new_hdr).
+    pub fn is_hdr(&self) -> bool {
+        self.image.is_hdr()
+    }
+
+    /// Returns `true` if this canvas is multisampled.
+    pub fn is_multisampled(&self) -> bool {
+        self.image.multisample_source.is_some()
+    }
+
+    /// Resolves everything rendered to a multisampled canvas so far into
+    /// its single-sampled [`image()`](#method.image), if anything was
+    /// rendered since the last time.  Does nothing for canvases with
+    /// only one sample.
+    pub fn resolve(&self, ctx: &mut Context) -> GameResult {
+        self.debug_id.assert(ctx);
+        self.image.resolve_multisample(ctx)
+    }
+
+    /// Regenerates the mipmaps of a canvas made with
+    /// [`new_mipmapped()`](#method.new_mipmapped) from what has been
+    /// rendered to it so far.
+    pub fn generate_mipmaps(&self, ctx: &mut Context) -> GameResult {
+        self.debug_id.assert(ctx);
+        self.image.generate_mipmaps(ctx)
+    }
+
     /// Get the filter mode for the image.
     pub fn filter(&self) -> FilterMode {
         self.image.filter()
@@ -128,6 +296,10 @@ impl Canvas {
     }
 
     /// Destroys the `Canvas` and returns the `Image` it contains.
+    ///
+    /// The image of a multisampled canvas keeps resolving from the
+    /// canvas' samples, which can no longer change, whenever it is
+    /// drawn or read back.
     pub fn into_inner(self) -> Image {
         // TODO: This texture is created with different settings
         // than the default; does that matter?
@@ -163,13 +335,85 @@ impl Drawable for Canvas {
 /// Set the `Canvas` to render to. Specifying `Option::None` will cause all
 /// rendering to be done directly to the screen.
 pub fn set_canvas(ctx: &mut Context, target: Option<&Canvas>) {
-    match target {
+    leave_canvas(ctx);
+    let target = match target {
         Some(surface) => {
             surface.debug_id.assert(ctx);
-            ctx.gfx_context.data.out = surface.target.clone();
+            if let Some(ref source) = surface.image.multisample_source {
+                source.mark_dirty();
+                ctx.gfx_context.multisample_canvas = Some(surface.image.clone());
+            }
+            surface.target.clone()
+        }
+        None => ctx.gfx_context.screen_target(),
+    };
+    ctx.gfx_context.set_render_target(target);
+}
+
+/// Finishes drawing to the current canvas, if any, before switching
+/// away from it: draws the debug shapes queued for it and resolves it
+/// if it is multisampled.
+pub(crate) fn leave_canvas(ctx: &mut Context) {
+    if ctx.gfx_context.data.out != ctx.gfx_context.screen_target() {
+        if let Err(e) = debug::flush_target(ctx) {
+            warn!("Couldn't draw the debug shapes queued for a canvas: {}", e);
         }
-        None => {
-            ctx.gfx_context.data.out = ctx.gfx_context.screen_render_target.clone();
+    }
+    if let Some(image) = ctx.gfx_context.multisample_canvas.take() {
+        // If this fails, so does using the image, which reports it.
+        let _ = image.resolve_multisample(ctx);
+    }
+}
+
+/// Creates or drops the canvas of `ScalingMode::PixelPerfect` to match
+/// the current scaling mode.
+pub(crate) fn update_pixel_canvas(ctx: &mut Context) -> GameResult {
+    let size = match ctx.gfx_context.scaling {
+        conf::ScalingMode::PixelPerfect { width, height } => Some((width, height)),
+        _ => None,
+    };
+    let current_size = ctx
+        .gfx_context
+        .pixel_canvas
+        .as_ref()
+        .map(|canvas| (canvas.image.width(), canvas.image.height()));
+    if size == current_size {
+        return Ok(());
+    }
+    let drawing_to_screen = ctx.gfx_context.data.out == ctx.gfx_context.screen_target();
+    ctx.gfx_context.pixel_canvas = match size {
+        Some((width, height)) => {
+            let mut canvas = Canvas::new(ctx, width, height, conf::NumSamples::One)?;
+            canvas.set_filter(FilterMode::Nearest);
+            Some(canvas)
         }
+        None => None,
+    };
+    if drawing_to_screen {
+        let target = ctx.gfx_context.screen_target();
+        ctx.gfx_context.set_render_target(target);
+    }
+    Ok(())
+}
+
+/// Draws the canvas of `ScalingMode::PixelPerfect` to the window,
+/// scaled up to fill the scaling viewport, on a background of the
+/// active window's border color.
+pub(crate) fn draw_pixel_canvas(ctx: &mut Context) -> GameResult {
+    let canvas = match ctx.gfx_context.pixel_canvas.take() {
+        Some(canvas) => canvas,
+        None => return Ok(()),
     };
+    let window = ctx.gfx_context.screen_render_target.clone();
+    ctx.gfx_context.set_render_target(window);
+    let border_color = ctx.gfx_context.border_color;
+    clear(ctx, border_color);
+    let viewport = ctx.gfx_context.scaling_viewport;
+    let param = DrawParam::new().dest([viewport.x, viewport.y]).scale([
+        viewport.w / f32::from(canvas.image.width()),
+        viewport.h / f32::from(canvas.image.height()),
+    ]);
+    let result = with_window_pixels(ctx, |ctx| canvas.draw(ctx, param));
+    ctx.gfx_context.pixel_canvas = Some(canvas);
+    result
 }
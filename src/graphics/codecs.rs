@@ -0,0 +1,254 @@
+//! Image decoding with format detection, and the encoders the `image`
+//! crate doesn't have.
+
+use ::image;
+
+use crate::error::{GameError, GameResult};
+
+/// Decodes an image file, telling the format from its first bytes
+/// rather than the file name.  Returns the width, height and RGBA
+/// bytes.
+///
+/// TGA files have no magic bytes, so anything unrecognized is tried
+/// as TGA last.
+pub(crate) fn decode(bytes: &[u8]) -> GameResult<(u16, u16, Vec<u8>)> {
+    #[cfg(feature = "qoi")]
+    {
+        if bytes.starts_with(QOI_MAGIC) {
+            return decode_qoi(bytes);
+        }
+    }
+    let img = match image::guess_format(bytes) {
+        Ok(format) => image::load_from_memory_with_format(bytes, format)?,
+        #[cfg(feature = "tga")]
+        Err(_) => image::load_from_memory_with_format(bytes, image::ImageFormat::TGA)?,
+        #[cfg(not(feature = "tga"))]
+        Err(e) => return Err(e.into()),
+    };
+    let img = img.to_rgba();
+    let (width, height) = img.dimensions();
+    if width > u32::from(u16::max_value()) || height > u32::from(u16::max_value()) {
+        return Err(GameError::ResourceLoadError(format!(
+            "Image of size {}x{} is too large",
+            width, height
+        )));
+    }
+    Ok((width as u16, height as u16, img.into_raw()))
+}
+
+/// Encodes RGBA bytes as an uncompressed 32-bit TGA file.
+#[cfg(feature = "tga")]
+pub(crate) fn encode_tga(rgba: &[u8], width: u16, height: u16) -> Vec<u8> {
+    let mut out = Vec::with_capacity(18 + rgba.len());
+    // No image ID or color map, uncompressed true-color image at 0, 0.
+    out.extend_from_slice(&[0, 0, 2, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+    out.extend_from_slice(&width.to_le_bytes());
+    out.extend_from_slice(&height.to_le_bytes());
+    // 32 bits per pixel; 8 alpha bits and rows from the top.
+    out.extend_from_slice(&[32, 0x28]);
+    for pixel in rgba.chunks_exact(4) {
+        out.extend_from_slice(&[pixel[2], pixel[1], pixel[0], pixel[3]]);
+    }
+    out
+}
+
+#[cfg(feature = "qoi")]
+const QOI_MAGIC: &[u8] = b"qoif";
+#[cfg(feature = "qoi")]
+const QOI_HEADER_SIZE: usize = 14;
+#[cfg(feature = "qoi")]
+const QOI_END_MARKER: [u8; 8] = [0, 0, 0, 0, 0, 0, 0, 1];
+
+#[cfg(feature = "qoi")]
+const QOI_OP_INDEX: u8 = 0x00;
+#[cfg(feature = "qoi")]
+const QOI_OP_DIFF: u8 = 0x40;
+#[cfg(feature = "qoi")]
+const QOI_OP_LUMA: u8 = 0x80;
+#[cfg(feature = "qoi")]
+const QOI_OP_RUN: u8 = 0xc0;
+#[cfg(feature = "qoi")]
+const QOI_OP_RGB: u8 = 0xfe;
+#[cfg(feature = "qoi")]
+const QOI_OP_RGBA: u8 = 0xff;
+
+#[cfg(feature = "qoi")]
+fn qoi_hash(pixel: [u8; 4]) -> usize {
+    let [r, g, b, a] = pixel;
+    (usize::from(r) * 3 + usize::from(g) * 5 + usize::from(b) * 7 + usize::from(a) * 11) % 64
+}
+
+/// Encodes RGBA bytes as a QOI file (see <https://qoiformat.org/>).
+#[cfg(feature = "qoi")]
+pub(crate) fn encode_qoi(rgba: &[u8], width: u16, height: u16) -> Vec<u8> {
+    let mut out = Vec::with_capacity(QOI_HEADER_SIZE + rgba.len() + QOI_END_MARKER.len());
+    out.extend_from_slice(QOI_MAGIC);
+    out.extend_from_slice(&u32::from(width).to_be_bytes());
+    out.extend_from_slice(&u32::from(height).to_be_bytes());
+    // RGBA, sRGB with linear alpha.
+    out.extend_from_slice(&[4, 0]);
+
+    let mut index = [[0u8; 4]; 64];
+    let mut previous = [0, 0, 0, 255];
+    let mut run = 0u8;
+    for pixel in rgba.chunks_exact(4) {
+        let pixel = [pixel[0], pixel[1], pixel[2], pixel[3]];
+        if pixel == previous {
+            run += 1;
+            if run == 62 {
+                out.push(QOI_OP_RUN | (run - 1));
+                run = 0;
+            }
+            continue;
+        }
+        if run > 0 {
+            out.push(QOI_OP_RUN | (run - 1));
+            run = 0;
+        }
+
+        let hash = qoi_hash(pixel);
+        if index[hash] == pixel {
+            out.push(QOI_OP_INDEX | hash as u8);
+        } else if pixel[3] == previous[3] {
+            let dr = i32::from(pixel[0].wrapping_sub(previous[0]) as i8);
+            let dg = i32::from(pixel[1].wrapping_sub(previous[1]) as i8);
+            let db = i32::from(pixel[2].wrapping_sub(previous[2]) as i8);
+            let (dr_dg, db_dg) = (dr - dg, db - dg);
+            if (-2..=1).contains(&dr) && (-2..=1).contains(&dg) && (-2..=1).contains(&db) {
+                out.push(QOI_OP_DIFF | ((dr + 2) << 4 | (dg + 2) << 2 | (db + 2)) as u8);
+            } else if (-32..=31).contains(&dg)
+                && (-8..=7).contains(&dr_dg)
+                && (-8..=7).contains(&db_dg)
+            {
+                out.push(QOI_OP_LUMA | (dg + 32) as u8);
+                out.push(((dr_dg + 8) << 4 | (db_dg + 8)) as u8);
+            } else {
+                out.extend_from_slice(&[QOI_OP_RGB, pixel[0], pixel[1], pixel[2]]);
+            }
+        } else {
+            out.extend_from_slice(&[QOI_OP_RGBA, pixel[0], pixel[1], pixel[2], pixel[3]]);
+        }
+        index[hash] = pixel;
+        previous = pixel;
+    }
+    if run > 0 {
+        out.push(QOI_OP_RUN | (run - 1));
+    }
+    out.extend_from_slice(&QOI_END_MARKER);
+    out
+}
+
+/// Decodes a QOI file into its width, height and RGBA bytes.
+#[cfg(feature = "qoi")]
+pub(crate) fn decode_qoi(bytes: &[u8]) -> GameResult<(u16, u16, Vec<u8>)> {
+    let invalid = |msg: &str| GameError::ResourceLoadError(format!("Invalid QOI file: {}", msg));
+    if bytes.len() < QOI_HEADER_SIZE + QOI_END_MARKER.len() || !bytes.starts_with(QOI_MAGIC) {
+        return Err(invalid("missing header"));
+    }
+    let width = u32::from_be_bytes([bytes[4], bytes[5], bytes[6], bytes[7]]);
+    let height = u32::from_be_bytes([bytes[8], bytes[9], bytes[10], bytes[11]]);
+    let max = u32::from(u16::max_value());
+    if width == 0 || height == 0 || width > max || height > max {
+        return Err(invalid("unsupported size"));
+    }
+    let len = width as usize * height as usize * 4;
+
+    // Don't trust the header with the allocation: every byte of the
+    // input makes at most a 62 pixel run.
+    let mut out = Vec::with_capacity(len.min(bytes.len() * 62 * 4));
+    let mut index = [[0u8; 4]; 64];
+    let mut pixel = [0, 0, 0, 255];
+    let mut pos = QOI_HEADER_SIZE;
+    while out.len() < len {
+        // The longest chunk is 5 bytes, and the end marker follows the
+        // last one, so this only fails for truncated files.
+        if pos + 5 > bytes.len() {
+            return Err(invalid("truncated data"));
+        }
+        let op = bytes[pos];
+        pos += 1;
+        let mut run = 1;
+        match op {
+            QOI_OP_RGB => {
+                pixel[..3].copy_from_slice(&bytes[pos..pos + 3]);
+                pos += 3;
+            }
+            QOI_OP_RGBA => {
+                pixel.copy_from_slice(&bytes[pos..pos + 4]);
+                pos += 4;
+            }
+            _ => match op & 0xc0 {
+                QOI_OP_INDEX => pixel = index[usize::from(op)],
+                QOI_OP_DIFF => {
+                    pixel[0] = pixel[0].wrapping_add(((op >> 4) & 0x03).wrapping_sub(2));
+                    pixel[1] = pixel[1].wrapping_add(((op >> 2) & 0x03).wrapping_sub(2));
+                    pixel[2] = pixel[2].wrapping_add((op & 0x03).wrapping_sub(2));
+                }
+                QOI_OP_LUMA => {
+                    let next = bytes[pos];
+                    pos += 1;
+                    let dg = (op & 0x3f).wrapping_sub(32);
+                    pixel[0] = pixel[0].wrapping_add(dg.wrapping_add(next >> 4).wrapping_sub(8));
+                    pixel[1] = pixel[1].wrapping_add(dg);
+                    pixel[2] = pixel[2].wrapping_add(dg.wrapping_add(next & 0x0f).wrapping_sub(8));
+                }
+                _ => run = usize::from(op & 0x3f) + 1,
+            },
+        }
+        index[qoi_hash(pixel)] = pixel;
+        for _ in 0..run {
+            out.extend_from_slice(&pixel);
+        }
+    }
+    out.truncate(len);
+    Ok((width as u16, height as u16, out))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "qoi")]
+    #[test]
+    fn headless_test_qoi_round_trip() {
+        let (width, height) = (13u16, 7u16);
+        let mut rgba = Vec::new();
+        for i in 0..u32::from(width) * u32::from(height) {
+            // Runs, small and large differences, repeats and alpha changes.
+            let pixel = match i % 9 {
+                0..=2 => [10, 20, 30, 255],
+                3 => [11, 19, 31, 255],
+                4 => [40, 60, 35, 255],
+                5 => [200, 3, 99, 255],
+                6 => [10, 20, 30, 255],
+                7 => [200, 3, 99, 128],
+                _ => [(i * 7) as u8, (i * 13) as u8, (i * 29) as u8, 255],
+            };
+            rgba.extend_from_slice(&pixel);
+        }
+        let encoded = encode_qoi(&rgba, width, height);
+        assert!(encoded.ends_with(&QOI_END_MARKER));
+        assert_eq!(decode(&encoded).unwrap(), (width, height, rgba));
+    }
+
+    #[cfg(feature = "qoi")]
+    #[test]
+    fn headless_test_qoi_truncated() {
+        let encoded = encode_qoi(&[1, 2, 3, 4, 5, 6, 7, 8], 2, 1);
+        assert!(decode_qoi(&encoded[..encoded.len() - 9]).is_err());
+
+        // A tiny file claiming a huge size fails instead of allocating it.
+        let mut huge = encode_qoi(&[1, 2, 3, 4], 1, 1);
+        huge[4..12].copy_from_slice(&[0, 0, 255, 255, 0, 0, 255, 255]);
+        assert!(decode_qoi(&huge).is_err());
+    }
+
+    #[cfg(feature = "tga")]
+    #[test]
+    fn headless_test_tga_round_trip() {
+        let rgba = vec![1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12];
+        let encoded = encode_tga(&rgba, 3, 1);
+        assert_eq!(encoded.len(), 18 + rgba.len());
+        assert_eq!(decode(&encoded).unwrap(), (3, 1, rgba));
+    }
+}
@@ -1,4 +1,5 @@
 use std::cell::RefCell;
+use std::mem;
 use std::path::Path;
 use std::rc::Rc;
 
@@ -7,15 +8,17 @@ use gfx::traits::FactoryExt;
 use glutin;
 #[cfg(target_os = "windows")]
 use glutin::platform::windows::WindowBuilderExtWindows;
-use glutin::PossiblyCurrent;
+use glutin::NotCurrent;
 use winit::{self, dpi};
-use winit::window::Fullscreen;
+use winit::event_loop::EventLoopWindowTarget;
+use winit::window::{Fullscreen, WindowId};
 
-use crate::conf::{FullscreenType, WindowMode, WindowSetup};
+use crate::conf::{FullscreenType, ScalingMode, WindowMode, WindowSetup};
 use crate::context::DebugId;
 use crate::error::GameResult;
 use crate::filesystem::Filesystem;
 use crate::GameError;
+use crate::graphics::glutin_ext::{ContextBuilderExt, WindowInitExt};
 use crate::graphics::*;
 
 /// A structure that contains graphics state.
@@ -32,11 +35,27 @@ pub(crate) struct GraphicsContextGeneric<B>
     pub(crate) modelview_stack: Vec<Matrix4>,
     pub(crate) white_image: ImageGeneric<B>,
     pub(crate) screen_rect: Rect,
+    /// How the screen coordinates are fitted to the window.
+    pub(crate) scaling: ScalingMode,
+    /// The screen coordinates set with `set_screen_coordinates()`,
+    /// which fitting them to the window keeps.
+    pub(crate) custom_screen_rect: Option<Rect>,
+    /// The part of the window, in physical pixels, the screen
+    /// coordinates are mapped to.
+    pub(crate) scaling_viewport: Rect,
+    /// Squeezes the projection into `scaling_viewport`; only applied
+    /// when drawing to the screen.
+    letterbox: Matrix4,
+    /// What is drawn to the screen goes to this canvas in
+    /// `ScalingMode::PixelPerfect`.
+    pub(crate) pixel_canvas: Option<CanvasGeneric<B>>,
+    /// The color of the bars around the scaling viewport.
+    pub(crate) border_color: Color,
     color_format: gfx::format::Format,
     depth_format: gfx::format::Format,
 
     pub(crate) backend_spec: B,
-    pub(crate) window: glutin::WindowedContext<PossiblyCurrent>,
+    pub(crate) window: glutin_ext::CurrentWindow,
     pub(crate) multisample_samples: u8,
     pub(crate) device: Box<B::Device>,
     pub(crate) factory: Box<B::Factory>,
@@ -55,6 +74,48 @@ pub(crate) struct GraphicsContextGeneric<B>
     default_shader: ShaderId,
     pub(crate) current_shader: Rc<RefCell<Option<ShaderId>>>,
     pub(crate) shaders: Vec<Box<dyn ShaderHandle<B>>>,
+
+    pub(crate) debug_queue: debug::DebugQueue,
+    pub(crate) multisample_resolver: Option<glutin_ext::MultisampleResolver>,
+    /// The image of the multisampled canvas being drawn to, which gets
+    /// resolved when it is unset.
+    pub(crate) multisample_canvas: Option<ImageGeneric<B>>,
+    pub(crate) readbacks: readback::ReadbackRing,
+    pub(crate) recording: Option<recorder::ActiveRecording>,
+    pub(crate) texture_compression: ktx::TextureCompression,
+    pub(crate) hdr_support: canvas::HdrSupport,
+    swap_control: Option<glutin_ext::SwapInterval>,
+    swap_interval: i32,
+
+    /// The window the context was created with.
+    pub(crate) main_window: WindowId,
+    /// Every window but the active one, whose state is in the fields
+    /// above.
+    inactive_windows: Vec<WindowSlot<B>>,
+    /// Whether the main window was last resized to nothing, which is
+    /// how minimizing shows.
+    pub(crate) minimized: bool,
+    pub(crate) lifecycle: lifecycle::Lifecycle,
+}
+
+/// The state of a window that isn't active.  `activate_window()` swaps
+/// it with the same fields of the `GraphicsContext`.
+pub(crate) struct WindowSlot<B>
+where
+    B: BackendSpec,
+{
+    device: Box<B::Device>,
+    encoder: gfx::Encoder<B::Resources, B::CommandBuffer>,
+    screen_render_target: gfx::handle::RawRenderTargetView<B::Resources>,
+    depth_view: gfx::handle::RawDepthStencilView<B::Resources>,
+    scaling: ScalingMode,
+    custom_screen_rect: Option<Rect>,
+    pixel_canvas: Option<CanvasGeneric<B>>,
+    border_color: Color,
+    swap_interval: i32,
+    // Dropped last, so that the GL objects of the fields above can be
+    // deleted while its context is current.
+    window: glutin_ext::CurrentWindow,
 }
 
 impl<B> fmt::Debug for GraphicsContextGeneric<B>
@@ -81,7 +142,11 @@ impl GraphicsContextGeneric<GlBackendSpec> {
     ) -> GameResult<Self> {
         let color_format = gfx::format::Format(
             gfx::format::SurfaceType::R8_G8_B8_A8,
-            gfx::format::ChannelType::Unorm,
+            if window_setup.srgb {
+                gfx::format::ChannelType::Srgb
+            } else {
+                gfx::format::ChannelType::Unorm
+            },
         );
         let depth_format = gfx::format::Format(
             gfx::format::SurfaceType::D24_S8,
@@ -89,28 +154,8 @@ impl GraphicsContextGeneric<GlBackendSpec> {
         );
 
         // WINDOW SETUP
-        let gl_builder = glutin::ContextBuilder::new()
-            .with_gl(glutin::GlRequest::Specific(
-                backend.api(),
-                backend.version_tuple(),
-            ))
-            .with_gl_profile(glutin::GlProfile::Core)
-            .with_multisampling(window_setup.samples as u16)
-            // 24 color bits, 8 alpha bits
-            .with_pixel_format(24, 8)
-            .with_vsync(window_setup.vsync);
-
-        let window_size =
-            dpi::LogicalSize::<f64>::from((f64::from(window_mode.width), f64::from(window_mode.height)));
-        let mut window_builder = glutin::window::WindowBuilder::new()
-            .with_title(window_setup.title.clone())
-            .with_inner_size(window_size)
-            .with_resizable(window_mode.resizable);
-        #[cfg(target_os = "windows")]
-            {
-                window_builder = window_builder.with_drag_and_drop(false);
-            }
-
+        let gl_builder = gl_builder(&backend, window_setup);
+        let mut window_builder = window_builder(window_setup, &window_mode);
         window_builder = if !window_setup.icon.is_empty() {
             let icon = load_icon(window_setup.icon.as_ref(), filesystem)?;
             window_builder.with_window_icon(Some(icon))
@@ -125,6 +170,15 @@ impl GraphicsContextGeneric<GlBackendSpec> {
             color_format,
             depth_format,
         )?;
+        let multisample_resolver = glutin_ext::MultisampleResolver::load(&window);
+        let texture_compression = ktx::TextureCompression::new(
+            glutin_ext::CompressedUploader::load(&window),
+            ktx::CompressionSupport::from_info(device.get_info()),
+        );
+        let hdr_support = canvas::HdrSupport::from_info(device.get_info());
+        let swap_control = glutin_ext::SwapInterval::load(&window);
+        let readback_fences = glutin_ext::FenceSync::load(&window, device.get_info());
+        let main_window = window.window().id();
 
         #[allow(unsafe_code)] // fuck off
         #[cfg(target_os = "android")]
@@ -195,6 +249,7 @@ impl GraphicsContextGeneric<GlBackendSpec> {
             multisample_samples,
             Some(&blend_modes[..]),
             color_format,
+            None,
             debug_id,
         )?;
 
@@ -222,6 +277,7 @@ impl GraphicsContextGeneric<GlBackendSpec> {
             1,
             &[255, 255, 255, 255],
             color_format,
+            false,
             debug_id,
         )?;
         let texture = white_image.texture.clone();
@@ -253,11 +309,17 @@ impl GraphicsContextGeneric<GlBackendSpec> {
             modelview_stack: vec![initial_transform],
             white_image,
             screen_rect: Rect::new(left, top, right - left, bottom - top),
+            scaling: window_mode.scaling,
+            custom_screen_rect: None,
+            scaling_viewport: Rect::new(left, top, right - left, bottom - top),
+            letterbox: Matrix4::identity(),
+            pixel_canvas: None,
+            border_color: window_mode.border_color,
             color_format,
             depth_format,
 
             backend_spec: backend,
-            window,
+            window: glutin_ext::CurrentWindow::new(window),
             multisample_samples,
             device: Box::new(device as <GlBackendSpec as BackendSpec>::Device),
             factory: Box::new(factory as <GlBackendSpec as BackendSpec>::Factory),
@@ -275,23 +337,132 @@ impl GraphicsContextGeneric<GlBackendSpec> {
             default_shader: shader.shader_id(),
             current_shader: Rc::new(RefCell::new(None)),
             shaders: vec![draw],
-        };
-        gfx.set_window_mode(window_mode)?;
 
-        // Calculate and apply the actual initial projection matrix
-        let w = window_mode.width;
-        let h = window_mode.height;
-        let rect = Rect {
-            x: 0.0,
-            y: 0.0,
-            w,
-            h,
+            debug_queue: debug::DebugQueue::new(),
+            multisample_resolver,
+            multisample_canvas: None,
+            readbacks: readback::ReadbackRing::new(readback_fences),
+            recording: None,
+            texture_compression,
+            hdr_support,
+            swap_control,
+            swap_interval: if window_setup.vsync { 1 } else { 0 },
+
+            main_window,
+            inactive_windows: Vec::new(),
+            minimized: false,
+            lifecycle: lifecycle::Lifecycle::default(),
         };
-        gfx.set_projection_rect(rect);
-        gfx.calculate_transform_matrix();
-        gfx.update_globals()?;
+        // Also calculates and applies the actual initial projection matrix.
+        gfx.set_window_mode(window_mode)?;
         Ok(gfx)
     }
+
+    /// Opens another window, whose GL context shares images, meshes,
+    /// shaders and everything else with the main window's.  The active
+    /// window stays active, and the new window's mode is not applied
+    /// beyond its size until it is activated.
+    pub(crate) fn create_window(
+        &mut self,
+        events_loop: &EventLoopWindowTarget<()>,
+        window_setup: &WindowSetup,
+        window_mode: &WindowMode,
+    ) -> GameResult<WindowId> {
+        // Creating the context makes it current, so first run
+        // everything recorded for the active one.
+        self.encoder.flush(&mut *self.device);
+        let (window, device, mut factory, screen_render_target, depth_view) =
+            gl_builder(&self.backend_spec, window_setup)
+                .with_gfx_color_raw(self.color_format)
+                .with_gfx_depth_raw(self.depth_format)
+                .with_shared_lists(self.window.context())
+                .build_windowed(window_builder(window_setup, window_mode), events_loop)?
+                .init_gfx_raw(self.color_format, self.depth_format);
+        let id = window.window().id();
+        // The encoder's command buffer has GL objects of its own, which
+        // aren't shared, so it has to be made in the new context.
+        let encoder = GlBackendSpec::encoder(&mut factory);
+        self.inactive_windows.push(WindowSlot {
+            device: Box::new(device as <GlBackendSpec as BackendSpec>::Device),
+            encoder,
+            screen_render_target,
+            depth_view,
+            scaling: window_mode.scaling,
+            custom_screen_rect: None,
+            pixel_canvas: None,
+            border_color: window_mode.border_color,
+            swap_interval: if window_setup.vsync { 1 } else { 0 },
+            window: glutin_ext::CurrentWindow::new(window),
+        });
+        self.window.make_current()?;
+        Ok(id)
+    }
+}
+
+/// The GL context settings of every window.
+fn gl_builder<'a>(
+    backend: &GlBackendSpec,
+    window_setup: &WindowSetup,
+) -> glutin::ContextBuilder<'a, NotCurrent> {
+    glutin::ContextBuilder::new()
+        .with_gl(glutin::GlRequest::Specific(
+            backend.api(),
+            backend.version_tuple(),
+        ))
+        .with_gl_profile(glutin::GlProfile::Core)
+        .with_multisampling(window_setup.samples as u16)
+        // 24 color bits, 8 alpha bits
+        .with_pixel_format(24, 8)
+        .with_vsync(window_setup.vsync)
+}
+
+/// The settings a window is opened with; the rest of the window mode is
+/// applied by `set_window_mode()` afterwards.
+fn window_builder(
+    window_setup: &WindowSetup,
+    window_mode: &WindowMode,
+) -> glutin::window::WindowBuilder {
+    let window_size = dpi::LogicalSize::<f64>::from((
+        f64::from(window_mode.width),
+        f64::from(window_mode.height),
+    ));
+    #[allow(unused_mut)]
+    let mut window_builder = glutin::window::WindowBuilder::new()
+        .with_title(window_setup.title.clone())
+        .with_inner_size(window_size)
+        .with_resizable(window_mode.resizable);
+    #[cfg(target_os = "windows")]
+    {
+        window_builder = window_builder.with_drag_and_drop(window_setup.drag_and_drop);
+    }
+    window_builder
+}
+
+/// The part of a `width` by `height` window, in physical pixels, that
+/// the screen coordinates of `scaling` are mapped to.
+pub(crate) fn scaling_viewport(scaling: ScalingMode, width: f32, height: f32) -> Rect {
+    let full = Rect::new(0.0, 0.0, width, height);
+    let (virtual_width, virtual_height, integer) = match scaling {
+        ScalingMode::Window | ScalingMode::Stretch { .. } => return full,
+        ScalingMode::Letterbox { width, height } => (width, height, false),
+        ScalingMode::IntegerScale { width, height } => (width, height, true),
+        ScalingMode::PixelPerfect { width, height } => (f32::from(width), f32::from(height), true),
+    };
+    if virtual_width <= 0.0 || virtual_height <= 0.0 {
+        return full;
+    }
+    let mut scale = (width / virtual_width).min(height / virtual_height);
+    if integer && scale >= 1.0 {
+        scale = scale.floor();
+    }
+    let (w, h) = (virtual_width * scale, virtual_height * scale);
+    // Starting on a whole pixel keeps integer scaled pixels sharp.
+    Rect::new(
+        ((width - w) / 2.0).floor(),
+        ((height - h) / 2.0).floor(),
+        w,
+        h,
+    )
 }
 
 // This is kinda awful 'cause it copies a couple times,
@@ -334,11 +505,157 @@ impl<B> GraphicsContextGeneric<B>
             .modelview_stack
             .last()
             .expect("Transform stack empty; should never happen");
-        let mvp = self.projection * modelview;
+        let mvp = if self.data.out == self.screen_render_target {
+            self.letterbox * self.projection * modelview
+        } else {
+            self.projection * modelview
+        };
         self.data.mvp = mvp.into();
         self.shader_globals.mvp_matrix = mvp.into();
     }
 
+    /// Switches drawing to the given target.  Recalculates the MVP
+    /// matrix, since only the screen gets the scaling mode's letterbox.
+    pub(crate) fn set_render_target(
+        &mut self,
+        target: gfx::handle::RawRenderTargetView<B::Resources>,
+    ) {
+        self.data.out = target;
+        self.calculate_transform_matrix();
+        self.encoder
+            .update_constant_buffer(&self.data.globals, &self.shader_globals);
+    }
+
+    /// Where drawing to the screen goes: the `ScalingMode::PixelPerfect`
+    /// canvas if there is one, otherwise the window.
+    pub(crate) fn screen_target(&self) -> gfx::handle::RawRenderTargetView<B::Resources> {
+        match self.pixel_canvas {
+            Some(ref canvas) => canvas.target.clone(),
+            None => self.screen_render_target.clone(),
+        }
+    }
+
+    /// Returns whether `target` is the screen of a window that isn't
+    /// active, which can only be drawn to after activating it.
+    pub(crate) fn is_inactive_window_target(
+        &self,
+        target: &gfx::handle::RawRenderTargetView<B::Resources>,
+    ) -> bool {
+        self.inactive_windows.iter().any(|slot| {
+            slot.screen_render_target == *target
+                || slot
+                    .pixel_canvas
+                    .as_ref()
+                    .map_or(false, |canvas| canvas.target == *target)
+        })
+    }
+
+    /// Refits the screen coordinates to the window after its size,
+    /// DPI scale factor or scaling mode changed.  In
+    /// `ScalingMode::Window` this resets them to the window's size in
+    /// logical pixels, unless they were set with
+    /// `set_screen_coordinates()`.
+    pub(crate) fn update_scaling(&mut self) {
+        let size = self.window.window().inner_size();
+        let (width, height) = (size.width as f32, size.height as f32);
+        if width < 1.0 || height < 1.0 {
+            // Minimized; keep everything until the window comes back.
+            return;
+        }
+        let viewport = scaling_viewport(self.scaling, width, height);
+        self.scaling_viewport = viewport;
+        match self.scaling.virtual_size() {
+            None => {
+                self.letterbox = Matrix4::identity();
+            }
+            Some(_) => {
+                let (sx, sy) = (viewport.w / width, viewport.h / height);
+                let tx = (2.0 * viewport.x + viewport.w) / width - 1.0;
+                let ty = 1.0 - (2.0 * viewport.y + viewport.h) / height;
+                #[rustfmt::skip]
+                let letterbox = Matrix4::new(
+                    sx,  0.0, 0.0, tx,
+                    0.0, sy,  0.0, ty,
+                    0.0, 0.0, 1.0, 0.0,
+                    0.0, 0.0, 0.0, 1.0,
+                );
+                self.letterbox = letterbox;
+            }
+        }
+        let rect = self
+            .custom_screen_rect
+            .unwrap_or_else(|| self.scaling_screen_rect());
+        self.set_projection_rect(rect);
+        self.calculate_transform_matrix();
+        self.encoder
+            .update_constant_buffer(&self.data.globals, &self.shader_globals);
+    }
+
+    /// The screen coordinates the scaling mode fits to the window: its
+    /// size in logical pixels in `ScalingMode::Window`, or else the
+    /// virtual resolution.
+    pub(crate) fn scaling_screen_rect(&self) -> Rect {
+        match self.scaling.virtual_size() {
+            None => {
+                let window = self.window.window();
+                let size = window.inner_size();
+                let scale_factor = window.scale_factor() as f32;
+                Rect::new(
+                    0.0,
+                    0.0,
+                    size.width as f32 / scale_factor,
+                    size.height as f32 / scale_factor,
+                )
+            }
+            Some((virtual_width, virtual_height)) => {
+                Rect::new(0.0, 0.0, virtual_width, virtual_height)
+            }
+        }
+    }
+
+    /// Sets the letterbox matrix, returning the old one.
+    pub(crate) fn replace_letterbox(&mut self, letterbox: Matrix4) -> Matrix4 {
+        mem::replace(&mut self.letterbox, letterbox)
+    }
+
+    /// Converts a position in the window, in physical pixels, into
+    /// screen coordinates of the scaling mode.
+    pub(crate) fn window_to_screen(&self, x: f32, y: f32) -> Point2 {
+        match self.scaling.virtual_size() {
+            None => {
+                let scale_factor = self.window.window().scale_factor() as f32;
+                Point2::new(x / scale_factor, y / scale_factor)
+            }
+            Some((virtual_width, virtual_height)) => {
+                let v = self.scaling_viewport;
+                if v.w <= 0.0 || v.h <= 0.0 {
+                    return Point2::origin();
+                }
+                Point2::new(
+                    (x - v.x) * virtual_width / v.w,
+                    (y - v.y) * virtual_height / v.h,
+                )
+            }
+        }
+    }
+
+    /// The inverse of `window_to_screen()`.
+    pub(crate) fn screen_to_window(&self, x: f32, y: f32) -> Point2 {
+        match self.scaling.virtual_size() {
+            None => {
+                let scale_factor = self.window.window().scale_factor() as f32;
+                Point2::new(x * scale_factor, y * scale_factor)
+            }
+            Some((virtual_width, virtual_height)) => {
+                let v = self.scaling_viewport;
+                Point2::new(
+                    v.x + x * v.w / virtual_width,
+                    v.y + y * v.h / virtual_height,
+                )
+            }
+        }
+    }
+
     /// Pushes a homogeneous transform matrix to the top of the transform
     /// (model) matrix stack.
     pub(crate) fn push_transform(&mut self, t: Matrix4) {
@@ -382,9 +699,7 @@ impl<B> GraphicsContextGeneric<B>
     /// Converts the given `DrawParam` into an `InstanceProperties` object and
     /// sends it to the graphics card at the front of the instance buffer.
     pub(crate) fn update_instance_properties(&mut self, draw_params: DrawTransform) -> GameResult {
-        let mut new_draw_params = draw_params;
-        new_draw_params.color = draw_params.color;
-        let properties = new_draw_params.to_instance_properties();
+        let properties = draw_params.to_instance_properties(self.is_srgb());
         self.encoder
             .update_buffer(&self.data.rect_instance_properties, &[properties], 0)?;
         Ok(())
@@ -485,6 +800,129 @@ impl<B> GraphicsContextGeneric<B>
         self.projection
     }
 
+    /// Returns the window that is drawn to and presented.
+    pub(crate) fn active_window(&self) -> WindowId {
+        self.window.window().id()
+    }
+
+    /// Returns the ids of all windows, the active one first.
+    pub(crate) fn window_ids(&self) -> Vec<WindowId> {
+        let mut ids = vec![self.active_window()];
+        ids.extend(
+            self.inactive_windows
+                .iter()
+                .map(|slot| slot.window.window().id()),
+        );
+        ids
+    }
+
+    fn inactive_window_index(&self, id: WindowId) -> GameResult<usize> {
+        self.inactive_windows
+            .iter()
+            .position(|slot| slot.window.window().id() == id)
+            .ok_or_else(|| GameError::WindowError(format!("There is no window {:?}", id)))
+    }
+
+    /// Makes the given window the one that is drawn to and presented,
+    /// drawing to its screen.  Its screen coordinates are refitted to
+    /// it.
+    pub(crate) fn activate_window(&mut self, id: WindowId) -> GameResult {
+        if id == self.active_window() {
+            return Ok(());
+        }
+        let index = self.inactive_window_index(id)?;
+        // What was recorded so far has to run in its own context.
+        self.encoder.flush(&mut *self.device);
+        {
+            let slot = &mut self.inactive_windows[index];
+            mem::swap(&mut self.window, &mut slot.window);
+            mem::swap(&mut self.device, &mut slot.device);
+            mem::swap(&mut self.encoder, &mut slot.encoder);
+            mem::swap(
+                &mut self.screen_render_target,
+                &mut slot.screen_render_target,
+            );
+            mem::swap(&mut self.depth_view, &mut slot.depth_view);
+            mem::swap(&mut self.scaling, &mut slot.scaling);
+            mem::swap(&mut self.custom_screen_rect, &mut slot.custom_screen_rect);
+            mem::swap(&mut self.pixel_canvas, &mut slot.pixel_canvas);
+            mem::swap(&mut self.border_color, &mut slot.border_color);
+            mem::swap(&mut self.swap_interval, &mut slot.swap_interval);
+        }
+        self.window.make_current()?;
+        self.data.out = self.screen_target();
+        self.update_scaling();
+        Ok(())
+    }
+
+    /// Closes a window other than the main one, activating the main
+    /// window if it was active.
+    pub(crate) fn close_window(&mut self, id: WindowId) -> GameResult {
+        if id == self.main_window {
+            return Err(GameError::WindowError(
+                "The main window can't be closed, only quit".to_string(),
+            ));
+        }
+        if id == self.active_window() {
+            let main_window = self.main_window;
+            self.activate_window(main_window)?;
+        }
+        let index = self.inactive_window_index(id)?;
+        let mut slot = self.inactive_windows.remove(index);
+        // Its GL objects have to be deleted in its own context.
+        slot.window.make_current()?;
+        drop(slot);
+        self.window.make_current()?;
+        Ok(())
+    }
+
+    /// Updates the size of a window's render targets after it was
+    /// resized.
+    pub(crate) fn resize_window(&mut self, id: WindowId, size: dpi::PhysicalSize<u32>) {
+        if id == self.active_window() {
+            self.window.resize(size);
+            self.resize_viewport();
+        } else if let Ok(index) = self.inactive_window_index(id) {
+            let slot = &mut self.inactive_windows[index];
+            slot.window.resize(size);
+            if let Some((cv, dv)) = self.backend_spec.resize_viewport(
+                &slot.screen_render_target,
+                &slot.depth_view,
+                self.color_format,
+                self.depth_format,
+                &slot.window,
+            ) {
+                slot.screen_render_target = cv;
+                slot.depth_view = dv;
+            }
+        }
+    }
+
+    /// Returns the swap interval of the active window.
+    pub(crate) fn swap_interval(&self) -> i32 {
+        self.swap_interval
+    }
+
+    /// Sets the swap interval of the active window, see
+    /// `glutin_ext::SwapInterval::set()`.
+    pub(crate) fn set_swap_interval(&mut self, interval: i32) -> GameResult {
+        let swap_control = self.swap_control.as_ref().ok_or_else(|| {
+            GameError::WindowError(
+                "Vsync can't be changed at runtime on this platform".to_string(),
+            )
+        })?;
+        if interval < 0 && !swap_control.supports_adaptive() {
+            return Err(GameError::WindowError(
+                "Adaptive vsync isn't supported by the driver".to_string(),
+            ));
+        }
+        swap_control
+            .set(interval)
+            .map_err(GameError::WindowError)?;
+        self.swap_interval = interval;
+        Ok(())
+    }
+
     /// Sets window mode from a WindowMode object.
     pub(crate) fn set_window_mode(&mut self, mode: WindowMode) -> GameResult {
         let window = &self.window.window();
@@ -512,7 +950,17 @@ impl<B> GraphicsContextGeneric<B>
         };
         window.set_max_inner_size(max_dimensions);
 
-        let monitor = window.current_monitor();
+        // A monitor saved in a config file may have been unplugged
+        // since, which mustn't keep the game from starting.
+        #[cfg(not(target_os = "android"))]
+        let fullscreen_monitor = || match mode.monitor.map(|i| monitor::monitor_handle(window, i)) {
+            Some(Ok(monitor)) => Some(monitor),
+            Some(Err(e)) => {
+                warn!("{}, using the window's current monitor instead", e);
+                window.current_monitor()
+            }
+            None => window.current_monitor(),
+        };
         #[cfg(not(target_os = "android"))]
         match mode.fullscreen_type {
             FullscreenType::Windowed => {
@@ -525,14 +973,40 @@ impl<B> GraphicsContextGeneric<B>
                 window.set_resizable(mode.resizable);
             }
             FullscreenType::True => {
-                window.set_fullscreen(Some(Fullscreen::Borderless(monitor)));
-                window.set_inner_size(dpi::LogicalSize {
-                    width: f64::from(mode.width),
-                    height: f64::from(mode.height),
+                let monitor = fullscreen_monitor();
+                let video_mode = monitor.as_ref().and_then(|monitor| {
+                    monitor::exclusive_mode(
+                        monitor,
+                        mode.video_mode,
+                        mode.width as u32,
+                        mode.height as u32,
+                    )
                 });
+                match video_mode {
+                    Some(video_mode) => {
+                        window.set_fullscreen(Some(Fullscreen::Exclusive(video_mode)));
+                    }
+                    None => {
+                        if let Some(wanted) = mode.video_mode {
+                            return Err(GameError::WindowError(format!(
+                                "The monitor doesn't support video mode {:?}",
+                                wanted
+                            )));
+                        }
+                        warn!(
+                            "No {}x{} video mode, falling back to borderless fullscreen",
+                            mode.width, mode.height
+                        );
+                        window.set_fullscreen(Some(Fullscreen::Borderless(monitor)));
+                        window.set_inner_size(dpi::LogicalSize {
+                            width: f64::from(mode.width),
+                            height: f64::from(mode.height),
+                        });
+                    }
+                }
             }
             FullscreenType::Desktop => {
-                if let Some(monitor) = monitor {
+                if let Some(monitor) = fullscreen_monitor() {
                     let position = monitor.position();
                     let dimensions = monitor.size();
                     let hidpi_factor = window.scale_factor();
@@ -544,6 +1018,10 @@ impl<B> GraphicsContextGeneric<B>
                 }
             }
         }
+        self.scaling = mode.scaling;
+        self.border_color = mode.border_color;
+        self.custom_screen_rect = None;
+        self.update_scaling();
         Ok(())
     }
 
@@ -560,9 +1038,13 @@ impl<B> GraphicsContextGeneric<B>
             self.depth_format(),
             &self.window,
         ) {
+            if self.data.out == self.screen_render_target {
+                self.data.out = cv.clone();
+            }
             self.screen_render_target = cv;
             self.depth_view = dv;
         }
+        self.update_scaling();
     }
 
     /// Returns the screen color format used by the context.
@@ -585,4 +1067,69 @@ impl<B> GraphicsContextGeneric<B>
             false
         }
     }
+
+    /// The format for textures holding data in the given color space.
+    /// sRGB data only gets an sRGB format if the whole pipeline is
+    /// sRGB, so that it is decoded exactly when the output is encoded.
+    pub(crate) fn texture_format(&self, color_space: ColorSpace) -> gfx::format::Format {
+        let gfx::format::Format(surface, channel) = self.color_format();
+        match color_space {
+            ColorSpace::Srgb => gfx::format::Format(surface, channel),
+            ColorSpace::Linear => gfx::format::Format(surface, gfx::format::ChannelType::Unorm),
+        }
+    }
+
+    /// Converts a `Color`, which is always sRGB, into the values a
+    /// shader should work with: linear ones if the render targets
+    /// encode their output as sRGB, and the same ones otherwise.
+    pub(crate) fn shader_color(&self, color: Color) -> [f32; 4] {
+        if self.is_srgb() {
+            LinearColor::from(color).into()
+        } else {
+            color.into()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn headless_test_scaling_viewport() {
+        let window = ScalingMode::Window;
+        assert_eq!(
+            scaling_viewport(window, 800.0, 600.0),
+            Rect::new(0.0, 0.0, 800.0, 600.0)
+        );
+        let letterbox = ScalingMode::Letterbox {
+            width: 320.0,
+            height: 240.0,
+        };
+        assert_eq!(
+            scaling_viewport(letterbox, 1000.0, 600.0),
+            Rect::new(100.0, 0.0, 800.0, 600.0)
+        );
+        let integer = ScalingMode::IntegerScale {
+            width: 320.0,
+            height: 240.0,
+        };
+        assert_eq!(
+            scaling_viewport(integer, 1000.0, 600.0),
+            Rect::new(180.0, 60.0, 640.0, 480.0)
+        );
+        // Too small a window still shows everything.
+        assert_eq!(
+            scaling_viewport(integer, 160.0, 240.0),
+            Rect::new(0.0, 60.0, 160.0, 120.0)
+        );
+        let pixel = ScalingMode::PixelPerfect {
+            width: 320,
+            height: 240,
+        };
+        assert_eq!(
+            scaling_viewport(pixel, 1000.0, 600.0),
+            scaling_viewport(integer, 1000.0, 600.0)
+        );
+    }
 }
@@ -0,0 +1,401 @@
+//! An immediate-mode overlay for drawing debug shapes such as hitboxes,
+//! rays and points.
+//!
+//! Instead of building throwaway [`Mesh`](../struct.Mesh.html)es every
+//! frame, call the functions in here from anywhere in your draw code.
+//! Shapes are collected into a single [`DynamicMesh`](../struct.DynamicMesh.html)
+//! that is drawn on top of everything else when
+//! [`graphics::present()`](../fn.present.html) is called, and then the
+//! queue is emptied for the next frame.
+//!
+//! By default coordinates are in world space: they go through the
+//! transform that is current when the shape is queued, just like
+//! anything else you draw.  Use [`set_space()`](fn.set_space.html)
+//! to switch to screen space, where coordinates are in the units set by
+//! [`graphics::set_screen_coordinates()`](../fn.set_screen_coordinates.html)
+//! and the transform is ignored.
+//!
+//! Shapes queued while drawing to a [`Canvas`](../type.Canvas.html)
+//! go on that canvas instead, when [`graphics::set_canvas()`](../fn.set_canvas.html)
+//! switches away from it, so that they are there when it is drawn.
+//! Either way they use the screen coordinates that were set when they
+//! were queued.
+//!
+//! ```rust,no_run
+//! # use ggez::*;
+//! # use ggez::graphics::*;
+//! # fn main() -> GameResult {
+//! # let ctx = &mut ContextBuilder::new("foo", "bar").build().unwrap().0;
+//! graphics::debug::rect(ctx, DrawMode::stroke(1.0), Rect::new(10.0, 10.0, 32.0, 48.0), (255, 0, 0).into())?;
+//! graphics::debug::arrow(ctx, [26.0, 34.0], [80.0, 34.0], 2.0, WHITE)?;
+//! graphics::present(ctx)?;
+//! # Ok(()) }
+//! ```
+
+use std::mem;
+
+use crate::context::Context;
+use crate::error::GameResult;
+use crate::graphics::*;
+
+/// Which coordinate space debug shapes are given in.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Space {
+    /// Coordinates go through the current transform, like everything else drawn.
+    World,
+    /// Coordinates are in screen units and ignore the current transform.
+    Screen,
+}
+
+impl Default for Space {
+    fn default() -> Self {
+        Space::World
+    }
+}
+
+type RenderTarget = gfx::handle::RawRenderTargetView<gfx_device_gl::Resources>;
+
+/// Shapes queued for the same render target with the same projection.
+/// Generic over the target only so batching can be tested without a
+/// GPU.
+#[derive(Debug)]
+struct DebugBatch<T = RenderTarget> {
+    target: T,
+    projection: Matrix4,
+    /// Queued geometry, in screen coordinates: the transform is applied,
+    /// the projection isn't yet.
+    builder: MeshBuilder,
+}
+
+/// The per-frame queue of debug shapes, kept in the graphics context.
+#[derive(Debug, Default)]
+pub(crate) struct DebugQueue {
+    batches: Vec<DebugBatch>,
+    /// The GPU buffers, created on the first flush and reused afterwards.
+    mesh: Option<DynamicMesh>,
+    space: Space,
+    enabled: bool,
+}
+
+impl DebugQueue {
+    pub(crate) fn new() -> Self {
+        DebugQueue {
+            enabled: true,
+            ..Default::default()
+        }
+    }
+}
+
+/// Sets which coordinate space the following debug shapes are given in.
+/// The default is [`Space::World`](enum.Space.html#variant.World).
+pub fn set_space(ctx: &mut Context, space: Space) {
+    ctx.gfx_context.debug_queue.space = space;
+}
+
+/// Returns which coordinate space debug shapes are currently given in.
+pub fn space(ctx: &Context) -> Space {
+    ctx.gfx_context.debug_queue.space
+}
+
+/// Turns the debug overlay on or off.  While it is off, all shapes
+/// are ignored, so you can leave debug drawing calls in a release build
+/// and switch them off at runtime.  It is on by default.
+pub fn set_enabled(ctx: &mut Context, enabled: bool) {
+    let queue = &mut ctx.gfx_context.debug_queue;
+    queue.enabled = enabled;
+    if !enabled {
+        queue.batches.clear();
+    }
+}
+
+/// Returns whether the debug overlay is on.
+pub fn is_enabled(ctx: &Context) -> bool {
+    ctx.gfx_context.debug_queue.enabled
+}
+
+/// Queues a line from `from` to `to`.
+pub fn line<P>(ctx: &mut Context, from: P, to: P, width: f32, color: Color) -> GameResult
+where
+    P: Into<mint::Point2<f32>>,
+{
+    let points: [mint::Point2<f32>; 2] = [from.into(), to.into()];
+    let mut mb = MeshBuilder::new();
+    let _ = mb.line(&points, width, color)?;
+    queue(ctx, &mb);
+    Ok(())
+}
+
+/// Queues a rectangle.
+pub fn rect(ctx: &mut Context, mode: DrawMode, bounds: Rect, color: Color) -> GameResult {
+    let mut mb = MeshBuilder::new();
+    let _ = mb.rectangle(mode, bounds, color);
+    queue(ctx, &mb);
+    Ok(())
+}
+
+/// Queues a circle.
+pub fn circle<P>(
+    ctx: &mut Context,
+    mode: DrawMode,
+    center: P,
+    radius: f32,
+    color: Color,
+) -> GameResult
+where
+    P: Into<mint::Point2<f32>>,
+{
+    let mut mb = MeshBuilder::new();
+    let _ = mb.circle(mode, center, radius, 0.25, color);
+    queue(ctx, &mb);
+    Ok(())
+}
+
+/// Queues a point, drawn as a square that is `size` screen units wide
+/// whatever the current transform is; only its position is transformed.
+pub fn point<P>(ctx: &mut Context, point: P, size: f32, color: Color) -> GameResult
+where
+    P: Into<mint::Point2<f32>>,
+{
+    let point: mint::Point2<f32> = point.into();
+    let transform = space_transform(
+        ctx.gfx_context.debug_queue.space,
+        ctx.gfx_context.transform(),
+    );
+    let center = transform_point(&transform, [point.x, point.y]);
+    let half = size / 2.0;
+    let mut mb = MeshBuilder::new();
+    let _ = mb.rectangle(
+        DrawMode::fill(),
+        Rect::new(center[0] - half, center[1] - half, size, size),
+        color,
+    );
+    queue_in_space(ctx, &mb, Space::Screen);
+    Ok(())
+}
+
+/// Queues an arrow from `from` to `to`, with a head sized to match
+/// the line `width`.
+pub fn arrow<P>(ctx: &mut Context, from: P, to: P, width: f32, color: Color) -> GameResult
+where
+    P: Into<mint::Point2<f32>>,
+{
+    let (from, to): (mint::Point2<f32>, mint::Point2<f32>) = (from.into(), to.into());
+    let (from, to) = (Point2::from(from), Point2::from(to));
+    let direction = to - from;
+    let length = direction.norm();
+    if length <= std::f32::EPSILON {
+        return Ok(());
+    }
+    let direction = direction / length;
+    let normal = Vector2::new(-direction.y, direction.x);
+    let head_length = (width * 4.0).min(length);
+    let head_base = to - direction * head_length;
+    let head_half_width = width * 2.0;
+
+    let mut mb = MeshBuilder::new();
+    // Stop the shaft at the head so the two don't overlap, which would
+    // show up with translucent colors.
+    if head_length < length {
+        let _ = mb.line(&[from, head_base], width, color)?;
+    }
+    let _ = mb.triangles(
+        &[
+            to,
+            head_base + normal * head_half_width,
+            head_base - normal * head_half_width,
+        ],
+        color,
+    )?;
+    queue(ctx, &mb);
+    Ok(())
+}
+
+/// Throws away all shapes queued so far this frame.
+pub fn clear(ctx: &mut Context) {
+    ctx.gfx_context.debug_queue.batches.clear();
+}
+
+/// Moves the geometry of `mb` into the queue, in the current space.
+fn queue(ctx: &mut Context, mb: &MeshBuilder) {
+    let space = ctx.gfx_context.debug_queue.space;
+    queue_in_space(ctx, mb, space);
+}
+
+/// Moves the geometry of `mb` into the batch for the current render
+/// target and projection, applying the current transform in world space.
+fn queue_in_space(ctx: &mut Context, mb: &MeshBuilder, space: Space) {
+    let gfx = &mut ctx.gfx_context;
+    if !gfx.debug_queue.enabled {
+        return;
+    }
+    let transform = space_transform(space, gfx.transform());
+    let target = gfx.data.out.clone();
+    let projection = gfx.projection();
+    push_shape(
+        &mut gfx.debug_queue.batches,
+        target,
+        projection,
+        &transform,
+        mb,
+    );
+}
+
+/// Appends the geometry of `mb`, passed through `transform`, to the last
+/// batch if it has the same target and projection, or to a new one.
+fn push_shape<T>(
+    batches: &mut Vec<DebugBatch<T>>,
+    target: T,
+    projection: Matrix4,
+    transform: &Matrix4,
+    mb: &MeshBuilder,
+) where
+    T: PartialEq,
+{
+    let same_batch = batches.last().map_or(false, |batch| {
+        batch.target == target && batch.projection == projection
+    });
+    if !same_batch {
+        batches.push(DebugBatch {
+            target,
+            projection,
+            builder: MeshBuilder::new(),
+        });
+    }
+    if let Some(batch) = batches.last_mut() {
+        let _ = batch
+            .builder
+            .append_transformed(mb, |p| transform_point(transform, p));
+    }
+}
+
+/// The transform from coordinates in `space` to screen coordinates,
+/// given the current `transform`.
+fn space_transform(space: Space, transform: Matrix4) -> Matrix4 {
+    match space {
+        Space::World => transform,
+        Space::Screen => Matrix4::identity(),
+    }
+}
+
+fn transform_point(matrix: &Matrix4, p: [f32; 2]) -> [f32; 2] {
+    let v = matrix * na::Vector4::new(p[0], p[1], 0.0, 1.0);
+    [v.x, v.y]
+}
+
+/// Draws everything queued this frame for the active window and
+/// empties the queue.  Called by `graphics::present()`.  Shapes queued
+/// for another window wait for it to be presented.
+pub(crate) fn flush(ctx: &mut Context) -> GameResult {
+    let gfx = &mut ctx.gfx_context;
+    let (batches, others): (Vec<DebugBatch>, Vec<DebugBatch>) =
+        mem::replace(&mut gfx.debug_queue.batches, Vec::new())
+            .into_iter()
+            .partition(|batch| !gfx.is_inactive_window_target(&batch.target));
+    gfx.debug_queue.batches = others;
+    draw_batches(ctx, batches)
+}
+
+/// Draws the shapes queued for the current render target, a canvas
+/// that is about to be unset, and takes them out of the queue.
+pub(crate) fn flush_target(ctx: &mut Context) -> GameResult {
+    let gfx = &mut ctx.gfx_context;
+    let target = gfx.data.out.clone();
+    let (batches, others): (Vec<DebugBatch>, Vec<DebugBatch>) =
+        mem::replace(&mut gfx.debug_queue.batches, Vec::new())
+            .into_iter()
+            .partition(|batch| batch.target == target);
+    gfx.debug_queue.batches = others;
+    draw_batches(ctx, batches)
+}
+
+fn draw_batches(ctx: &mut Context, batches: Vec<DebugBatch>) -> GameResult {
+    if batches.is_empty() {
+        return Ok(());
+    }
+    let mut mesh = match ctx.gfx_context.debug_queue.mesh.take() {
+        Some(mesh) => mesh,
+        None => DynamicMesh::new(ctx, 1024, 1024, None)?,
+    };
+
+    // The geometry is already transformed, so draw it with each batch's
+    // projection on its target and the default shader, then put
+    // everything back.
+    let (old_target, old_projection, old_transform) = {
+        let gfx = &ctx.gfx_context;
+        (gfx.data.out.clone(), gfx.projection(), gfx.transform())
+    };
+    let old_shader = ctx.gfx_context.current_shader.borrow_mut().take();
+    ctx.gfx_context.set_transform(Matrix4::identity());
+    let result = batches.iter().try_for_each(|batch| {
+        mesh.set_data(ctx, batch.builder.vertices(), batch.builder.indices())?;
+        let gfx = &mut ctx.gfx_context;
+        gfx.set_projection(batch.projection);
+        // Also recalculates the MVP matrix, with the letterbox if the
+        // target is the window.
+        gfx.set_render_target(batch.target.clone());
+        mesh.draw(ctx, DrawParam::default())
+    });
+    {
+        let gfx = &mut ctx.gfx_context;
+        *gfx.current_shader.borrow_mut() = old_shader;
+        gfx.set_projection(old_projection);
+        gfx.set_transform(old_transform);
+        gfx.set_render_target(old_target);
+    }
+    ctx.gfx_context.debug_queue.mesh = Some(mesh);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn triangle() -> MeshBuilder {
+        let mut mb = MeshBuilder::new();
+        let _ = mb
+            .triangles(&[[0.0, 0.0], [1.0, 0.0], [0.0, 1.0]], WHITE)
+            .unwrap();
+        mb
+    }
+
+    #[test]
+    fn headless_test_debug_batches() {
+        let mut batches = Vec::new();
+        let identity = Matrix4::identity();
+        let other_projection = Matrix4::new_scaling(2.0);
+        push_shape(&mut batches, 1, identity, &identity, &triangle());
+        push_shape(&mut batches, 1, identity, &identity, &triangle());
+        push_shape(&mut batches, 2, identity, &identity, &triangle());
+        push_shape(&mut batches, 2, other_projection, &identity, &triangle());
+        // Going back to an earlier target starts a new batch, to keep
+        // the drawing order.
+        push_shape(&mut batches, 1, identity, &identity, &triangle());
+
+        let summary: Vec<(u32, usize)> = batches
+            .iter()
+            .map(|batch| (batch.target, batch.builder.indices().len()))
+            .collect();
+        assert_eq!(summary, vec![(1, 6), (2, 3), (2, 3), (1, 3)]);
+        assert_eq!(batches[2].projection, other_projection);
+    }
+
+    #[test]
+    fn headless_test_debug_spaces() {
+        let transform = Matrix4::new_translation(&na::Vector3::new(10.0, 20.0, 0.0));
+        let world = space_transform(Space::World, transform);
+        let screen = space_transform(Space::Screen, transform);
+        assert_eq!(transform_point(&world, [1.0, 1.0]), [11.0, 21.0]);
+        assert_eq!(transform_point(&screen, [1.0, 1.0]), [1.0, 1.0]);
+
+        // Queued geometry is stored already transformed.
+        let mut batches = Vec::new();
+        push_shape(&mut batches, 0, Matrix4::identity(), &world, &triangle());
+        let positions: Vec<[f32; 2]> = batches[0]
+            .builder
+            .vertices()
+            .iter()
+            .map(|v| v.pos)
+            .collect();
+        assert_eq!(positions, vec![[10.0, 20.0], [11.0, 20.0], [10.0, 21.0]]);
+    }
+}
@@ -244,9 +244,15 @@ impl From<DrawParam> for DrawTransform {
 }
 
 impl DrawTransform {
-    pub(crate) fn to_instance_properties(&self) -> InstanceProperties {
+    /// Converts the transform to what the shaders get, with the color
+    /// made linear if `srgb` is set; see `GraphicsContext::shader_color()`.
+    pub(crate) fn to_instance_properties(&self, srgb: bool) -> InstanceProperties {
         let mat: [[f32; 4]; 4] = self.matrix.into();
-        let color: [f32; 4] = self.color.into();
+        let color: [f32; 4] = if srgb {
+            LinearColor::from(self.color).into()
+        } else {
+            self.color.into()
+        };
         InstanceProperties {
             src: self.src.into(),
             col1: mat[0],
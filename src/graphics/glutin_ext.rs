@@ -66,6 +66,7 @@ impl ContextBuilderExt for glutin::ContextBuilder<'_, NotCurrent> {
         let alpha_bits = surface.get_alpha_stencil_bits();
 
         self.with_pixel_format(color_total_bits - alpha_bits, alpha_bits)
+            .with_srgb(channel == ChannelType::Srgb)
     }
 
     fn with_gfx_depth<Depth: DepthFormat>(self) -> Self {
@@ -189,6 +190,51 @@ impl WindowUpdateExt for glutin::WindowedContext<PossiblyCurrent> {
     }
 }
 
+/// A windowed context kept in a struct, which can be made current in
+/// place.  glutin's `make_current()` takes the context by value, so it
+/// is taken out of the `Option` for the call; if that panics, the
+/// context is gone and using it panics too, instead of touching one
+/// that was moved out.
+pub(crate) struct CurrentWindow(Option<glutin::WindowedContext<PossiblyCurrent>>);
+
+impl CurrentWindow {
+    pub(crate) fn new(window: glutin::WindowedContext<PossiblyCurrent>) -> Self {
+        CurrentWindow(Some(window))
+    }
+
+    /// Makes the window's context current.
+    pub(crate) fn make_current(&mut self) -> Result<(), glutin::ContextError> {
+        if self.is_current() {
+            return Ok(());
+        }
+        let window = self.0.take().expect(WINDOW_LOST);
+        // Safe since `make_current()` hands the context back whether it
+        // succeeds or not.
+        let (window, result) = match unsafe { window.make_current() } {
+            Ok(window) => (window, Ok(())),
+            Err((window, e)) => (window, Err(e)),
+        };
+        self.0 = Some(window);
+        result
+    }
+}
+
+const WINDOW_LOST: &str = "Window context lost while making it current; should never happen";
+
+impl std::ops::Deref for CurrentWindow {
+    type Target = glutin::WindowedContext<PossiblyCurrent>;
+
+    fn deref(&self) -> &Self::Target {
+        self.0.as_ref().expect(WINDOW_LOST)
+    }
+}
+
+impl std::ops::DerefMut for CurrentWindow {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.0.as_mut().expect(WINDOW_LOST)
+    }
+}
+
 fn get_window_dimensions(ctx: &glutin::WindowedContext<PossiblyCurrent>) -> texture::Dimensions {
     let window = ctx.window();
     let (width, height) = {
@@ -199,3 +245,665 @@ fn get_window_dimensions(ctx: &glutin::WindowedContext<PossiblyCurrent>) -> text
 
     (width, height, 1, aa.into())
 }
+
+/// Raw OpenGL entry points for resolving multisampled render targets,
+/// which gfx has no command for.
+pub(crate) struct MultisampleResolver {
+    gen_framebuffers: unsafe extern "system" fn(i32, *mut u32),
+    delete_framebuffers: unsafe extern "system" fn(i32, *const u32),
+    bind_framebuffer: unsafe extern "system" fn(u32, u32),
+    framebuffer_texture_2d: unsafe extern "system" fn(u32, u32, u32, u32, i32),
+    framebuffer_renderbuffer: unsafe extern "system" fn(u32, u32, u32, u32),
+    blit_framebuffer: unsafe extern "system" fn(i32, i32, i32, i32, i32, i32, i32, i32, u32, u32),
+    get_integerv: unsafe extern "system" fn(u32, *mut i32),
+}
+
+impl std::fmt::Debug for MultisampleResolver {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "<MultisampleResolver: {:p}>", self)
+    }
+}
+
+const GL_READ_FRAMEBUFFER: u32 = 0x8CA8;
+const GL_DRAW_FRAMEBUFFER: u32 = 0x8CA9;
+const GL_READ_FRAMEBUFFER_BINDING: u32 = 0x8CAA;
+const GL_DRAW_FRAMEBUFFER_BINDING: u32 = 0x8CA6;
+const GL_COLOR_ATTACHMENT0: u32 = 0x8CE0;
+const GL_TEXTURE_2D: u32 = 0x0DE1;
+const GL_TEXTURE_2D_MULTISAMPLE: u32 = 0x9100;
+const GL_RENDERBUFFER: u32 = 0x8D41;
+const GL_COLOR_BUFFER_BIT: u32 = 0x4000;
+const GL_NEAREST: u32 = 0x2600;
+
+impl MultisampleResolver {
+    /// Looks up the needed functions in the current context.  Returns
+    /// `None` if any of them are missing, as on GL versions before 3.0.
+    pub(crate) fn load(window: &glutin::WindowedContext<PossiblyCurrent>) -> Option<Self> {
+        macro_rules! load {
+            ($name:expr) => {{
+                let ptr = window.get_proc_address($name);
+                if ptr.is_null() {
+                    return None;
+                }
+                unsafe { std::mem::transmute(ptr) }
+            }};
+        }
+        Some(MultisampleResolver {
+            gen_framebuffers: load!("glGenFramebuffers"),
+            delete_framebuffers: load!("glDeleteFramebuffers"),
+            bind_framebuffer: load!("glBindFramebuffer"),
+            framebuffer_texture_2d: load!("glFramebufferTexture2D"),
+            framebuffer_renderbuffer: load!("glFramebufferRenderbuffer"),
+            blit_framebuffer: load!("glBlitFramebuffer"),
+            get_integerv: load!("glGetIntegerv"),
+        })
+    }
+
+    /// Blits `width` by `height` pixels from `src` into the single-sampled
+    /// texture `dst`, resolving the samples on the way.  The GL context
+    /// must be current and all rendering into `src` already flushed.
+    pub(crate) fn resolve(
+        &self,
+        src: &gfx_device_gl::NewTexture,
+        src_multisampled: bool,
+        dst: &gfx_device_gl::NewTexture,
+        width: u16,
+        height: u16,
+    ) -> Result<(), String> {
+        use gfx_device_gl::NewTexture;
+
+        let dst = match *dst {
+            NewTexture::Texture(name) => name,
+            NewTexture::Surface(_) => {
+                return Err("Can only resolve multisampled targets into textures".to_string())
+            }
+        };
+
+        // Safe as long as the names are live GL objects, which gfx
+        // guarantees for as long as we hold their handles.
+        unsafe {
+            self.blit(src, src_multisampled, dst, width, height);
+        }
+        Ok(())
+    }
+
+    unsafe fn blit(
+        &self,
+        src: &gfx_device_gl::NewTexture,
+        src_multisampled: bool,
+        dst: u32,
+        width: u16,
+        height: u16,
+    ) {
+        use gfx_device_gl::NewTexture;
+
+        let mut old_read = 0;
+        let mut old_draw = 0;
+        (self.get_integerv)(GL_READ_FRAMEBUFFER_BINDING, &mut old_read);
+        (self.get_integerv)(GL_DRAW_FRAMEBUFFER_BINDING, &mut old_draw);
+
+        let mut fbos = [0u32; 2];
+        (self.gen_framebuffers)(2, fbos.as_mut_ptr());
+        let [read_fbo, draw_fbo] = fbos;
+
+        match *src {
+            // Surface 0 is the window's own framebuffer.
+            NewTexture::Surface(0) => (self.bind_framebuffer)(GL_READ_FRAMEBUFFER, 0),
+            NewTexture::Surface(name) => {
+                (self.bind_framebuffer)(GL_READ_FRAMEBUFFER, read_fbo);
+                (self.framebuffer_renderbuffer)(
+                    GL_READ_FRAMEBUFFER,
+                    GL_COLOR_ATTACHMENT0,
+                    GL_RENDERBUFFER,
+                    name,
+                );
+            }
+            NewTexture::Texture(name) => {
+                let target = if src_multisampled {
+                    GL_TEXTURE_2D_MULTISAMPLE
+                } else {
+                    GL_TEXTURE_2D
+                };
+                (self.bind_framebuffer)(GL_READ_FRAMEBUFFER, read_fbo);
+                (self.framebuffer_texture_2d)(
+                    GL_READ_FRAMEBUFFER,
+                    GL_COLOR_ATTACHMENT0,
+                    target,
+                    name,
+                    0,
+                );
+            }
+        }
+        (self.bind_framebuffer)(GL_DRAW_FRAMEBUFFER, draw_fbo);
+        (self.framebuffer_texture_2d)(
+            GL_DRAW_FRAMEBUFFER,
+            GL_COLOR_ATTACHMENT0,
+            GL_TEXTURE_2D,
+            dst,
+            0,
+        );
+
+        let (w, h) = (i32::from(width), i32::from(height));
+        (self.blit_framebuffer)(0, 0, w, h, 0, 0, w, h, GL_COLOR_BUFFER_BIT, GL_NEAREST);
+
+        (self.bind_framebuffer)(GL_READ_FRAMEBUFFER, old_read as u32);
+        (self.bind_framebuffer)(GL_DRAW_FRAMEBUFFER, old_draw as u32);
+        (self.delete_framebuffers)(2, fbos.as_ptr());
+    }
+}
+
+/// Raw OpenGL entry points for fence syncs, which tell when the GPU has
+/// got through everything submitted before them.
+pub(crate) struct FenceSync {
+    fence_sync: unsafe extern "system" fn(u32, u32) -> *const std::ffi::c_void,
+    client_wait_sync: unsafe extern "system" fn(*const std::ffi::c_void, u32, u64) -> u32,
+    delete_sync: unsafe extern "system" fn(*const std::ffi::c_void),
+}
+
+impl std::fmt::Debug for FenceSync {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "<FenceSync: {:p}>", self)
+    }
+}
+
+/// A fence made by [`FenceSync::insert()`](struct.FenceSync.html#method.insert),
+/// which has to be given back to `FenceSync::delete()`.
+pub(crate) struct Fence(*const std::ffi::c_void);
+
+const GL_SYNC_GPU_COMMANDS_COMPLETE: u32 = 0x9117;
+const GL_TIMEOUT_EXPIRED: u32 = 0x911B;
+
+impl FenceSync {
+    /// Looks up the needed functions in the current context, if it is
+    /// OpenGL 3.2, OpenGL ES 3.0 or has `GL_ARB_sync`.  Returns `None`
+    /// otherwise, since some drivers hand out pointers for functions
+    /// they don't have.
+    pub(crate) fn load(
+        window: &glutin::WindowedContext<PossiblyCurrent>,
+        info: &gfx_device_gl::Info,
+    ) -> Option<Self> {
+        let version = &info.version;
+        let supported = if version.is_embedded {
+            version.major >= 3
+        } else {
+            (version.major, version.minor) >= (3, 2) || info.extensions.contains("GL_ARB_sync")
+        };
+        if !supported {
+            return None;
+        }
+        macro_rules! load {
+            ($name:expr) => {{
+                let ptr = window.get_proc_address($name);
+                if ptr.is_null() {
+                    return None;
+                }
+                unsafe { std::mem::transmute(ptr) }
+            }};
+        }
+        Some(FenceSync {
+            fence_sync: load!("glFenceSync"),
+            client_wait_sync: load!("glClientWaitSync"),
+            delete_sync: load!("glDeleteSync"),
+        })
+    }
+
+    /// Puts a fence after everything submitted to the GL so far.  The
+    /// GL context must be current.
+    pub(crate) fn insert(&self) -> Fence {
+        // Safe since it takes no pointers.
+        Fence(unsafe { (self.fence_sync)(GL_SYNC_GPU_COMMANDS_COMPLETE, 0) })
+    }
+
+    /// Returns whether the GPU has got past `fence`, without waiting.
+    /// Errors count as signaled, so that nothing waits for them forever;
+    /// reading what the fence guards then just blocks.
+    pub(crate) fn is_signaled(&self, fence: &Fence) -> bool {
+        if fence.0.is_null() {
+            return true;
+        }
+        // Safe since `fence` is live until it's given to `delete()`.
+        let status = unsafe { (self.client_wait_sync)(fence.0, 0, 0) };
+        status != GL_TIMEOUT_EXPIRED
+    }
+
+    /// Deletes a fence made by [`insert()`](#method.insert).
+    pub(crate) fn delete(&self, fence: Fence) {
+        if !fence.0.is_null() {
+            // Safe since taking `fence` by value means it's deleted once.
+            unsafe { (self.delete_sync)(fence.0) };
+        }
+    }
+}
+
+/// Raw OpenGL entry points for uploading compressed textures, which
+/// gfx has no formats for.
+pub(crate) struct CompressedUploader {
+    gen_textures: unsafe extern "system" fn(i32, *mut u32),
+    delete_textures: unsafe extern "system" fn(i32, *const u32),
+    bind_texture: unsafe extern "system" fn(u32, u32),
+    compressed_tex_image_2d:
+        unsafe extern "system" fn(u32, i32, u32, i32, i32, i32, i32, *const std::ffi::c_void),
+    tex_parameteri: unsafe extern "system" fn(u32, u32, i32),
+    get_integerv: unsafe extern "system" fn(u32, *mut i32),
+    get_error: unsafe extern "system" fn() -> u32,
+}
+
+impl std::fmt::Debug for CompressedUploader {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "<CompressedUploader: {:p}>", self)
+    }
+}
+
+const GL_TEXTURE_BINDING_2D: u32 = 0x8069;
+const GL_TEXTURE_BASE_LEVEL: u32 = 0x813C;
+const GL_TEXTURE_MAX_LEVEL: u32 = 0x813D;
+const GL_NO_ERROR: u32 = 0;
+
+impl CompressedUploader {
+    /// Looks up the needed functions in the current context.  Returns
+    /// `None` if any of them are missing.
+    pub(crate) fn load(window: &glutin::WindowedContext<PossiblyCurrent>) -> Option<Self> {
+        macro_rules! load {
+            ($name:expr) => {{
+                let ptr = window.get_proc_address($name);
+                if ptr.is_null() {
+                    return None;
+                }
+                unsafe { std::mem::transmute(ptr) }
+            }};
+        }
+        Some(CompressedUploader {
+            gen_textures: load!("glGenTextures"),
+            delete_textures: load!("glDeleteTextures"),
+            bind_texture: load!("glBindTexture"),
+            compressed_tex_image_2d: load!("glCompressedTexImage2D"),
+            tex_parameteri: load!("glTexParameteri"),
+            get_integerv: load!("glGetIntegerv"),
+            get_error: load!("glGetError"),
+        })
+    }
+
+    /// Creates a 2D texture in the given compressed `internal_format`
+    /// out of its mipmap levels, each given as width, height and data,
+    /// and returns its GL name.  The GL context must be current.
+    pub(crate) fn upload(
+        &self,
+        internal_format: u32,
+        levels: &[(u16, u16, &[u8])],
+    ) -> Result<u32, String> {
+        // Safe as long as every level holds as many bytes as its size
+        // needs in the given format, which the caller checks.
+        unsafe { self.upload_levels(internal_format, levels) }
+    }
+
+    unsafe fn upload_levels(
+        &self,
+        internal_format: u32,
+        levels: &[(u16, u16, &[u8])],
+    ) -> Result<u32, String> {
+        // Don't blame errors from earlier calls on this upload.
+        for _ in 0..16 {
+            if (self.get_error)() == GL_NO_ERROR {
+                break;
+            }
+        }
+        let mut old_binding = 0;
+        (self.get_integerv)(GL_TEXTURE_BINDING_2D, &mut old_binding);
+
+        let mut name = 0;
+        (self.gen_textures)(1, &mut name);
+        (self.bind_texture)(GL_TEXTURE_2D, name);
+        for (level, &(width, height, data)) in levels.iter().enumerate() {
+            (self.compressed_tex_image_2d)(
+                GL_TEXTURE_2D,
+                level as i32,
+                internal_format,
+                i32::from(width),
+                i32::from(height),
+                0,
+                data.len() as i32,
+                data.as_ptr() as *const std::ffi::c_void,
+            );
+        }
+        (self.tex_parameteri)(GL_TEXTURE_2D, GL_TEXTURE_BASE_LEVEL, 0);
+        (self.tex_parameteri)(
+            GL_TEXTURE_2D,
+            GL_TEXTURE_MAX_LEVEL,
+            levels.len().saturating_sub(1) as i32,
+        );
+        let error = (self.get_error)();
+        (self.bind_texture)(GL_TEXTURE_2D, old_binding as u32);
+
+        if error == GL_NO_ERROR {
+            Ok(name)
+        } else {
+            (self.delete_textures)(1, &name);
+            Err(format!(
+                "Uploading compressed texture format 0x{:X} failed with GL error 0x{:X}",
+                internal_format, error
+            ))
+        }
+    }
+
+    /// Deletes textures created by [`upload()`](#method.upload).
+    pub(crate) fn delete(&self, names: &[u32]) {
+        if names.is_empty() {
+            return;
+        }
+        // Safe since gfx never sees these names, so they are only
+        // deleted here, once.
+        unsafe {
+            (self.delete_textures)(names.len() as i32, names.as_ptr());
+        }
+    }
+}
+
+/// A platform's function for changing the swap interval of the current
+/// context, which glutin only sets on creation.
+enum SwapIntervalFn {
+    #[cfg(not(any(target_os = "macos", target_os = "ios")))]
+    Egl(
+        unsafe extern "system" fn(*const std::ffi::c_void, i32) -> u32,
+        *const std::ffi::c_void,
+    ),
+    #[cfg(windows)]
+    Wgl(unsafe extern "system" fn(i32) -> i32),
+    /// `glXSwapIntervalEXT()`, which sets the interval of a drawable
+    /// rather than of the current context, so it is given the current
+    /// one from `glXGetCurrentDrawable()`.
+    #[cfg(any(
+        target_os = "linux",
+        target_os = "dragonfly",
+        target_os = "freebsd",
+        target_os = "netbsd",
+        target_os = "openbsd"
+    ))]
+    GlxExt {
+        swap_interval: unsafe extern "C" fn(*mut std::ffi::c_void, std::os::raw::c_ulong, i32),
+        current_drawable: unsafe extern "C" fn() -> std::os::raw::c_ulong,
+        display: *mut std::ffi::c_void,
+    },
+    #[cfg(any(
+        target_os = "linux",
+        target_os = "dragonfly",
+        target_os = "freebsd",
+        target_os = "netbsd",
+        target_os = "openbsd"
+    ))]
+    GlxMesa(unsafe extern "C" fn(u32) -> i32),
+    #[cfg(any(
+        target_os = "linux",
+        target_os = "dragonfly",
+        target_os = "freebsd",
+        target_os = "netbsd",
+        target_os = "openbsd"
+    ))]
+    GlxSgi(unsafe extern "C" fn(i32) -> i32),
+}
+
+/// Raw entry points for changing the swap interval, i.e. vsync, of a
+/// window at runtime.
+pub(crate) struct SwapInterval {
+    function: SwapIntervalFn,
+    /// Whether the driver takes negative intervals, for adaptive vsync.
+    adaptive: bool,
+}
+
+impl std::fmt::Debug for SwapInterval {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "<SwapInterval: {:p}>", self)
+    }
+}
+
+/// Returns `true` if the space separated list of `extensions` has `name`.
+fn has_extension(extensions: &str, name: &str) -> bool {
+    extensions
+        .split_whitespace()
+        .any(|extension| extension == name)
+}
+
+/// Copies a C string returned by GL, if there is one.
+///
+/// # Safety
+///
+/// `ptr` has to be null or point to a nul-terminated string.
+unsafe fn gl_string(ptr: *const std::os::raw::c_char) -> Option<String> {
+    if ptr.is_null() {
+        None
+    } else {
+        Some(std::ffi::CStr::from_ptr(ptr).to_string_lossy().into_owned())
+    }
+}
+
+impl SwapInterval {
+    /// Looks up the platform's swap interval function for the current
+    /// context: `eglSwapInterval()` for EGL contexts, or the one of the
+    /// `WGL_EXT_swap_control`, `GLX_EXT_swap_control`,
+    /// `GLX_MESA_swap_control` or `GLX_SGI_swap_control` extension the
+    /// driver lists.  Returns `None` if there is none, as on macOS.
+    pub(crate) fn load(window: &glutin::WindowedContext<PossiblyCurrent>) -> Option<Self> {
+        #[cfg(not(any(target_os = "macos", target_os = "ios")))]
+        {
+            use glutin::platform::ContextTraitExt;
+            // Safe since the display lives as long as the context does.
+            let display = unsafe { window.get_egl_display() };
+            if let Some(display) = display {
+                // Core since EGL 1.1, so there is no extension to check.
+                let ptr = window.get_proc_address("eglSwapInterval");
+                if ptr.is_null() {
+                    return None;
+                }
+                let function = unsafe { std::mem::transmute(ptr) };
+                return Some(SwapInterval {
+                    function: SwapIntervalFn::Egl(function, display),
+                    adaptive: false,
+                });
+            }
+        }
+        Self::load_platform(window)
+    }
+
+    #[cfg(windows)]
+    fn load_platform(window: &glutin::WindowedContext<PossiblyCurrent>) -> Option<Self> {
+        let get_extensions = window.get_proc_address("wglGetExtensionsStringEXT");
+        if get_extensions.is_null() {
+            return None;
+        }
+        // Safe since it takes nothing and returns a string that lives
+        // as long as the context.
+        let extensions = unsafe {
+            let get_extensions: unsafe extern "system" fn() -> *const std::os::raw::c_char =
+                std::mem::transmute(get_extensions);
+            gl_string(get_extensions())?
+        };
+        if !has_extension(&extensions, "WGL_EXT_swap_control") {
+            return None;
+        }
+        let ptr = window.get_proc_address("wglSwapIntervalEXT");
+        if ptr.is_null() {
+            return None;
+        }
+        Some(SwapInterval {
+            function: SwapIntervalFn::Wgl(unsafe { std::mem::transmute(ptr) }),
+            adaptive: has_extension(&extensions, "WGL_EXT_swap_control_tear"),
+        })
+    }
+
+    #[cfg(any(
+        target_os = "linux",
+        target_os = "dragonfly",
+        target_os = "freebsd",
+        target_os = "netbsd",
+        target_os = "openbsd"
+    ))]
+    fn load_platform(window: &glutin::WindowedContext<PossiblyCurrent>) -> Option<Self> {
+        use winit::platform::unix::WindowExtUnix;
+        // Not an X11 window, e.g. a Wayland one without EGL.
+        let display = window.window().xlib_display()?;
+        let screen = window.window().xlib_screen_id()?;
+        let load = |name| {
+            let ptr = window.get_proc_address(name);
+            if ptr.is_null() {
+                None
+            } else {
+                Some(ptr)
+            }
+        };
+        // Safe since the display is open as long as the window is.
+        let extensions = unsafe {
+            let query: unsafe extern "C" fn(
+                *mut std::ffi::c_void,
+                std::os::raw::c_int,
+            ) -> *const std::os::raw::c_char =
+                std::mem::transmute(load("glXQueryExtensionsString")?);
+            gl_string(query(display, screen))?
+        };
+        let function = if has_extension(&extensions, "GLX_EXT_swap_control") {
+            SwapIntervalFn::GlxExt {
+                swap_interval: unsafe { std::mem::transmute(load("glXSwapIntervalEXT")?) },
+                current_drawable: unsafe { std::mem::transmute(load("glXGetCurrentDrawable")?) },
+                display,
+            }
+        } else if has_extension(&extensions, "GLX_MESA_swap_control") {
+            SwapIntervalFn::GlxMesa(unsafe { std::mem::transmute(load("glXSwapIntervalMESA")?) })
+        } else if has_extension(&extensions, "GLX_SGI_swap_control") {
+            SwapIntervalFn::GlxSgi(unsafe { std::mem::transmute(load("glXSwapIntervalSGI")?) })
+        } else {
+            return None;
+        };
+        let adaptive = match function {
+            SwapIntervalFn::GlxExt { .. } => {
+                has_extension(&extensions, "GLX_EXT_swap_control_tear")
+            }
+            _ => false,
+        };
+        Some(SwapInterval { function, adaptive })
+    }
+
+    #[cfg(not(any(
+        windows,
+        target_os = "linux",
+        target_os = "dragonfly",
+        target_os = "freebsd",
+        target_os = "netbsd",
+        target_os = "openbsd"
+    )))]
+    fn load_platform(_window: &glutin::WindowedContext<PossiblyCurrent>) -> Option<Self> {
+        None
+    }
+
+    /// Whether negative intervals, for adaptive vsync, work, which
+    /// takes `WGL_EXT_swap_control_tear` or `GLX_EXT_swap_control_tear`.
+    pub(crate) fn supports_adaptive(&self) -> bool {
+        self.adaptive
+    }
+
+    /// Sets the swap interval of the current context: the number of
+    /// vertical blanks to wait for before swapping buffers, 0 for none,
+    /// or negative for adaptive vsync, which doesn't wait when a frame
+    /// is late.  The window's context must be current.
+    pub(crate) fn set(&self, interval: i32) -> Result<(), String> {
+        if interval < 0 && !self.adaptive {
+            return Err(format!(
+                "The driver doesn't support adaptive swap interval {}",
+                interval
+            ));
+        }
+        // Safe since the functions were loaded for this kind of
+        // context, and take plain integers or the handles they were
+        // loaded with.
+        let ok = unsafe {
+            match self.function {
+                #[cfg(not(any(target_os = "macos", target_os = "ios")))]
+                SwapIntervalFn::Egl(swap_interval, display) => {
+                    swap_interval(display, interval) != 0
+                }
+                #[cfg(windows)]
+                SwapIntervalFn::Wgl(swap_interval) => swap_interval(interval) != 0,
+                #[cfg(any(
+                    target_os = "linux",
+                    target_os = "dragonfly",
+                    target_os = "freebsd",
+                    target_os = "netbsd",
+                    target_os = "openbsd"
+                ))]
+                SwapIntervalFn::GlxExt {
+                    swap_interval,
+                    current_drawable,
+                    display,
+                } => {
+                    // Errors are reported to the X error handler, so
+                    // this can only tell whether there is a drawable.
+                    let drawable = current_drawable();
+                    if drawable != 0 {
+                        swap_interval(display, drawable, interval);
+                    }
+                    drawable != 0
+                }
+                #[cfg(any(
+                    target_os = "linux",
+                    target_os = "dragonfly",
+                    target_os = "freebsd",
+                    target_os = "netbsd",
+                    target_os = "openbsd"
+                ))]
+                SwapIntervalFn::GlxMesa(swap_interval) => {
+                    interval >= 0 && swap_interval(interval as u32) == 0
+                }
+                // The SGI extension can't turn vsync off.
+                #[cfg(any(
+                    target_os = "linux",
+                    target_os = "dragonfly",
+                    target_os = "freebsd",
+                    target_os = "netbsd",
+                    target_os = "openbsd"
+                ))]
+                SwapIntervalFn::GlxSgi(swap_interval) => {
+                    interval > 0 && swap_interval(interval) == 0
+                }
+            }
+        };
+        if ok {
+            Ok(())
+        } else {
+            Err(format!("The driver refused swap interval {}", interval))
+        }
+    }
+}
+
+/// Destroys the EGL surface of `window`, on Android, whose native
+/// window goes away when the app is suspended.  The context survives
+/// with no surface until `create_surface()`.  Does nothing elsewhere.
+pub(crate) fn destroy_surface(window: &glutin::WindowedContext<PossiblyCurrent>) {
+    #[cfg(target_os = "android")]
+    {
+        use glutin::platform::ContextTraitExt;
+        // Safe since nothing is drawn to the surface until it's made
+        // again.
+        unsafe { window.context().surface_destroyed() };
+    }
+    #[cfg(not(target_os = "android"))]
+    let _ = window;
+}
+
+/// Makes a new EGL surface for `window` on the activity's current
+/// native window, on Android, after `destroy_surface()`.  Does nothing
+/// elsewhere.
+pub(crate) fn create_surface(window: &glutin::WindowedContext<PossiblyCurrent>) -> Result<(), String> {
+    #[cfg(target_os = "android")]
+    {
+        use glutin::platform::ContextTraitExt;
+        let native_window = ndk_glue::native_window();
+        let native_window = native_window
+            .as_ref()
+            .ok_or_else(|| "There is no native window to draw to".to_string())?;
+        // Safe since the native window is alive while it's locked.
+        unsafe {
+            let handle = native_window.ptr().as_ptr() as glutin_egl_sys::EGLNativeWindowType;
+            window.context().surface_created(handle);
+        }
+    }
+    #[cfg(not(target_os = "android"))]
+    let _ = window;
+    Ok(())
+}
@@ -0,0 +1,301 @@
+//! Linear and radial color gradients, for filling meshes per vertex
+//! or through a texture rendered from them.
+
+use crate::context::Context;
+use crate::error::GameResult;
+use crate::graphics::*;
+
+/// What a [`Gradient`](struct.Gradient.html) does beyond its first
+/// and last stops.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum ExtendMode {
+    /// Keep using the color of the nearest end stop.
+    Pad,
+    /// Start the gradient over again.
+    Repeat,
+    /// Run the gradient backwards, then forwards again, and so on.
+    Reflect,
+}
+
+impl Default for ExtendMode {
+    fn default() -> Self {
+        ExtendMode::Pad
+    }
+}
+
+/// The geometry of a [`Gradient`](struct.Gradient.html).
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum GradientShape {
+    /// Colors change along the line from `start` (offset `0.0`)
+    /// to `end` (offset `1.0`), and stay constant perpendicular to it.
+    Linear {
+        /// Where offset `0.0` lies.
+        start: mint::Point2<f32>,
+        /// Where offset `1.0` lies.
+        end: mint::Point2<f32>,
+    },
+    /// Colors change with the distance from `center`, reaching
+    /// offset `1.0` at `radius`.
+    Radial {
+        /// Where offset `0.0` lies.
+        center: mint::Point2<f32>,
+        /// The distance from `center` at which offset `1.0` lies.
+        radius: f32,
+    },
+}
+
+/// A linear or radial color gradient, for filling shapes in a
+/// [`MeshBuilder`](struct.MeshBuilder.html) with something other than a
+/// flat color.
+///
+/// A gradient is made of color stops at offsets between `0.0` and
+/// `1.0`, and colors in between stops are interpolated.  All
+/// coordinates are in the same space as the mesh's vertices.
+///
+/// ```rust,no_run
+/// # use ggez::*;
+/// # use ggez::graphics::*;
+/// # fn main() -> GameResult {
+/// # let ctx = &mut ContextBuilder::new("foo", "bar").build().unwrap().0;
+/// let sky = Gradient::linear([0.0, 0.0], [0.0, 600.0])
+///     .stop(0.0, Color::from_rgb(20, 40, 120))
+///     .stop(0.7, Color::from_rgb(120, 160, 255))
+///     .stop(1.0, WHITE);
+/// let mesh = Mesh::new_rectangle_gradient(
+///     ctx,
+///     DrawMode::fill(),
+///     Rect::new(0.0, 0.0, 800.0, 600.0),
+///     &sky,
+/// )?;
+/// # Ok(()) }
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Gradient {
+    shape: GradientShape,
+    stops: Vec<(f32, Color)>,
+    extend: ExtendMode,
+}
+
+impl Gradient {
+    /// Creates a new linear gradient from `start` to `end`, with no stops.
+    pub fn linear<P>(start: P, end: P) -> Self
+    where
+        P: Into<mint::Point2<f32>>,
+    {
+        Gradient {
+            shape: GradientShape::Linear {
+                start: start.into(),
+                end: end.into(),
+            },
+            stops: Vec::new(),
+            extend: ExtendMode::default(),
+        }
+    }
+
+    /// Creates a new radial gradient around `center`, with no stops.
+    pub fn radial<P>(center: P, radius: f32) -> Self
+    where
+        P: Into<mint::Point2<f32>>,
+    {
+        Gradient {
+            shape: GradientShape::Radial {
+                center: center.into(),
+                radius,
+            },
+            stops: Vec::new(),
+            extend: ExtendMode::default(),
+        }
+    }
+
+    /// Adds a color stop at the given offset, which is clamped to `0.0..=1.0`.
+    /// Stops may be added in any order.
+    pub fn stop(mut self, offset: f32, color: Color) -> Self {
+        let offset = offset.max(0.0).min(1.0);
+        // Insert after any stops at the same offset, so that two stops
+        // at one offset make a hard edge in the order they were added.
+        let index = self
+            .stops
+            .iter()
+            .position(|&(o, _)| o > offset)
+            .unwrap_or_else(|| self.stops.len());
+        self.stops.insert(index, (offset, color));
+        self
+    }
+
+    /// Sets what happens beyond the ends of the gradient.
+    /// The default is [`ExtendMode::Pad`](enum.ExtendMode.html#variant.Pad).
+    pub fn extend(mut self, extend: ExtendMode) -> Self {
+        self.extend = extend;
+        self
+    }
+
+    /// Returns the gradient's geometry.
+    pub fn shape(&self) -> GradientShape {
+        self.shape
+    }
+
+    /// Returns the gradient's color stops, sorted by offset.
+    pub fn stops(&self) -> &[(f32, Color)] {
+        &self.stops
+    }
+
+    /// Returns the gradient's extend mode.
+    pub fn extend_mode(&self) -> ExtendMode {
+        self.extend
+    }
+
+    /// Returns the gradient's color at the given point.  A gradient
+    /// without stops is transparent black everywhere.
+    pub fn color_at<P>(&self, point: P) -> Color
+    where
+        P: Into<mint::Point2<f32>>,
+    {
+        let point: mint::Point2<f32> = point.into();
+        let point = Point2::from(point);
+        let offset = match self.shape {
+            GradientShape::Linear { start, end } => {
+                let start: Point2 = start.into();
+                let direction = Point2::from(end) - start;
+                let length_squared = direction.norm_squared();
+                if length_squared > 0.0 {
+                    (point - start).dot(&direction) / length_squared
+                } else {
+                    0.0
+                }
+            }
+            GradientShape::Radial { center, radius } => {
+                if radius > 0.0 {
+                    na::distance(&point, &Point2::from(center)) / radius
+                } else {
+                    1.0
+                }
+            }
+        };
+        self.color_at_offset(offset)
+    }
+
+    /// Returns the gradient's color at the given offset, applying the
+    /// extend mode to offsets outside of `0.0..=1.0`.
+    pub fn color_at_offset(&self, offset: f32) -> Color {
+        let offset = match self.extend {
+            ExtendMode::Pad => offset.max(0.0).min(1.0),
+            ExtendMode::Repeat => offset - offset.floor(),
+            ExtendMode::Reflect => {
+                let m = offset.abs() % 2.0;
+                if m > 1.0 {
+                    2.0 - m
+                } else {
+                    m
+                }
+            }
+        };
+        let (first, last) = match (self.stops.first(), self.stops.last()) {
+            (Some(first), Some(last)) => (*first, *last),
+            _ => return Color::new(0.0, 0.0, 0.0, 0.0),
+        };
+        if offset >= last.0 {
+            return last.1;
+        }
+        if offset <= first.0 {
+            return first.1;
+        }
+        for pair in self.stops.windows(2) {
+            let ((o1, c1), (o2, c2)) = (pair[0], pair[1]);
+            if offset >= o1 && offset < o2 {
+                let t = (offset - o1) / (o2 - o1);
+                let lerp = |a: f32, b: f32| a + (b - a) * t;
+                return Color::new(
+                    lerp(c1.r, c2.r),
+                    lerp(c1.g, c2.g),
+                    lerp(c1.b, c2.b),
+                    lerp(c1.a, c2.a),
+                );
+            }
+        }
+        last.1
+    }
+
+    /// Renders the part of the gradient that covers `bounds` into a new
+    /// `width` by `height` `Image`.
+    ///
+    /// This is what [`MeshBuilder::texture_gradient()`](struct.MeshBuilder.html#method.texture_gradient)
+    /// uses, but the image can be drawn on its own as well.
+    pub fn to_image(
+        &self,
+        ctx: &mut Context,
+        bounds: Rect,
+        width: u16,
+        height: u16,
+    ) -> GameResult<Image> {
+        let (w, h) = (f32::from(width.max(1)), f32::from(height.max(1)));
+        let mut rgba = Vec::with_capacity(usize::from(width) * usize::from(height) * 4);
+        for y in 0..height {
+            for x in 0..width {
+                // Sample at texel centers.
+                let point = Point2::new(
+                    bounds.x + (f32::from(x) + 0.5) / w * bounds.w,
+                    bounds.y + (f32::from(y) + 0.5) / h * bounds.h,
+                );
+                let (r, g, b, a) = self.color_at(point).into();
+                rgba.extend_from_slice(&[r, g, b, a]);
+            }
+        }
+        let mut image = Image::from_rgba8(ctx, width, height, &rgba)?;
+        image.set_wrap(WrapMode::Clamp, WrapMode::Clamp);
+        Ok(image)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn headless_test_linear_gradient() {
+        let gradient = Gradient::linear([0.0, 0.0], [10.0, 0.0])
+            .stop(1.0, WHITE)
+            .stop(0.0, BLACK);
+        assert_eq!(gradient.color_at([0.0, 5.0]), BLACK);
+        assert_eq!(gradient.color_at([10.0, -3.0]), WHITE);
+        assert_eq!(
+            gradient.color_at([5.0, 0.0]),
+            Color::new(0.5, 0.5, 0.5, 1.0)
+        );
+        // Pad is the default.
+        assert_eq!(gradient.color_at([20.0, 0.0]), WHITE);
+        assert_eq!(gradient.color_at([-20.0, 0.0]), BLACK);
+    }
+
+    #[test]
+    fn headless_test_extend_modes() {
+        let gradient = Gradient::radial([0.0, 0.0], 4.0)
+            .stop(0.0, BLACK)
+            .stop(1.0, WHITE);
+        let repeat = gradient.clone().extend(ExtendMode::Repeat);
+        let reflect = gradient.extend(ExtendMode::Reflect);
+        assert_eq!(
+            repeat.color_at([5.0, 0.0]),
+            Color::new(0.25, 0.25, 0.25, 1.0)
+        );
+        assert_eq!(
+            reflect.color_at([0.0, 5.0]),
+            Color::new(0.75, 0.75, 0.75, 1.0)
+        );
+        assert_eq!(reflect.color_at_offset(-0.25), repeat.color_at_offset(0.25));
+    }
+
+    #[test]
+    fn headless_test_gradient_stops() {
+        assert_eq!(
+            Gradient::linear([0.0, 0.0], [1.0, 0.0]).color_at_offset(0.5),
+            Color::new(0.0, 0.0, 0.0, 0.0)
+        );
+        let red = Color::new(1.0, 0.0, 0.0, 1.0);
+        let hard_edge = Gradient::linear([0.0, 0.0], [1.0, 0.0])
+            .stop(0.5, BLACK)
+            .stop(0.5, red);
+        assert_eq!(hard_edge.color_at_offset(0.49), BLACK);
+        assert_eq!(hard_edge.color_at_offset(0.5), red);
+        assert_eq!(hard_edge.stops().len(), 2);
+    }
+}
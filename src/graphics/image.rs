@@ -1,5 +1,7 @@
 use std::io::Read;
 use std::path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 
 use ::image;
 use gfx;
@@ -9,6 +11,8 @@ use crate::error::GameError;
 use crate::error::GameResult;
 use crate::filesystem;
 use crate::graphics;
+use crate::graphics::codecs;
+use crate::graphics::ktx;
 use crate::graphics::shader::*;
 use crate::graphics::*;
 
@@ -25,10 +29,55 @@ where
     pub(crate) blend_mode: Option<BlendMode>,
     pub(crate) width: u16,
     pub(crate) height: u16,
+    /// For the image of a multisampled `Canvas`, the texture that is
+    /// actually rendered to and has to be resolved into this one.
+    pub(crate) multisample_source: Option<MultisampleSource<B::Resources>>,
+    /// For images loaded from a KTX file in a compressed format, the
+    /// GL texture that gfx doesn't own.
+    pub(crate) compressed: Option<Arc<ktx::CompressedStorage>>,
 
     pub(crate) debug_id: DebugId,
 }
 
+/// The multisampled texture a `Canvas` renders to, and whether it has
+/// been drawn to since it was last resolved.  Shared by all the copies
+/// of the canvas's image.
+#[derive(Clone, Debug)]
+pub(crate) struct MultisampleSource<R>
+where
+    R: gfx::Resources,
+{
+    pub(crate) texture: gfx::handle::RawTexture<R>,
+    dirty: Arc<AtomicBool>,
+}
+
+impl<R> MultisampleSource<R>
+where
+    R: gfx::Resources,
+{
+    pub(crate) fn new(texture: gfx::handle::RawTexture<R>) -> Self {
+        MultisampleSource {
+            texture,
+            dirty: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// Notes that the canvas is being drawn to, so it has to be resolved
+    /// before its image is used.
+    pub(crate) fn mark_dirty(&self) {
+        self.dirty.store(true, Ordering::SeqCst);
+    }
+}
+
+impl<R> PartialEq for MultisampleSource<R>
+where
+    R: gfx::Resources,
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.texture == other.texture
+    }
+}
+
 impl<B> ImageGeneric<B>
 where
     B: BackendSpec,
@@ -43,6 +92,7 @@ where
         height: u16,
         rgba: &[u8],
         color_format: gfx::format::Format,
+        mipmaps: bool,
         debug_id: DebugId,
     ) -> GameResult<Self> {
         if width == 0 || height == 0 {
@@ -80,9 +130,14 @@ where
         let kind = gfx::texture::Kind::D2(width, height, gfx::texture::AaMode::Single);
         use gfx::memory::Bind;
         let gfx::format::Format(surface_format, channel_type) = color_format;
+        let levels = if mipmaps {
+            mipmap_count(width, height)
+        } else {
+            1
+        };
         let texinfo = gfx::texture::Info {
             kind,
-            levels: 1,
+            levels,
             format: surface_format,
             bind: Bind::SHADER_RESOURCE
                 | Bind::RENDER_TARGET
@@ -90,11 +145,15 @@ where
                 | Bind::TRANSFER_DST,
             usage: gfx::memory::Usage::Dynamic,
         };
-        let raw_tex = factory.create_texture_raw(
-            texinfo,
-            Some(channel_type),
-            Some((&[rgba], gfx::texture::Mipmap::Provided)),
-        )?;
+        // Only the first level is given; any others get generated by
+        // `Image::generate_mipmaps()`.
+        let mipmap = if levels > 1 {
+            gfx::texture::Mipmap::Allocated
+        } else {
+            gfx::texture::Mipmap::Provided
+        };
+        let raw_tex =
+            factory.create_texture_raw(texinfo, Some(channel_type), Some((&[rgba], mipmap)))?;
         let resource_desc = gfx::texture::ResourceDesc {
             channel: channel_type,
             layer: None,
@@ -110,11 +169,82 @@ where
             blend_mode: None,
             width,
             height,
+            multisample_source: None,
+            compressed: None,
             debug_id,
         })
     }
 }
 
+/// Options for creating an [`Image`](type.Image.html) with
+/// [`Image::new_with_options()`](struct.ImageGeneric.html#method.new_with_options)
+/// or [`Image::from_rgba8_with_options()`](struct.ImageGeneric.html#method.from_rgba8_with_options).
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub struct ImageOptions {
+    /// Whether to give the image a full chain of mipmaps.
+    pub mipmaps: bool,
+    /// The color space of the image's pixels.
+    pub color_space: ColorSpace,
+}
+
+impl ImageOptions {
+    /// Set whether the image gets a full chain of mipmaps.
+    pub fn mipmaps(mut self, mipmaps: bool) -> Self {
+        self.mipmaps = mipmaps;
+        self
+    }
+
+    /// Set the color space of the image's pixels.  Use
+    /// `ColorSpace::Linear` for normal maps, lookup tables and other
+    /// data that isn't a picture.
+    pub fn color_space(mut self, color_space: ColorSpace) -> Self {
+        self.color_space = color_space;
+        self
+    }
+}
+
+/// Whether textures with the given surface hold floating point HDR
+/// colors rather than 8-bit ones.
+pub(crate) fn is_hdr_surface(surface: gfx::format::SurfaceType) -> bool {
+    match surface {
+        gfx::format::SurfaceType::R16_G16_B16_A16
+        | gfx::format::SurfaceType::R32_G32_B32_A32
+        | gfx::format::SurfaceType::R11_G11_B10 => true,
+        _ => false,
+    }
+}
+
+/// Clamps a floating point color to what fits in 8 bits, encoding it
+/// as sRGB if it is linear because the pipeline is sRGB.
+pub(crate) fn hdr_to_rgba8(pixel: [f32; 4], srgb: bool) -> [u8; 4] {
+    let clamp = |c: f32| c.max(0.0).min(1.0);
+    let color = Color::new(
+        clamp(pixel[0]),
+        clamp(pixel[1]),
+        clamp(pixel[2]),
+        clamp(pixel[3]),
+    );
+    let color = if srgb {
+        Color::from(LinearColor {
+            r: color.r,
+            g: color.g,
+            b: color.b,
+            a: color.a,
+        })
+    } else {
+        color
+    };
+    let (r, g, b, a) = color.to_rgba();
+    [r, g, b, a]
+}
+
+/// The number of mipmap levels of a full chain for the given size,
+/// down to 1x1.
+pub(crate) fn mipmap_count(width: u16, height: u16) -> u8 {
+    let largest = width.max(height).max(1);
+    (16 - largest.leading_zeros()) as u8
+}
+
 /// In-GPU-memory image data available to be drawn on the screen,
 /// using the OpenGL backend.
 ///
@@ -124,24 +254,182 @@ where
 pub type Image = ImageGeneric<GlBackendSpec>;
 
 /// The supported formats for saving an image.
+///
+/// Saving as JPEG, TGA or QOI needs the cargo feature of the same
+/// name, and fails without it; the same features control which formats
+/// [`Image::new()`](struct.Image.html#method.new) can load.
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum ImageFormat {
     /// .png image format (defaults to RGBA with 8-bit channels.)
     Png,
+    /// .jpg image format with the given quality from 1 to 100.  JPEG
+    /// has no alpha channel, so transparency is lost.
+    Jpeg(u8),
+    /// .bmp image format, with 32-bit RGBA pixels.
+    Bmp,
+    /// .tga image format, uncompressed with 32-bit RGBA pixels.
+    Tga,
+    /// .qoi image format (see <https://qoiformat.org/>), lossless and
+    /// much faster to encode than PNG.
+    Qoi,
+}
+
+/// Encodes RGBA bytes, row by row from the top, in the given format.
+pub(crate) fn encode_rgba8<W: std::io::Write>(
+    mut writer: W,
+    format: ImageFormat,
+    data: &[u8],
+    width: u16,
+    height: u16,
+) -> GameResult {
+    let color_format = image::ColorType::RGBA(8);
+    match format {
+        ImageFormat::Png => image::png::PNGEncoder::new(writer)
+            .encode(data, u32::from(width), u32::from(height), color_format)
+            .map_err(Into::into),
+        #[cfg(feature = "jpeg")]
+        ImageFormat::Jpeg(quality) => {
+            let rgb: Vec<u8> = data
+                .chunks_exact(4)
+                .flat_map(|pixel| pixel[..3].iter().cloned())
+                .collect();
+            image::jpeg::JPEGEncoder::new_with_quality(&mut writer, quality.max(1).min(100))
+                .encode(
+                    &rgb,
+                    u32::from(width),
+                    u32::from(height),
+                    image::ColorType::RGB(8),
+                )
+                .map_err(Into::into)
+        }
+        #[cfg(not(feature = "jpeg"))]
+        ImageFormat::Jpeg(_) => Err(missing_encoder(format, "jpeg")),
+        ImageFormat::Bmp => image::bmp::BMPEncoder::new(&mut writer)
+            .encode(data, u32::from(width), u32::from(height), color_format)
+            .map_err(Into::into),
+        #[cfg(feature = "tga")]
+        ImageFormat::Tga => writer
+            .write_all(&codecs::encode_tga(data, width, height))
+            .map_err(Into::into),
+        #[cfg(not(feature = "tga"))]
+        ImageFormat::Tga => Err(missing_encoder(format, "tga")),
+        #[cfg(feature = "qoi")]
+        ImageFormat::Qoi => writer
+            .write_all(&codecs::encode_qoi(data, width, height))
+            .map_err(Into::into),
+        #[cfg(not(feature = "qoi"))]
+        ImageFormat::Qoi => Err(missing_encoder(format, "qoi")),
+    }
+}
+
+/// The error for saving in a format whose cargo feature is off.
+#[cfg(not(all(feature = "jpeg", feature = "tga", feature = "qoi")))]
+fn missing_encoder(format: ImageFormat, feature: &str) -> GameError {
+    GameError::InvalidValue(format!(
+        "Saving images as {:?} needs the `{}` feature of ggez",
+        format, feature
+    ))
 }
 
 impl Image {
+    /// If this is the image of a multisampled `Canvas` that has been
+    /// drawn to since it was last resolved, resolves everything rendered
+    /// to the canvas into it.  Does nothing for other images.
+    pub(crate) fn resolve_multisample(&self, ctx: &mut Context) -> GameResult {
+        let source = match self.multisample_source {
+            Some(ref source) => source,
+            None => return Ok(()),
+        };
+        if !source.dirty.load(Ordering::SeqCst) {
+            return Ok(());
+        }
+        let gfx = &mut ctx.gfx_context;
+        // The resolve happens outside of gfx's command stream, so make
+        // sure everything drawn to the canvas has actually been submitted.
+        gfx.encoder.flush(&mut *gfx.device);
+        let resolver = gfx.multisample_resolver.as_ref().ok_or_else(|| {
+            GameError::RenderError(
+                "Resolving multisampled canvases needs OpenGL 3.0 or OpenGL ES 3.0".to_string(),
+            )
+        })?;
+        resolver
+            .resolve(
+                source.texture.resource(),
+                true,
+                self.texture_handle.resource(),
+                self.width,
+                self.height,
+            )
+            .map_err(GameError::RenderError)?;
+        // A canvas that is still being drawn to needs resolving again.
+        let drawing = gfx.multisample_canvas.as_ref().map_or(false, |image| {
+            image.multisample_source == self.multisample_source
+        });
+        source.dirty.store(drawing, Ordering::SeqCst);
+        Ok(())
+    }
+
     /// Load a new image from the file at the given path. The documentation for the
     /// [`filesystem`](../filesystem/index.html) module explains how the path must be specified.
+    ///
+    /// PNG, BMP and PNM files can always be loaded, and JPEG, TGA, GIF
+    /// (only the first frame), DDS and QOI files when the cargo feature
+    /// of the same name is enabled.  The format is told by the file's
+    /// contents, not its extension.
+    ///
+    /// KTX and KTX2 files holding BC1-BC7, ETC2/EAC or ASTC data are
+    /// uploaded as they are when the GPU supports the format.  If it
+    /// doesn't, the largest mipmap level of BC1-BC5, BC7 and ETC2/EAC
+    /// textures is decompressed on the CPU instead, and other formats
+    /// fail to load.  [`renderer_info()`](fn.renderer_info.html) tells
+    /// which formats are supported.
     pub fn new<P: AsRef<path::Path>>(context: &mut Context, path: P) -> GameResult<Self> {
-        let img = {
-            let mut buf = Vec::new();
+        Self::new_with_options(context, path, ImageOptions::default())
+    }
+
+    /// Like [`new()`](#method.new), but gives the image a full chain of
+    /// mipmaps so it stays smooth when drawn scaled down with
+    /// [`FilterMode::Trilinear`](enum.FilterMode.html#variant.Trilinear)
+    /// or `FilterMode::Anisotropic`.  Compressed KTX textures keep the
+    /// mipmaps stored in the file instead.
+    pub fn new_mipmapped<P: AsRef<path::Path>>(context: &mut Context, path: P) -> GameResult<Self> {
+        Self::new_with_options(context, path, ImageOptions::default().mipmaps(true))
+    }
+
+    /// Like [`new()`](#method.new), but with the given mipmap and color
+    /// space options.
+    pub fn new_with_options<P: AsRef<path::Path>>(
+        context: &mut Context,
+        path: P,
+        options: ImageOptions,
+    ) -> GameResult<Self> {
+        let mut buf = Vec::new();
+        {
             let mut reader = context.filesystem.open(path)?;
             let _ = reader.read_to_end(&mut buf)?;
-            image::load_from_memory(&buf)?.to_rgba()
-        };
-        let (width, height) = img.dimensions();
-        Self::from_rgba8(context, width as u16, height as u16, &img)
+        }
+        if ktx::is_ktx(&buf) {
+            return ktx::load(context, &buf, options);
+        }
+        let (width, height, rgba) = codecs::decode(&buf)?;
+        Self::from_rgba8_with_options(context, width, height, &rgba, options)
+    }
+
+    /// Returns the format of an image loaded from a KTX file without
+    /// decompressing it, or `None` for an image with plain RGBA pixels.
+    pub fn compressed_format(&self) -> Option<CompressedFormat> {
+        self.compressed.as_ref().map(|storage| storage.format())
+    }
+
+    /// Compressed textures can't be read back through gfx.
+    pub(crate) fn check_readable(&self) -> GameResult {
+        match self.compressed_format() {
+            Some(format) => Err(GameError::RenderError(format!(
+                "Can't read back the pixels of an image in compressed format {:?}",
+                format
+            ))),
+            None => Ok(()),
+        }
     }
 
     /// Creates a new `Image` from the given buffer of `u8` RGBA values.
@@ -156,18 +444,159 @@ impl Image {
         width: u16,
         height: u16,
         rgba: &[u8],
+    ) -> GameResult<Self> {
+        Self::from_rgba8_with_options(context, width, height, rgba, ImageOptions::default())
+    }
+
+    /// Like [`from_rgba8()`](#method.from_rgba8), but also generates a
+    /// full chain of mipmaps for the image.
+    pub fn from_rgba8_mipmapped(
+        context: &mut Context,
+        width: u16,
+        height: u16,
+        rgba: &[u8],
+    ) -> GameResult<Self> {
+        let options = ImageOptions::default().mipmaps(true);
+        Self::from_rgba8_with_options(context, width, height, rgba, options)
+    }
+
+    /// Like [`from_rgba8()`](#method.from_rgba8), but with the given
+    /// mipmap and color space options.
+    pub fn from_rgba8_with_options(
+        context: &mut Context,
+        width: u16,
+        height: u16,
+        rgba: &[u8],
+        options: ImageOptions,
     ) -> GameResult<Self> {
         let debug_id = DebugId::get(context);
-        let color_format = context.gfx_context.color_format();
-        Self::make_raw(
+        let color_format = context.gfx_context.texture_format(options.color_space);
+        let image = Self::make_raw(
             &mut *context.gfx_context.factory,
             &context.gfx_context.default_sampler_info,
             width,
             height,
             rgba,
             color_format,
+            options.mipmaps,
             debug_id,
-        )
+        )?;
+        if options.mipmaps {
+            image.generate_mipmaps(context)?;
+        }
+        Ok(image)
+    }
+
+    /// Returns the number of mipmap levels the image has, which is 1
+    /// unless it was created with mipmaps.
+    pub fn mipmap_levels(&self) -> u8 {
+        self.texture_handle.get_info().levels
+    }
+
+    /// Regenerates the image's mipmaps from its full size pixels, for
+    /// after they have changed.  The image must have been created with
+    /// mipmaps, e.g. by [`from_rgba8_mipmapped()`](#method.from_rgba8_mipmapped)
+    /// or [`Canvas::new_mipmapped()`](struct.Canvas.html#method.new_mipmapped).
+    pub fn generate_mipmaps(&self, ctx: &mut Context) -> GameResult {
+        self.debug_id.assert(ctx);
+        if let Some(format) = self.compressed_format() {
+            return Err(GameError::RenderError(format!(
+                "Can't generate mipmaps for an image in compressed format {:?}",
+                format
+            )));
+        }
+        if self.mipmap_levels() < 2 {
+            return Err(GameError::RenderError(
+                "Image was created without room for mipmaps".to_string(),
+            ));
+        }
+        self.resolve_multisample(ctx)?;
+        ctx.gfx_context.encoder.generate_mipmap_raw(&self.texture);
+        Ok(())
+    }
+
+    /// Overwrites the pixels inside `rect` with the given buffer of `u8`
+    /// RGBA values, laid out as for [`from_rgba8()`](#method.from_rgba8)
+    /// but only `rect.w` pixels wide.  `rect` is in pixels, rounded to
+    /// whole ones, and must lie within the image.
+    ///
+    /// The image's mipmaps, if it has any, are regenerated.  Clones of
+    /// the `Image` share its texture and see the change too.  The images
+    /// of canvases and compressed images can't be updated.
+    pub fn update_region(&mut self, ctx: &mut Context, rect: Rect, rgba: &[u8]) -> GameResult {
+        self.debug_id.assert(ctx);
+        if let Some(format) = self.compressed_format() {
+            return Err(GameError::RenderError(format!(
+                "Can't update an image in compressed format {:?}",
+                format
+            )));
+        }
+        if !self
+            .texture_handle
+            .get_info()
+            .bind
+            .contains(gfx::memory::Bind::TRANSFER_DST)
+        {
+            return Err(GameError::RenderError(
+                "Can't update the image of a Canvas; draw to the canvas instead".to_string(),
+            ));
+        }
+        let (left, top) = (rect.x.round(), rect.y.round());
+        let (right, bottom) = (rect.right().round(), rect.bottom().round());
+        if left < 0.0
+            || top < 0.0
+            || right < left
+            || bottom < top
+            || right > f32::from(self.width)
+            || bottom > f32::from(self.height)
+        {
+            return Err(GameError::RenderError(format!(
+                "Region {:?} is not inside the {}x{} image",
+                rect, self.width, self.height
+            )));
+        }
+        let (x, y) = (left as u16, top as u16);
+        let (w, h) = ((right - left) as u16, (bottom - top) as u16);
+        let expected_bytes = usize::from(w) * usize::from(h) * 4;
+        if rgba.len() != expected_bytes {
+            return Err(GameError::RenderError(format!(
+                "Tried to update a {}x{} region, but gave {} bytes of data (expected {})",
+                w,
+                h,
+                rgba.len(),
+                expected_bytes
+            )));
+        }
+        if w == 0 || h == 0 {
+            return Ok(());
+        }
+
+        let gfx = &mut ctx.gfx_context;
+        let image_info = gfx::texture::RawImageInfo {
+            xoffset: x,
+            yoffset: y,
+            zoffset: 0,
+            width: w,
+            height: h,
+            depth: 0,
+            format: crate::graphics::readback::download_format(&self.texture_handle),
+            mipmap: 0,
+        };
+        gfx.encoder
+            .update_texture_raw(&self.texture_handle, None, image_info, rgba)
+            .map_err(|e| GameError::RenderError(format!("Texture update error: {:?}", e)))?;
+        if self.mipmap_levels() > 1 {
+            gfx.encoder.generate_mipmap_raw(&self.texture);
+        }
+        Ok(())
+    }
+
+    /// Overwrites all of the image's pixels with the given buffer of `u8`
+    /// RGBA values, laid out as for [`from_rgba8()`](#method.from_rgba8).
+    /// See [`update_region()`](#method.update_region).
+    pub fn replace(&mut self, ctx: &mut Context, rgba: &[u8]) -> GameResult {
+        let rect = self.dimensions();
+        self.update_region(ctx, rect, rgba)
     }
 
     /// Dumps the `Image`'s data to a `Vec` of `u8` RGBA values.
@@ -175,6 +604,11 @@ impl Image {
         use gfx::memory::Typed;
         use gfx::traits::FactoryExt;
 
+        self.check_readable()?;
+        self.resolve_multisample(ctx)?;
+        if self.is_hdr() {
+            return self.hdr_to_rgba8(ctx);
+        }
         let gfx = &mut ctx.gfx_context;
         let w = self.width;
         let h = self.height;
@@ -202,7 +636,7 @@ impl Image {
                 width: w as u16,
                 height: h as u16,
                 depth: 0,
-                format: gfx.color_format(),
+                format: crate::graphics::readback::download_format(&self.texture_handle),
                 mipmap: 0,
             },
             dl_buffer.raw(),
@@ -228,6 +662,54 @@ impl Image {
         Ok(data)
     }
 
+    /// Reads back a floating point image as floats and converts them,
+    /// since GL can't be trusted to do that for every format.
+    fn hdr_to_rgba8(&self, ctx: &mut Context) -> GameResult<Vec<u8>> {
+        use gfx::memory::Typed;
+        use gfx::traits::FactoryExt;
+
+        let gfx = &mut ctx.gfx_context;
+        let (w, h) = (self.width, self.height);
+        let dl_buffer = gfx
+            .factory
+            .create_download_buffer::<[f32; 4]>(usize::from(w) * usize::from(h))?;
+        let factory = &mut *gfx.factory;
+        let mut local_encoder = GlBackendSpec::encoder(factory);
+        local_encoder.copy_texture_to_buffer_raw(
+            &self.texture_handle,
+            None,
+            gfx::texture::RawImageInfo {
+                xoffset: 0,
+                yoffset: 0,
+                zoffset: 0,
+                width: w,
+                height: h,
+                depth: 0,
+                format: gfx::format::Format(
+                    gfx::format::SurfaceType::R32_G32_B32_A32,
+                    gfx::format::ChannelType::Float,
+                ),
+                mipmap: 0,
+            },
+            dl_buffer.raw(),
+            0,
+        )?;
+        local_encoder.flush(&mut *gfx.device);
+
+        let srgb = gfx.is_srgb();
+        let reader = gfx.factory.read_mapping(&dl_buffer)?;
+        let pixels: Vec<[u8; 4]> = reader.iter().map(|&p| hdr_to_rgba8(p, srgb)).collect();
+        Ok(readback::flip_rows(&pixels, w, h))
+    }
+
+    /// Returns `true` if the image has floating point colors, like the
+    /// image of a [`Canvas::new_hdr()`](struct.CanvasGeneric.html#method.new_hdr).
+    /// Reading it back with [`to_rgba8()`](#method.to_rgba8) clamps
+    /// the colors to between 0 and 1.
+    pub fn is_hdr(&self) -> bool {
+        is_hdr_surface(self.texture_handle.get_info().format)
+    }
+
     /// Encode the `Image` to the given file format and
     /// write it out to the given path.
     ///
@@ -243,17 +725,7 @@ impl Image {
         let data = self.to_rgba8(ctx)?;
         let f = filesystem::user_create(ctx, path)?;
         let writer = &mut io::BufWriter::new(f);
-        let color_format = image::ColorType::RGBA(8);
-        match format {
-            ImageFormat::Png => image::png::PNGEncoder::new(writer)
-                .encode(
-                    &data,
-                    u32::from(self.width),
-                    u32::from(self.height),
-                    color_format,
-                )
-                .map_err(Into::into),
-        }
+        encode_rgba8(writer, format, &data, self.width, self.height)
     }
 
     /// A little helper function that creates a new `Image` that is just
@@ -324,6 +796,7 @@ impl fmt::Debug for Image {
 impl Drawable for Image {
     fn draw(&self, ctx: &mut Context, param: DrawParam) -> GameResult {
         self.debug_id.assert(ctx);
+        self.resolve_multisample(ctx)?;
 
         let gfx = &mut ctx.gfx_context;
         let src_width = param.src.w;
@@ -378,3 +851,18 @@ impl Drawable for Image {
         self.blend_mode
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn headless_test_hdr_to_rgba8() {
+        assert_eq!(
+            hdr_to_rgba8([2.0, 0.5, -1.0, 1.0], false),
+            [255, 127, 0, 255]
+        );
+        // Linear 0.5 is 187.5 in sRGB; alpha is never encoded.
+        assert_eq!(hdr_to_rgba8([0.5, 0.0, 1.0, 0.5], true), [187, 0, 255, 127]);
+    }
+}
@@ -0,0 +1,334 @@
+//! Images kept in memory rather than on the GPU, for editing pixels.
+
+use std::io::Read;
+use std::path;
+
+use crate::context::Context;
+use crate::error::{GameError, GameResult};
+use crate::filesystem;
+use crate::graphics::codecs;
+use crate::graphics::*;
+
+/// RGBA pixels kept in memory, for editing on the CPU.
+///
+/// Unlike an [`Image`](struct.Image.html), whose pixels live on the GPU,
+/// reading and changing these is cheap.  Once you're done,
+/// [`to_image()`](#method.to_image) or [`upload()`](#method.upload)
+/// puts them on the GPU to be drawn.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ImageData {
+    width: u16,
+    height: u16,
+    rgba: Vec<u8>,
+}
+
+impl ImageData {
+    /// Creates a transparent black image of the given size.
+    pub fn new(width: u16, height: u16) -> Self {
+        ImageData {
+            width,
+            height,
+            rgba: vec![0; usize::from(width) * usize::from(height) * 4],
+        }
+    }
+
+    /// Creates an image of the given size filled with `color`.
+    pub fn filled(width: u16, height: u16, color: Color) -> Self {
+        let (r, g, b, a) = color.to_rgba();
+        let pixels = usize::from(width) * usize::from(height);
+        let mut rgba = Vec::with_capacity(pixels * 4);
+        for _ in 0..pixels {
+            rgba.extend_from_slice(&[r, g, b, a]);
+        }
+        ImageData {
+            width,
+            height,
+            rgba,
+        }
+    }
+
+    /// Creates an image from a buffer of `u8` RGBA values, laid out as
+    /// for [`Image::from_rgba8()`](struct.Image.html#method.from_rgba8).
+    pub fn from_rgba8(width: u16, height: u16, rgba: Vec<u8>) -> GameResult<Self> {
+        let expected_bytes = usize::from(width) * usize::from(height) * 4;
+        if rgba.len() != expected_bytes {
+            return Err(GameError::ResourceLoadError(format!(
+                "Tried to create an image of size {}x{}, but gave {} bytes of data (expected {})",
+                width,
+                height,
+                rgba.len(),
+                expected_bytes
+            )));
+        }
+        Ok(ImageData {
+            width,
+            height,
+            rgba,
+        })
+    }
+
+    /// Loads an image file into memory, in any format
+    /// [`Image::new()`](struct.Image.html#method.new) can load except
+    /// compressed KTX textures.
+    pub fn open<P: AsRef<path::Path>>(ctx: &mut Context, path: P) -> GameResult<Self> {
+        let mut buf = Vec::new();
+        let mut reader = ctx.filesystem.open(path)?;
+        let _ = reader.read_to_end(&mut buf)?;
+        let (width, height, rgba) = codecs::decode(&buf)?;
+        Ok(ImageData {
+            width,
+            height,
+            rgba,
+        })
+    }
+
+    /// Reads the pixels of an `Image` back from the GPU.  This is as
+    /// slow as [`Image::to_rgba8()`](struct.Image.html#method.to_rgba8).
+    pub fn from_image(ctx: &mut Context, image: &Image) -> GameResult<Self> {
+        let rgba = image.to_rgba8(ctx)?;
+        ImageData::from_rgba8(image.width(), image.height(), rgba)
+    }
+
+    /// Creates a new `Image` with these pixels.
+    pub fn to_image(&self, ctx: &mut Context) -> GameResult<Image> {
+        Image::from_rgba8(ctx, self.width, self.height, &self.rgba)
+    }
+
+    /// Copies these pixels into `image` with their top-left corner at
+    /// `(x, y)`, using [`Image::update_region()`](struct.Image.html#method.update_region).
+    pub fn upload(&self, ctx: &mut Context, image: &mut Image, x: u16, y: u16) -> GameResult {
+        let rect = Rect::new(
+            f32::from(x),
+            f32::from(y),
+            f32::from(self.width),
+            f32::from(self.height),
+        );
+        image.update_region(ctx, rect, &self.rgba)
+    }
+
+    /// Encodes the image to the given file format and writes it out to
+    /// the given path in the user directory, like
+    /// [`Image::encode()`](struct.Image.html#method.encode).
+    pub fn encode<P: AsRef<path::Path>>(
+        &self,
+        ctx: &mut Context,
+        format: ImageFormat,
+        path: P,
+    ) -> GameResult {
+        let f = filesystem::user_create(ctx, path)?;
+        let writer = std::io::BufWriter::new(f);
+        crate::graphics::image::encode_rgba8(writer, format, &self.rgba, self.width, self.height)
+    }
+
+    /// Returns the width of the image.
+    pub fn width(&self) -> u16 {
+        self.width
+    }
+
+    /// Returns the height of the image.
+    pub fn height(&self) -> u16 {
+        self.height
+    }
+
+    /// Returns the pixels as `u8` RGBA values, row by row from the top.
+    pub fn as_rgba8(&self) -> &[u8] {
+        &self.rgba
+    }
+
+    /// Returns the pixels as mutable `u8` RGBA values, row by row from
+    /// the top.
+    pub fn as_rgba8_mut(&mut self) -> &mut [u8] {
+        &mut self.rgba
+    }
+
+    /// Consumes the image and returns its `u8` RGBA values.
+    pub fn into_rgba8(self) -> Vec<u8> {
+        self.rgba
+    }
+
+    fn offset(&self, x: u16, y: u16) -> Option<usize> {
+        if x < self.width && y < self.height {
+            Some((usize::from(y) * usize::from(self.width) + usize::from(x)) * 4)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the color of the pixel at `(x, y)`, or `None` if that
+    /// is outside the image.
+    pub fn get_pixel(&self, x: u16, y: u16) -> Option<Color> {
+        self.offset(x, y).map(|i| {
+            let p = &self.rgba[i..i + 4];
+            Color::from((p[0], p[1], p[2], p[3]))
+        })
+    }
+
+    /// Sets the color of the pixel at `(x, y)`.  Does nothing if that is
+    /// outside the image, so shapes can be drawn past the edges.
+    pub fn set_pixel(&mut self, x: u16, y: u16, color: Color) {
+        if let Some(i) = self.offset(x, y) {
+            let (r, g, b, a) = color.to_rgba();
+            self.rgba[i..i + 4].copy_from_slice(&[r, g, b, a]);
+        }
+    }
+
+    /// Copies all of `source` into this image with its top-left corner
+    /// at `(x, y)`, which may be negative.  Pixels that fall outside
+    /// this image are skipped.  Alpha is copied rather than blended.
+    pub fn blit(&mut self, source: &ImageData, x: i32, y: i32) {
+        // The part of `source` that lands inside this image.
+        let src_left = (-x).max(0);
+        let src_top = (-y).max(0);
+        let src_right = i32::from(source.width).min(i32::from(self.width) - x);
+        let src_bottom = i32::from(source.height).min(i32::from(self.height) - y);
+        if src_left >= src_right || src_top >= src_bottom {
+            return;
+        }
+        let row_bytes = (src_right - src_left) as usize * 4;
+        for src_y in src_top..src_bottom {
+            let src_start = (src_y as usize * usize::from(source.width) + src_left as usize) * 4;
+            let dst_start =
+                ((src_y + y) as usize * usize::from(self.width) + (src_left + x) as usize) * 4;
+            self.rgba[dst_start..dst_start + row_bytes]
+                .copy_from_slice(&source.rgba[src_start..src_start + row_bytes]);
+        }
+    }
+
+    /// Mirrors the image left to right.
+    pub fn flip_horizontal(&mut self) {
+        let row_bytes = usize::from(self.width) * 4;
+        if row_bytes == 0 {
+            return;
+        }
+        for row in self.rgba.chunks_exact_mut(row_bytes) {
+            let width = usize::from(self.width);
+            for x in 0..width / 2 {
+                for channel in 0..4 {
+                    row.swap(x * 4 + channel, (width - 1 - x) * 4 + channel);
+                }
+            }
+        }
+    }
+
+    /// Mirrors the image top to bottom.
+    pub fn flip_vertical(&mut self) {
+        let row_bytes = usize::from(self.width) * 4;
+        let height = usize::from(self.height);
+        for y in 0..height / 2 {
+            let (top, bottom) = self.rgba.split_at_mut((height - 1 - y) * row_bytes);
+            top[y * row_bytes..(y + 1) * row_bytes].swap_with_slice(&mut bottom[..row_bytes]);
+        }
+    }
+
+    /// Returns a copy of the image scaled to the given size.  With
+    /// `FilterMode::Nearest` or `FilterMode::NearestMipmap` each pixel
+    /// is copied from the closest source pixel; the other modes
+    /// interpolate linearly between the four closest.
+    pub fn resize(&self, width: u16, height: u16, filter: FilterMode) -> ImageData {
+        let mut resized = ImageData::new(width, height);
+        if self.width == 0 || self.height == 0 {
+            return resized;
+        }
+        let scale_x = f32::from(self.width) / f32::from(width.max(1));
+        let scale_y = f32::from(self.height) / f32::from(height.max(1));
+        let nearest = match filter {
+            FilterMode::Nearest | FilterMode::NearestMipmap => true,
+            _ => false,
+        };
+        let max_x = f32::from(self.width - 1);
+        let max_y = f32::from(self.height - 1);
+        let mut out = resized.rgba.chunks_exact_mut(4);
+        for y in 0..height {
+            // The source position of the destination pixel's center.
+            let src_y = ((f32::from(y) + 0.5) * scale_y - 0.5).max(0.0).min(max_y);
+            for x in 0..width {
+                let src_x = ((f32::from(x) + 0.5) * scale_x - 0.5).max(0.0).min(max_x);
+                let pixel = out
+                    .next()
+                    .expect("Resized image is too small; should never happen");
+                if nearest {
+                    let i = self
+                        .offset(src_x.round() as u16, src_y.round() as u16)
+                        .expect("Pixel out of bounds; should never happen");
+                    pixel.copy_from_slice(&self.rgba[i..i + 4]);
+                    continue;
+                }
+                let (x0, y0) = (src_x.floor() as u16, src_y.floor() as u16);
+                let x1 = (x0 + 1).min(self.width - 1);
+                let y1 = (y0 + 1).min(self.height - 1);
+                let (fx, fy) = (src_x.fract(), src_y.fract());
+                let corner = |cx: u16, cy: u16| {
+                    let i = (usize::from(cy) * usize::from(self.width) + usize::from(cx)) * 4;
+                    &self.rgba[i..i + 4]
+                };
+                let (top_left, top_right) = (corner(x0, y0), corner(x1, y0));
+                let (bottom_left, bottom_right) = (corner(x0, y1), corner(x1, y1));
+                for (channel, value) in pixel.iter_mut().enumerate() {
+                    let lerp =
+                        |a: u8, b: u8, t: f32| f32::from(a) + (f32::from(b) - f32::from(a)) * t;
+                    let top = lerp(top_left[channel], top_right[channel], fx);
+                    let bottom = lerp(bottom_left[channel], bottom_right[channel], fx);
+                    *value = (top + (bottom - top) * fy).round() as u8;
+                }
+            }
+        }
+        resized
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A 3x2 image whose red channel is the pixel index.
+    fn numbered() -> ImageData {
+        let rgba = (0..6u8).flat_map(|i| vec![i, 0, 0, 255]).collect();
+        ImageData::from_rgba8(3, 2, rgba).unwrap()
+    }
+
+    fn reds(data: &ImageData) -> Vec<u8> {
+        data.as_rgba8().chunks_exact(4).map(|p| p[0]).collect()
+    }
+
+    #[test]
+    fn headless_test_pixels() {
+        let mut data = numbered();
+        assert_eq!(data.get_pixel(2, 1), Some(Color::from((5, 0, 0, 255))));
+        assert_eq!(data.get_pixel(3, 0), None);
+        data.set_pixel(1, 0, Color::from((9, 0, 0, 255)));
+        data.set_pixel(7, 7, Color::from((9, 0, 0, 255)));
+        assert_eq!(reds(&data), vec![0, 9, 2, 3, 4, 5]);
+        assert!(ImageData::from_rgba8(3, 2, vec![0; 4]).is_err());
+    }
+
+    #[test]
+    fn headless_test_flip() {
+        let mut data = numbered();
+        data.flip_horizontal();
+        assert_eq!(reds(&data), vec![2, 1, 0, 5, 4, 3]);
+        data.flip_vertical();
+        assert_eq!(reds(&data), vec![5, 4, 3, 2, 1, 0]);
+    }
+
+    #[test]
+    fn headless_test_blit() {
+        let mut data = ImageData::new(3, 2);
+        data.blit(&numbered(), 1, -1);
+        assert_eq!(reds(&data), vec![0, 3, 4, 0, 0, 0]);
+        assert_eq!(data.get_pixel(0, 0), Some(Color::from((0, 0, 0, 0))));
+        data.blit(&numbered(), 5, 0);
+        assert_eq!(reds(&data), vec![0, 3, 4, 0, 0, 0]);
+    }
+
+    #[test]
+    fn headless_test_resize() {
+        let data = numbered();
+        let doubled = data.resize(6, 4, FilterMode::Nearest);
+        assert_eq!(doubled.get_pixel(5, 3), data.get_pixel(2, 1));
+        assert_eq!(doubled.get_pixel(1, 1), data.get_pixel(0, 0));
+
+        let gradient = ImageData::from_rgba8(2, 1, vec![0, 0, 0, 255, 200, 0, 0, 255]).unwrap();
+        let smooth = gradient.resize(4, 1, FilterMode::Linear);
+        assert_eq!(reds(&smooth), vec![0, 50, 150, 200]);
+    }
+}
@@ -0,0 +1,733 @@
+//! Loading block compressed textures from KTX and KTX2 files.
+//!
+//! gfx has no compressed texture formats, so when the GL driver can
+//! sample a format directly the texture is uploaded with raw GL calls
+//! and wrapped in gfx handles.  Otherwise the first mipmap level is
+//! decompressed on the CPU and uploaded like any other image.
+
+use std::fmt;
+use std::sync::{Arc, Mutex};
+
+use gfx::memory::Bind;
+use gfx_core::handle::Producer;
+
+use crate::context::{Context, DebugId};
+use crate::error::{GameError, GameResult};
+use crate::graphics::block_decode;
+use crate::graphics::glutin_ext::CompressedUploader;
+use crate::graphics::*;
+
+const KTX1_IDENTIFIER: [u8; 12] = [
+    0xAB, 0x4B, 0x54, 0x58, 0x20, 0x31, 0x31, 0xBB, 0x0D, 0x0A, 0x1A, 0x0A,
+];
+const KTX2_IDENTIFIER: [u8; 12] = [
+    0xAB, 0x4B, 0x54, 0x58, 0x20, 0x32, 0x30, 0xBB, 0x0D, 0x0A, 0x1A, 0x0A,
+];
+
+/// The block compressed texture formats that can be loaded from KTX
+/// and KTX2 files.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum CompressedFormat {
+    /// BC1 (DXT1) without alpha.
+    Bc1Rgb,
+    /// BC1 (DXT1) with 1-bit alpha.
+    Bc1Rgba,
+    /// BC2 (DXT3).
+    Bc2,
+    /// BC3 (DXT5).
+    Bc3,
+    /// BC4, a single red channel.
+    Bc4,
+    /// BC5, red and green channels.
+    Bc5,
+    /// BC6H, unsigned floating point RGB.
+    Bc6h,
+    /// BC7.
+    Bc7,
+    /// ETC2 RGB, which includes ETC1.
+    Etc2Rgb,
+    /// ETC2 RGB with 1-bit "punchthrough" alpha.
+    Etc2RgbA1,
+    /// ETC2 RGB with EAC alpha.
+    Etc2Rgba,
+    /// EAC, a single red channel.
+    EacR11,
+    /// EAC, red and green channels.
+    EacRg11,
+    /// ASTC with the given block width and height.
+    Astc(u8, u8),
+}
+
+/// The ASTC block sizes, in the order of their GL and Vulkan formats.
+const ASTC_BLOCKS: [(u8, u8); 14] = [
+    (4, 4),
+    (5, 4),
+    (5, 5),
+    (6, 5),
+    (6, 6),
+    (8, 5),
+    (8, 6),
+    (8, 8),
+    (10, 5),
+    (10, 6),
+    (10, 8),
+    (10, 10),
+    (12, 10),
+    (12, 12),
+];
+
+impl CompressedFormat {
+    /// The width and height of the blocks the format is made of.
+    pub fn block_size(self) -> (u8, u8) {
+        match self {
+            CompressedFormat::Astc(w, h) => (w, h),
+            _ => (4, 4),
+        }
+    }
+
+    /// The size of one block in bytes.
+    pub(crate) fn block_bytes(self) -> usize {
+        match self {
+            CompressedFormat::Bc1Rgb
+            | CompressedFormat::Bc1Rgba
+            | CompressedFormat::Bc4
+            | CompressedFormat::Etc2Rgb
+            | CompressedFormat::Etc2RgbA1
+            | CompressedFormat::EacR11 => 8,
+            _ => 16,
+        }
+    }
+
+    /// The number of bytes a `width` by `height` image takes.
+    fn data_size(self, width: u16, height: u16) -> usize {
+        let (bw, bh) = self.block_size();
+        let blocks_x = (usize::from(width) + usize::from(bw) - 1) / usize::from(bw);
+        let blocks_y = (usize::from(height) + usize::from(bh) - 1) / usize::from(bh);
+        blocks_x * blocks_y * self.block_bytes()
+    }
+
+    fn astc_index(self) -> Option<u32> {
+        match self {
+            CompressedFormat::Astc(w, h) => ASTC_BLOCKS
+                .iter()
+                .position(|&size| size == (w, h))
+                .map(|i| i as u32),
+            _ => None,
+        }
+    }
+
+    /// Looks up a GL internal format, linear or sRGB.
+    fn from_gl(internal_format: u32) -> Option<Self> {
+        let format = match internal_format {
+            0x83F0 | 0x8C4C => CompressedFormat::Bc1Rgb,
+            0x83F1 | 0x8C4D => CompressedFormat::Bc1Rgba,
+            0x83F2 | 0x8C4E => CompressedFormat::Bc2,
+            0x83F3 | 0x8C4F => CompressedFormat::Bc3,
+            0x8DBB => CompressedFormat::Bc4,
+            0x8DBD => CompressedFormat::Bc5,
+            0x8E8F => CompressedFormat::Bc6h,
+            0x8E8C | 0x8E8D => CompressedFormat::Bc7,
+            // ETC1 is a subset of ETC2.
+            0x8D64 | 0x9274 | 0x9275 => CompressedFormat::Etc2Rgb,
+            0x9276 | 0x9277 => CompressedFormat::Etc2RgbA1,
+            0x9278 | 0x9279 => CompressedFormat::Etc2Rgba,
+            0x9270 => CompressedFormat::EacR11,
+            0x9272 => CompressedFormat::EacRg11,
+            0x93B0..=0x93BD => {
+                let (w, h) = ASTC_BLOCKS[(internal_format - 0x93B0) as usize];
+                CompressedFormat::Astc(w, h)
+            }
+            0x93D0..=0x93DD => {
+                let (w, h) = ASTC_BLOCKS[(internal_format - 0x93D0) as usize];
+                CompressedFormat::Astc(w, h)
+            }
+            _ => return None,
+        };
+        Some(format)
+    }
+
+    /// Looks up a Vulkan format, as used by KTX2, linear or sRGB.
+    fn from_vk(vk_format: u32) -> Option<Self> {
+        let format = match vk_format {
+            131 | 132 => CompressedFormat::Bc1Rgb,
+            133 | 134 => CompressedFormat::Bc1Rgba,
+            135 | 136 => CompressedFormat::Bc2,
+            137 | 138 => CompressedFormat::Bc3,
+            139 => CompressedFormat::Bc4,
+            141 => CompressedFormat::Bc5,
+            143 => CompressedFormat::Bc6h,
+            145 | 146 => CompressedFormat::Bc7,
+            147 | 148 => CompressedFormat::Etc2Rgb,
+            149 | 150 => CompressedFormat::Etc2RgbA1,
+            151 | 152 => CompressedFormat::Etc2Rgba,
+            153 => CompressedFormat::EacR11,
+            155 => CompressedFormat::EacRg11,
+            157..=184 => {
+                let (w, h) = ASTC_BLOCKS[((vk_format - 157) / 2) as usize];
+                CompressedFormat::Astc(w, h)
+            }
+            _ => return None,
+        };
+        Some(format)
+    }
+
+    /// The GL internal format to upload the texture as.
+    fn gl_internal_format(self, srgb: bool) -> u32 {
+        match (self, srgb) {
+            (CompressedFormat::Bc1Rgb, false) => 0x83F0,
+            (CompressedFormat::Bc1Rgb, true) => 0x8C4C,
+            (CompressedFormat::Bc1Rgba, false) => 0x83F1,
+            (CompressedFormat::Bc1Rgba, true) => 0x8C4D,
+            (CompressedFormat::Bc2, false) => 0x83F2,
+            (CompressedFormat::Bc2, true) => 0x8C4E,
+            (CompressedFormat::Bc3, false) => 0x83F3,
+            (CompressedFormat::Bc3, true) => 0x8C4F,
+            (CompressedFormat::Bc4, _) => 0x8DBB,
+            (CompressedFormat::Bc5, _) => 0x8DBD,
+            (CompressedFormat::Bc6h, _) => 0x8E8F,
+            (CompressedFormat::Bc7, false) => 0x8E8C,
+            (CompressedFormat::Bc7, true) => 0x8E8D,
+            (CompressedFormat::Etc2Rgb, false) => 0x9274,
+            (CompressedFormat::Etc2Rgb, true) => 0x9275,
+            (CompressedFormat::Etc2RgbA1, false) => 0x9276,
+            (CompressedFormat::Etc2RgbA1, true) => 0x9277,
+            (CompressedFormat::Etc2Rgba, false) => 0x9278,
+            (CompressedFormat::Etc2Rgba, true) => 0x9279,
+            (CompressedFormat::EacR11, _) => 0x9270,
+            (CompressedFormat::EacRg11, _) => 0x9272,
+            (CompressedFormat::Astc(_, _), srgb) => {
+                let base = if srgb { 0x93D0 } else { 0x93B0 };
+                base + self.astc_index().unwrap_or(0)
+            }
+        }
+    }
+
+    /// The uncompressed surface type with the channels and precision
+    /// the GPU samples the format as.  gfx has no compressed surface
+    /// types, so this is what the texture's info reports.
+    fn sampled_surface_type(self) -> gfx::format::SurfaceType {
+        use gfx::format::SurfaceType;
+        match self {
+            CompressedFormat::Bc4 | CompressedFormat::EacR11 => SurfaceType::R8,
+            CompressedFormat::Bc5 | CompressedFormat::EacRg11 => SurfaceType::R8_G8,
+            CompressedFormat::Bc6h => SurfaceType::R16_G16_B16,
+            _ => SurfaceType::R8_G8_B8_A8,
+        }
+    }
+}
+
+/// Which compressed formats the GL driver can sample directly.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub(crate) struct CompressionSupport {
+    s3tc: bool,
+    rgtc: bool,
+    bptc: bool,
+    etc2: bool,
+    astc: bool,
+}
+
+impl CompressionSupport {
+    pub(crate) fn from_info(info: &gfx_device_gl::Info) -> Self {
+        let version = &info.version;
+        let at_least = |major: u32, minor: u32| (version.major, version.minor) >= (major, minor);
+        let desktop = |major, minor| !version.is_embedded && at_least(major, minor);
+        let es = |major, minor| version.is_embedded && at_least(major, minor);
+        let ext = |name: &str| info.extensions.contains(name);
+        CompressionSupport {
+            s3tc: ext("GL_EXT_texture_compression_s3tc"),
+            rgtc: desktop(3, 0)
+                || ext("GL_ARB_texture_compression_rgtc")
+                || ext("GL_EXT_texture_compression_rgtc"),
+            bptc: desktop(4, 2)
+                || ext("GL_ARB_texture_compression_bptc")
+                || ext("GL_EXT_texture_compression_bptc"),
+            etc2: desktop(4, 3) || es(3, 0) || ext("GL_ARB_ES3_compatibility"),
+            astc: ext("GL_KHR_texture_compression_astc_ldr")
+                || ext("GL_OES_texture_compression_astc"),
+        }
+    }
+
+    fn supports(self, format: CompressedFormat) -> bool {
+        match format {
+            CompressedFormat::Bc1Rgb
+            | CompressedFormat::Bc1Rgba
+            | CompressedFormat::Bc2
+            | CompressedFormat::Bc3 => self.s3tc,
+            CompressedFormat::Bc4 | CompressedFormat::Bc5 => self.rgtc,
+            CompressedFormat::Bc6h | CompressedFormat::Bc7 => self.bptc,
+            CompressedFormat::Etc2Rgb
+            | CompressedFormat::Etc2RgbA1
+            | CompressedFormat::Etc2Rgba
+            | CompressedFormat::EacR11
+            | CompressedFormat::EacRg11 => self.etc2,
+            CompressedFormat::Astc(_, _) => self.astc,
+        }
+    }
+}
+
+/// The compressed texture state of the graphics context.
+pub(crate) struct TextureCompression {
+    uploader: Option<CompressedUploader>,
+    support: CompressionSupport,
+    /// GL names of dropped compressed textures, to be deleted.
+    dropped: Arc<Mutex<Vec<u32>>>,
+}
+
+impl fmt::Debug for TextureCompression {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "<TextureCompression: {:?}>", self.support)
+    }
+}
+
+impl TextureCompression {
+    pub(crate) fn new(uploader: Option<CompressedUploader>, support: CompressionSupport) -> Self {
+        TextureCompression {
+            uploader,
+            support,
+            dropped: Arc::new(Mutex::new(Vec::new())),
+        }
+    }
+
+    /// Returns `true` if `format` can be uploaded without decompressing it.
+    pub(crate) fn is_native(&self, format: CompressedFormat) -> bool {
+        self.uploader.is_some() && self.support.supports(format)
+    }
+
+    /// Deletes the textures of compressed images that were dropped.
+    /// Called by `present()` once the frame's commands are submitted.
+    pub(crate) fn delete_dropped(&self) {
+        if let Some(ref uploader) = self.uploader {
+            if let Ok(mut dropped) = self.dropped.lock() {
+                uploader.delete(&dropped);
+                dropped.clear();
+            }
+        }
+    }
+
+    /// Describes how each family of formats gets loaded, for
+    /// [`renderer_info()`](fn.renderer_info.html).
+    pub(crate) fn describe(&self) -> String {
+        let families = [
+            ("BC1-BC3", CompressedFormat::Bc1Rgb),
+            ("BC4/BC5", CompressedFormat::Bc4),
+            ("BC6H", CompressedFormat::Bc6h),
+            ("BC7", CompressedFormat::Bc7),
+            ("ETC2/EAC", CompressedFormat::Etc2Rgb),
+            ("ASTC", CompressedFormat::Astc(4, 4)),
+        ];
+        let paths: Vec<String> = families
+            .iter()
+            .map(|&(name, format)| {
+                let path = if self.is_native(format) {
+                    "native"
+                } else if block_decode::can_decode(format) {
+                    "CPU decompression"
+                } else {
+                    "unsupported"
+                };
+                format!("{} {}", name, path)
+            })
+            .collect();
+        format!("Compressed textures: {}", paths.join(", "))
+    }
+}
+
+/// Owns the GL texture of a compressed image, which gfx doesn't know
+/// how to delete.  Dropping it queues the texture for deletion.
+pub(crate) struct CompressedStorage {
+    name: u32,
+    format: CompressedFormat,
+    dropped: Arc<Mutex<Vec<u32>>>,
+}
+
+impl CompressedStorage {
+    pub(crate) fn format(&self) -> CompressedFormat {
+        self.format
+    }
+}
+
+impl fmt::Debug for CompressedStorage {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "<CompressedStorage: {:?} texture {}>",
+            self.format, self.name
+        )
+    }
+}
+
+impl PartialEq for CompressedStorage {
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name
+    }
+}
+
+impl Drop for CompressedStorage {
+    fn drop(&mut self) {
+        if let Ok(mut dropped) = self.dropped.lock() {
+            dropped.push(self.name);
+        }
+    }
+}
+
+/// A parsed KTX or KTX2 file.
+#[derive(Debug)]
+struct KtxTexture<'a> {
+    format: CompressedFormat,
+    width: u16,
+    height: u16,
+    /// The mipmap levels from largest to smallest.
+    levels: Vec<&'a [u8]>,
+}
+
+/// Returns `true` if `bytes` look like a KTX or KTX2 file.
+pub(crate) fn is_ktx(bytes: &[u8]) -> bool {
+    bytes.starts_with(&KTX1_IDENTIFIER) || bytes.starts_with(&KTX2_IDENTIFIER)
+}
+
+fn invalid(msg: &str) -> GameError {
+    GameError::ResourceLoadError(format!("Invalid KTX file: {}", msg))
+}
+
+fn unsupported(msg: &str) -> GameError {
+    GameError::ResourceLoadError(format!("Unsupported KTX file: {}", msg))
+}
+
+fn parse(bytes: &[u8]) -> GameResult<KtxTexture> {
+    let (format, width, height, levels) = if bytes.starts_with(&KTX1_IDENTIFIER) {
+        parse_ktx1(bytes)?
+    } else if bytes.starts_with(&KTX2_IDENTIFIER) {
+        parse_ktx2(bytes)?
+    } else {
+        return Err(invalid("missing identifier"));
+    };
+    let max = u32::from(u16::max_value());
+    if width == 0 || height == 0 || width > max || height > max {
+        return Err(unsupported(
+            "only 2D textures up to 65535x65535 can be loaded",
+        ));
+    }
+    let (width, height) = (width as u16, height as u16);
+    // Check every level holds as much data as its size needs.
+    let mut checked = Vec::with_capacity(levels.len());
+    for (i, level) in levels.into_iter().enumerate() {
+        let (w, h) = level_size(width, height, i);
+        let size = format.data_size(w, h);
+        if level.len() < size {
+            return Err(invalid("mipmap level is too short"));
+        }
+        checked.push(&level[..size]);
+        if (w, h) == (1, 1) {
+            break;
+        }
+    }
+    Ok(KtxTexture {
+        format,
+        width,
+        height,
+        levels: checked,
+    })
+}
+
+fn level_size(width: u16, height: u16, level: usize) -> (u16, u16) {
+    let shrink = |size: u16| (size.checked_shr(level as u32).unwrap_or(0)).max(1);
+    (shrink(width), shrink(height))
+}
+
+type ParsedLevels<'a> = (CompressedFormat, u32, u32, Vec<&'a [u8]>);
+
+fn parse_ktx1(bytes: &[u8]) -> GameResult<ParsedLevels> {
+    const HEADER_SIZE: usize = 64;
+    if bytes.len() < HEADER_SIZE {
+        return Err(invalid("truncated header"));
+    }
+    let big_endian = match u32::from_le_bytes([bytes[12], bytes[13], bytes[14], bytes[15]]) {
+        0x0403_0201 => false,
+        0x0102_0304 => true,
+        _ => return Err(invalid("bad endianness marker")),
+    };
+    let read = |offset: usize| -> GameResult<u32> {
+        let word = offset
+            .checked_add(4)
+            .and_then(|end| bytes.get(offset..end))
+            .ok_or_else(|| invalid("truncated data"))?;
+        let word = [word[0], word[1], word[2], word[3]];
+        Ok(if big_endian {
+            u32::from_be_bytes(word)
+        } else {
+            u32::from_le_bytes(word)
+        })
+    };
+    let field = |index: usize| read(12 + 4 * index);
+    let gl_type = field(1)?;
+    let gl_internal_format = field(4)?;
+    let width = field(6)?;
+    let height = field(7)?;
+    let depth = field(8)?;
+    let array_elements = field(9)?;
+    let faces = field(10)?;
+    let level_count = field(11)?.max(1);
+    let key_value_bytes = field(12)? as usize;
+
+    if gl_type != 0 {
+        return Err(unsupported("only compressed textures can be loaded"));
+    }
+    let format = CompressedFormat::from_gl(gl_internal_format).ok_or_else(|| {
+        unsupported(&format!(
+            "unknown compressed format 0x{:X}",
+            gl_internal_format
+        ))
+    })?;
+    if depth > 1 || array_elements > 0 || faces != 1 {
+        return Err(unsupported("only 2D textures can be loaded"));
+    }
+
+    // The sizes come from the file, so these sums can overflow a 32-bit
+    // usize.
+    let truncated = || invalid("truncated data");
+    let mut levels = Vec::new();
+    let mut offset = HEADER_SIZE
+        .checked_add(key_value_bytes)
+        .ok_or_else(truncated)?;
+    for _ in 0..level_count {
+        let size = read(offset)? as usize;
+        offset += 4;
+        let end = offset.checked_add(size).ok_or_else(truncated)?;
+        let level = bytes.get(offset..end).ok_or_else(truncated)?;
+        levels.push(level);
+        // Levels are padded to 4 bytes.
+        let padded = size.checked_add(3).ok_or_else(truncated)? / 4 * 4;
+        offset = offset.checked_add(padded).ok_or_else(truncated)?;
+    }
+    Ok((format, width, height, levels))
+}
+
+fn parse_ktx2(bytes: &[u8]) -> GameResult<ParsedLevels> {
+    const HEADER_SIZE: usize = 80;
+    const LEVEL_INDEX_ENTRY_SIZE: usize = 24;
+    if bytes.len() < HEADER_SIZE {
+        return Err(invalid("truncated header"));
+    }
+    let u32_at = |offset: usize| {
+        u32::from_le_bytes([
+            bytes[offset],
+            bytes[offset + 1],
+            bytes[offset + 2],
+            bytes[offset + 3],
+        ])
+    };
+    let u64_at = |offset: usize| -> GameResult<usize> {
+        let word = bytes
+            .get(offset..offset + 8)
+            .ok_or_else(|| invalid("truncated level index"))?;
+        let mut buf = [0u8; 8];
+        buf.copy_from_slice(word);
+        Ok(u64::from_le_bytes(buf) as usize)
+    };
+    let vk_format = u32_at(12);
+    let width = u32_at(20);
+    let height = u32_at(24);
+    let depth = u32_at(28);
+    let layers = u32_at(32);
+    let faces = u32_at(36);
+    let level_count = u32_at(40).max(1);
+    let supercompression = u32_at(44);
+
+    if supercompression != 0 {
+        return Err(unsupported("supercompressed (Basis or Zstandard) data"));
+    }
+    let format = CompressedFormat::from_vk(vk_format)
+        .ok_or_else(|| unsupported(&format!("unknown compressed format {}", vk_format)))?;
+    if depth > 1 || layers > 0 || faces != 1 {
+        return Err(unsupported("only 2D textures can be loaded"));
+    }
+
+    let mut levels = Vec::new();
+    for i in 0..level_count as usize {
+        let entry = HEADER_SIZE + i * LEVEL_INDEX_ENTRY_SIZE;
+        let offset = u64_at(entry)?;
+        let size = u64_at(entry + 8)?;
+        let level = offset
+            .checked_add(size)
+            .and_then(|end| bytes.get(offset..end))
+            .ok_or_else(|| invalid("truncated data"))?;
+        levels.push(level);
+    }
+    Ok((format, width, height, levels))
+}
+
+/// Loads an `Image` from the contents of a KTX or KTX2 file.  Textures
+/// that have to be decompressed get their mipmaps generated if
+/// `options.mipmaps` is set, since only the first level is decompressed.
+///
+/// ASTC and BC6H have no CPU decoder, so files in those formats are
+/// rejected before anything is decoded or uploaded when the GPU can't
+/// sample them.
+pub(crate) fn load(ctx: &mut Context, bytes: &[u8], options: ImageOptions) -> GameResult<Image> {
+    let texture = parse(bytes)?;
+    let native = ctx
+        .gfx_context
+        .texture_compression
+        .is_native(texture.format);
+    if !native && !block_decode::can_decode(texture.format) {
+        return Err(GameError::ResourceLoadError(format!(
+            "The GPU doesn't support {:?} textures, and there is no CPU decoder for them",
+            texture.format
+        )));
+    }
+    if !native {
+        let rgba = block_decode::decode(
+            texture.format,
+            texture.width,
+            texture.height,
+            texture.levels[0],
+        )?;
+        return Image::from_rgba8_with_options(ctx, texture.width, texture.height, &rgba, options);
+    }
+
+    let debug_id = DebugId::get(ctx);
+    let gfx = &mut ctx.gfx_context;
+    let levels: Vec<(u16, u16, &[u8])> = texture
+        .levels
+        .iter()
+        .enumerate()
+        .map(|(i, &data)| {
+            let (w, h) = level_size(texture.width, texture.height, i);
+            (w, h, data)
+        })
+        .collect();
+    let srgb = gfx.is_srgb() && options.color_space == ColorSpace::Srgb;
+    let internal_format = texture.format.gl_internal_format(srgb);
+    let name = gfx
+        .texture_compression
+        .uploader
+        .as_ref()
+        .expect("Native compressed format without an uploader; should never happen")
+        .upload(internal_format, &levels)
+        .map_err(GameError::RenderError)?;
+    let storage = CompressedStorage {
+        name,
+        format: texture.format,
+        dropped: gfx.texture_compression.dropped.clone(),
+    };
+
+    // Wrap the texture in gfx handles the way gfx_device_gl does for the
+    // window's own framebuffer.  gfx will never delete it; the storage
+    // does.  Nothing reads the texture through gfx, since readbacks and
+    // updates refuse compressed images, so the info only has to describe
+    // what sampling it gives.
+    let kind = gfx::texture::Kind::D2(texture.width, texture.height, gfx::texture::AaMode::Single);
+    let info = gfx::texture::Info {
+        kind,
+        levels: levels.len() as gfx::texture::Level,
+        format: texture.format.sampled_surface_type(),
+        bind: Bind::SHADER_RESOURCE,
+        usage: gfx::memory::Usage::Data,
+    };
+    let mut handles = gfx_core::handle::Manager::<gfx_device_gl::Resources>::new();
+    let texture_handle = handles.make_texture(gfx_device_gl::NewTexture::Texture(name), info);
+    let view = handles.make_texture_srv(
+        gfx_device_gl::ResourceView::new_texture(name, kind),
+        &texture_handle,
+    );
+    Ok(Image {
+        texture: view,
+        texture_handle,
+        sampler_info: gfx.default_sampler_info,
+        blend_mode: None,
+        width: texture.width,
+        height: texture.height,
+        multisample_source: None,
+        compressed: Some(Arc::new(storage)),
+        debug_id,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Builds a KTX2 file holding the given levels.
+    fn ktx2(vk_format: u32, width: u32, height: u32, levels: &[&[u8]]) -> Vec<u8> {
+        let mut header = KTX2_IDENTIFIER.to_vec();
+        for &value in &[vk_format, 1, width, height, 0, 0, 1, levels.len() as u32, 0] {
+            header.extend_from_slice(&value.to_le_bytes());
+        }
+        // No data format descriptor, key/value data or supercompression data.
+        header.extend_from_slice(&[0; 32]);
+        let mut offset = header.len() + levels.len() * 24;
+        let mut data = Vec::new();
+        for level in levels {
+            for &value in &[offset, level.len(), level.len()] {
+                header.extend_from_slice(&(value as u64).to_le_bytes());
+            }
+            offset += level.len();
+            data.extend_from_slice(level);
+        }
+        header.extend(data);
+        header
+    }
+
+    #[test]
+    fn headless_test_parse_ktx2() {
+        let big = [1u8; 32];
+        let small = [2u8; 8];
+        let bytes = ktx2(131, 8, 4, &[&big, &small]);
+        assert!(is_ktx(&bytes));
+        let texture = parse(&bytes).unwrap();
+        assert_eq!(texture.format, CompressedFormat::Bc1Rgb);
+        assert_eq!((texture.width, texture.height), (8, 4));
+        assert_eq!(texture.levels, vec![&big[..], &small[..]]);
+
+        // Levels must hold all of their blocks.
+        let bytes = ktx2(131, 8, 4, &[&small]);
+        assert!(parse(&bytes).is_err());
+    }
+
+    #[test]
+    fn headless_test_parse_ktx1() {
+        let mut bytes = KTX1_IDENTIFIER.to_vec();
+        // Endianness, glType, glTypeSize, glFormat, glInternalFormat
+        // (ETC2 RGBA), glBaseInternalFormat, size 4x4x0, no array
+        // elements, one face, one level and no key/value data.
+        for &value in &[0x0403_0201u32, 0, 1, 0, 0x9278, 0x1908, 4, 4, 0, 0, 1, 1, 0] {
+            bytes.extend_from_slice(&value.to_le_bytes());
+        }
+        bytes.extend_from_slice(&16u32.to_le_bytes());
+        bytes.extend_from_slice(&[7; 16]);
+        let texture = parse(&bytes).unwrap();
+        assert_eq!(texture.format, CompressedFormat::Etc2Rgba);
+        assert_eq!(texture.levels, vec![&[7u8; 16][..]]);
+
+        // Sizes running past the end of the file, or of the address
+        // space, make it truncated.
+        let mut huge_level = bytes.clone();
+        huge_level[64..68].copy_from_slice(&std::u32::MAX.to_le_bytes());
+        assert!(parse(&huge_level).is_err());
+        let mut huge_key_values = bytes;
+        huge_key_values[60..64].copy_from_slice(&std::u32::MAX.to_le_bytes());
+        assert!(parse(&huge_key_values).is_err());
+    }
+
+    #[test]
+    fn headless_test_format_tables() {
+        assert_eq!(
+            CompressedFormat::from_vk(171),
+            Some(CompressedFormat::Astc(8, 8))
+        );
+        assert_eq!(
+            CompressedFormat::from_gl(0x93D7),
+            Some(CompressedFormat::Astc(8, 8))
+        );
+        assert_eq!(
+            CompressedFormat::Astc(8, 8).gl_internal_format(false),
+            0x93B7
+        );
+        assert_eq!(CompressedFormat::Astc(6, 6).data_size(13, 13), 3 * 3 * 16);
+        assert_eq!(level_size(8, 2, 2), (2, 1));
+        assert_eq!(
+            CompressedFormat::EacRg11.sampled_surface_type(),
+            gfx::format::SurfaceType::R8_G8
+        );
+    }
+}
@@ -0,0 +1,259 @@
+//! Suspending and resuming the graphics when the app goes to the
+//! background, which on Android takes away the surface the window is
+//! drawn to, and recreating the GL context if that loses it.
+
+use std::fmt;
+use std::mem;
+
+use winit::event_loop::EventLoopWindowTarget;
+
+use crate::context::{Context, DebugId};
+use crate::error::{GameError, GameResult};
+use crate::graphics::context::GraphicsContext;
+use crate::graphics::{canvas, glutin_ext, GlBackendSpec};
+use crate::input::mouse;
+
+/// Identifies a callback added with
+/// [`add_reload_callback()`](fn.add_reload_callback.html), for
+/// removing it.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct ReloadId(usize);
+
+type ReloadCallback = Box<dyn FnMut(&mut Context) -> GameResult>;
+
+/// Whether the graphics are suspended, and what to do once the GL
+/// context has to be recreated.  Carried over to the new
+/// `GraphicsContext` when it is.
+#[derive(Default)]
+pub(crate) struct Lifecycle {
+    pub(crate) suspended: bool,
+    pub(crate) context_lost: bool,
+    generation: u64,
+    next_id: usize,
+    /// A callback is taken out of its place while it runs.
+    reload_callbacks: Vec<(ReloadId, Option<ReloadCallback>)>,
+}
+
+impl fmt::Debug for Lifecycle {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "<Lifecycle: suspended {}, context lost {}, generation {}, {} reload callbacks>",
+            self.suspended,
+            self.context_lost,
+            self.generation,
+            self.reload_callbacks.len()
+        )
+    }
+}
+
+impl Lifecycle {
+    fn add_callback(&mut self, callback: ReloadCallback) -> ReloadId {
+        let id = ReloadId(self.next_id);
+        self.next_id += 1;
+        self.reload_callbacks.push((id, Some(callback)));
+        id
+    }
+
+    fn remove_callback(&mut self, id: ReloadId) -> bool {
+        let count = self.reload_callbacks.len();
+        self.reload_callbacks.retain(|&(i, _)| i != id);
+        self.reload_callbacks.len() != count
+    }
+
+    /// Takes the callback with the given id out to run it, keeping its
+    /// place so it can still be removed meanwhile.
+    fn take_callback(&mut self, id: ReloadId) -> Option<ReloadCallback> {
+        self.reload_callbacks
+            .iter_mut()
+            .find(|(i, _)| *i == id)
+            .and_then(|(_, callback)| callback.take())
+    }
+
+    /// Puts back a callback taken with `take_callback()`, unless it was
+    /// removed while it ran.
+    fn restore_callback(&mut self, id: ReloadId, callback: ReloadCallback) {
+        if let Some((_, place)) = self.reload_callbacks.iter_mut().find(|(i, _)| *i == id) {
+            *place = Some(callback);
+        }
+    }
+}
+
+/// Returns whether the graphics are suspended, between
+/// [`suspend()`](fn.suspend.html) and [`resume()`](fn.resume.html).
+/// Nothing drawn then is shown.
+pub fn is_suspended(ctx: &Context) -> bool {
+    ctx.gfx_context.lifecycle.suspended
+}
+
+/// Suspends the graphics, e.g. when an Android app goes to the
+/// background, which destroys the surface of its window.  Drawing
+/// still works, but `present()` shows nothing until
+/// [`resume()`](fn.resume.html).
+///
+/// `Context::process_event()` calls this on `Event::Suspended`, and
+/// feeding it that event is also how to try it out on the desktop.
+pub fn suspend(ctx: &mut Context) -> GameResult {
+    let gfx = &mut ctx.gfx_context;
+    if gfx.lifecycle.suspended {
+        return Ok(());
+    }
+    gfx.encoder.flush(&mut *gfx.device);
+    glutin_ext::destroy_surface(&gfx.window);
+    gfx.lifecycle.suspended = true;
+    Ok(())
+}
+
+/// Resumes the graphics after [`suspend()`](fn.suspend.html), making
+/// a new surface for the window on Android.  Images, meshes, canvases
+/// and shaders normally survive that, but if the GL context itself was
+/// lost in the meantime, [`is_context_lost()`](fn.is_context_lost.html)
+/// says so afterwards and [`recreate_context()`](fn.recreate_context.html)
+/// has to be called.
+///
+/// `Context::process_event()` calls this on `Event::Resumed`.
+pub fn resume(ctx: &mut Context) -> GameResult {
+    let gfx = &mut ctx.gfx_context;
+    if !gfx.lifecycle.suspended {
+        return Ok(());
+    }
+    glutin_ext::create_surface(&gfx.window).map_err(GameError::WindowError)?;
+    gfx.lifecycle.suspended = false;
+    match gfx.window.make_current() {
+        Ok(()) => {
+            // The new surface may not be the size of the old one.
+            gfx.resize_viewport();
+            Ok(())
+        }
+        Err(glutin::ContextError::ContextLost) => {
+            gfx.lifecycle.context_lost = true;
+            Ok(())
+        }
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Returns whether the GL context has been lost, which makes every
+/// image, mesh, canvas, shader and font useless.  This shows after
+/// [`resume()`](fn.resume.html) or when `present()` fails to show a
+/// frame, and lasts until [`recreate_context()`](fn.recreate_context.html).
+pub fn is_context_lost(ctx: &Context) -> bool {
+    ctx.gfx_context.lifecycle.context_lost
+}
+
+/// Counts the times [`recreate_context()`](fn.recreate_context.html)
+/// has been called.  Graphics resources made while it was different
+/// belong to a context that's gone, so this tells whether ones kept
+/// around need to be made again.
+pub fn context_generation(ctx: &Context) -> u64 {
+    ctx.gfx_context.lifecycle.generation
+}
+
+/// Adds a callback that `recreate_context()` calls once the new GL
+/// context is ready, to make again the images, meshes, canvases,
+/// shaders and fonts the game keeps, e.g. by loading them from their
+/// files again or from [`ImageData`](struct.ImageData.html) copies
+/// kept in memory.  Callbacks are called in the order they were added.
+pub fn add_reload_callback<F>(ctx: &mut Context, callback: F) -> ReloadId
+where
+    F: FnMut(&mut Context) -> GameResult + 'static,
+{
+    ctx.gfx_context.lifecycle.add_callback(Box::new(callback))
+}
+
+/// Removes a callback added with
+/// [`add_reload_callback()`](fn.add_reload_callback.html).  Returns
+/// whether there was one to remove.
+pub fn remove_reload_callback(ctx: &mut Context, id: ReloadId) -> bool {
+    ctx.gfx_context.lifecycle.remove_callback(id)
+}
+
+/// Throws away the graphics context and creates a new one, with a new
+/// window on the desktop, in the current window mode, then calls the
+/// callbacks added with [`add_reload_callback()`](fn.add_reload_callback.html).
+/// This is what to do when [`is_context_lost()`](fn.is_context_lost.html),
+/// but it also works any other time, e.g. to test the reload callbacks.
+///
+/// Everything made with the old context has to be made again,
+/// including fonts; in debug builds using one of those panics.
+/// Windows opened with `create_window()` are closed, and the image set
+/// with `mouse::set_cursor_image()` is removed.  Callbacks can add and
+/// remove callbacks, including themselves; ones added aren't called
+/// until the next time.
+pub fn recreate_context(ctx: &mut Context, event_loop: &EventLoopWindowTarget<()>) -> GameResult {
+    // The new context can't make a surface for a native window that
+    // still has one.
+    suspend(ctx)?;
+    // Resources made with the old context must not pass for ones made
+    // with the new one, so it gets an ID of its own.
+    let debug_id = DebugId::new();
+    let backend = GlBackendSpec::from(ctx.conf.backend);
+    let gfx = GraphicsContext::new(
+        &mut ctx.filesystem,
+        event_loop,
+        &ctx.conf.window_setup,
+        ctx.conf.window_mode,
+        backend,
+        debug_id,
+    )?;
+    let mut old = mem::replace(&mut ctx.gfx_context, gfx);
+    ctx.debug_id = debug_id;
+    // Let the old context delete its own GL objects, if it still can.
+    let _ = old.window.make_current();
+    let mut lifecycle = mem::replace(&mut old.lifecycle, Lifecycle::default());
+    drop(old);
+    ctx.gfx_context.window.make_current()?;
+    lifecycle.suspended = false;
+    lifecycle.context_lost = false;
+    lifecycle.generation += 1;
+    ctx.gfx_context.lifecycle = lifecycle;
+    mouse::set_cursor_image(ctx, None, [0.0, 0.0]);
+    canvas::update_pixel_canvas(ctx)?;
+
+    let ids: Vec<ReloadId> = ctx
+        .gfx_context
+        .lifecycle
+        .reload_callbacks
+        .iter()
+        .map(|&(id, _)| id)
+        .collect();
+    ids.into_iter().try_for_each(|id| {
+        // Gone if an earlier callback removed it.
+        match ctx.gfx_context.lifecycle.take_callback(id) {
+            Some(mut callback) => {
+                let result = callback(ctx);
+                ctx.gfx_context.lifecycle.restore_callback(id, callback);
+                result
+            }
+            None => Ok(()),
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn headless_test_reload_callbacks() {
+        let mut lifecycle = Lifecycle::default();
+        let first = lifecycle.add_callback(Box::new(|_| Ok(())));
+        let second = lifecycle.add_callback(Box::new(|_| Ok(())));
+        assert_ne!(first, second);
+        assert!(lifecycle.remove_callback(first));
+        assert!(!lifecycle.remove_callback(first));
+        let ids: Vec<ReloadId> = lifecycle
+            .reload_callbacks
+            .iter()
+            .map(|&(id, _)| id)
+            .collect();
+        assert_eq!(ids, vec![second]);
+
+        // A running callback can be removed, and isn't put back then.
+        let callback = lifecycle.take_callback(second).unwrap();
+        assert!(lifecycle.take_callback(second).is_none());
+        assert!(lifecycle.remove_callback(second));
+        lifecycle.restore_callback(second, callback);
+        assert!(lifecycle.reload_callbacks.is_empty());
+    }
+}
@@ -1,3 +1,5 @@
+use std::borrow::Cow;
+
 use crate::context::DebugId;
 use crate::error::GameError;
 use crate::graphics::*;
@@ -65,6 +67,8 @@ pub use self::t::{FillOptions, FillRule, LineCap, LineJoin, StrokeOptions};
 pub struct MeshBuilder {
     buffer: t::geometry_builder::VertexBuffers<Vertex, u32>,
     image: Option<Image>,
+    gradient: Option<Gradient>,
+    uv_bounds: Option<Rect>,
 }
 
 impl Default for MeshBuilder {
@@ -72,6 +76,8 @@ impl Default for MeshBuilder {
         Self {
             buffer: t::VertexBuffers::new(),
             image: None,
+            gradient: None,
+            uv_bounds: None,
         }
     }
 }
@@ -82,6 +88,66 @@ impl MeshBuilder {
         Self::default()
     }
 
+    /// Fills all shapes added after this call with the given
+    /// [`Gradient`](struct.Gradient.html), until
+    /// [`solid()`](#method.solid) is called.
+    ///
+    /// The gradient's color is computed for every vertex and multiplied
+    /// with the color passed to each shape, so use `WHITE` to get the
+    /// gradient as it is.  Colors are only interpolated between vertices,
+    /// so gradients with several stops need reasonably detailed geometry;
+    /// for large, simple shapes see
+    /// [`texture_gradient()`](#method.texture_gradient).
+    pub fn gradient(&mut self, gradient: Gradient) -> &mut Self {
+        self.gradient = Some(gradient);
+        self.uv_bounds = None;
+        self
+    }
+
+    /// Renders the given [`Gradient`](struct.Gradient.html) into a
+    /// texture covering `bounds` and uses it as this mesh's texture,
+    /// mapping all shapes added after this call onto it, until
+    /// [`solid()`](#method.solid) is called.
+    ///
+    /// Unlike [`gradient()`](#method.gradient) this gives smooth results
+    /// no matter how few vertices a shape has, at the cost of one image
+    /// per mesh.  Shapes are still tinted with their own color, and any
+    /// texture set with [`texture()`](#method.texture) is replaced.
+    ///
+    /// `bounds` must have a non-zero width and height.
+    pub fn texture_gradient(
+        &mut self,
+        ctx: &mut Context,
+        gradient: &Gradient,
+        bounds: Rect,
+    ) -> GameResult<&mut Self> {
+        // The texture coordinates of the shapes are divided by the size.
+        if !bounds.w.is_normal() || !bounds.h.is_normal() {
+            let msg = format!(
+                "Tried to map a gradient texture onto {:?}, which has no area",
+                bounds
+            );
+            return Err(GameError::LyonError(msg));
+        }
+        // Roughly one texel per pixel, within reason.
+        let size = |length: f32| length.abs().ceil().max(1.0).min(1024.0) as u16;
+        let image = gradient.to_image(ctx, bounds, size(bounds.w), size(bounds.h))?;
+        self.image = Some(image);
+        self.gradient = None;
+        self.uv_bounds = Some(bounds);
+        Ok(self)
+    }
+
+    /// Goes back to filling shapes with flat colors, after a call to
+    /// [`gradient()`](#method.gradient) or
+    /// [`texture_gradient()`](#method.texture_gradient).
+    /// A texture created by `texture_gradient()` stays on the mesh.
+    pub fn solid(&mut self) -> &mut Self {
+        self.gradient = None;
+        self.uv_bounds = None;
+        self
+    }
+
     /// Create a new mesh for a line of one or more connected segments.
     pub fn line<P>(&mut self, points: &[P], width: f32, color: Color) -> GameResult<&mut Self>
     where
@@ -107,9 +173,7 @@ impl MeshBuilder {
         {
             let point = point.into();
             let buffers = &mut self.buffer;
-            let vb = VertexBuilder {
-                color: LinearColor::from(color),
-            };
+            let vb = VertexBuilder::new(color, &self.gradient, self.uv_bounds);
             match mode {
                 DrawMode::Fill(fill_options) => {
                     let builder = &mut t::BuffersBuilder::new(buffers, vb);
@@ -152,9 +216,7 @@ impl MeshBuilder {
         {
             let buffers = &mut self.buffer;
             let point = point.into();
-            let vb = VertexBuilder {
-                color: LinearColor::from(color),
-            };
+            let vb = VertexBuilder::new(color, &self.gradient, self.uv_bounds);
             match mode {
                 DrawMode::Fill(fill_options) => {
                     let builder = &mut t::BuffersBuilder::new(buffers, vb);
@@ -238,9 +300,7 @@ impl MeshBuilder {
                 let mint_point: mint::Point2<f32> = p.into();
                 t::math::point(mint_point.x, mint_point.y)
             });
-            let vb = VertexBuilder {
-                color: LinearColor::from(color),
-            };
+            let vb = VertexBuilder::new(color, &self.gradient, self.uv_bounds);
             match mode {
                 DrawMode::Fill(options) => {
                     let builder = &mut t::BuffersBuilder::new(buffers, vb);
@@ -261,9 +321,7 @@ impl MeshBuilder {
         {
             let buffers = &mut self.buffer;
             let rect = t::math::rect(bounds.x, bounds.y, bounds.w, bounds.h);
-            let vb = VertexBuilder {
-                color: LinearColor::from(color),
-            };
+            let vb = VertexBuilder::new(color, &self.gradient, self.uv_bounds);
             match mode {
                 DrawMode::Fill(fill_options) => {
                     let builder = &mut t::BuffersBuilder::new(buffers, vb);
@@ -278,6 +336,262 @@ impl MeshBuilder {
         self
     }
 
+    /// Create a new mesh for a rectangle with rounded corners.
+    ///
+    /// The `radius` is clamped by lyon so that opposite corners never overlap.
+    pub fn rounded_rectangle(
+        &mut self,
+        mode: DrawMode,
+        bounds: Rect,
+        radius: f32,
+        color: Color,
+    ) -> &mut Self {
+        {
+            let buffers = &mut self.buffer;
+            let rect = t::math::rect(bounds.x, bounds.y, bounds.w, bounds.h);
+            let radii = t::basic_shapes::BorderRadii::new_all_same(radius);
+            let vb = VertexBuilder::new(color, &self.gradient, self.uv_bounds);
+            match mode {
+                DrawMode::Fill(fill_options) => {
+                    let builder = &mut t::BuffersBuilder::new(buffers, vb);
+                    let _ = t::basic_shapes::fill_rounded_rectangle(
+                        &rect,
+                        &radii,
+                        &fill_options,
+                        builder,
+                    );
+                }
+                DrawMode::Stroke(options) => {
+                    let builder = &mut t::BuffersBuilder::new(buffers, vb);
+                    let _ = t::basic_shapes::stroke_rounded_rectangle(
+                        &rect,
+                        &radii,
+                        &options,
+                        builder,
+                    );
+                }
+            };
+        }
+        self
+    }
+
+    /// Create a new mesh for a circular arc around `center`, starting at
+    /// `start_angle` and sweeping `sweep_angle` radians clockwise.
+    ///
+    /// Filling an arc fills the area between it and its chord; see
+    /// [`pie()`](#method.pie) for a slice that includes the center.
+    ///
+    /// For the meaning of the `tolerance` parameter, [see here](https://docs.rs/lyon_geom/0.11.0/lyon_geom/#flattening).
+    pub fn arc<P>(
+        &mut self,
+        mode: DrawMode,
+        center: P,
+        radius: f32,
+        start_angle: f32,
+        sweep_angle: f32,
+        tolerance: f32,
+        color: Color,
+    ) -> GameResult<&mut Self>
+    where
+        P: Into<mint::Point2<f32>>,
+    {
+        let center = center.into();
+        let start = mint::Point2 {
+            x: center.x + radius * start_angle.cos(),
+            y: center.y + radius * start_angle.sin(),
+        };
+        let mut path = PathBuilder::new();
+        let _ = path
+            .move_to(start)
+            .arc(center, [radius, radius], sweep_angle, 0.0);
+        if let DrawMode::Fill(_) = mode {
+            let _ = path.close();
+        }
+        self.path(with_tolerance(mode, tolerance), &path, color)
+    }
+
+    /// Create a new mesh for a pie slice: a circular arc around `center`
+    /// that is closed off by two lines back to the center.
+    ///
+    /// For the meaning of the `tolerance` parameter, [see here](https://docs.rs/lyon_geom/0.11.0/lyon_geom/#flattening).
+    pub fn pie<P>(
+        &mut self,
+        mode: DrawMode,
+        center: P,
+        radius: f32,
+        start_angle: f32,
+        sweep_angle: f32,
+        tolerance: f32,
+        color: Color,
+    ) -> GameResult<&mut Self>
+    where
+        P: Into<mint::Point2<f32>>,
+    {
+        let center = center.into();
+        let start = mint::Point2 {
+            x: center.x + radius * start_angle.cos(),
+            y: center.y + radius * start_angle.sin(),
+        };
+        let mut path = PathBuilder::new();
+        let _ = path
+            .move_to(center)
+            .line_to(start)
+            .arc(center, [radius, radius], sweep_angle, 0.0)
+            .close();
+        self.path(with_tolerance(mode, tolerance), &path, color)
+    }
+
+    /// Create a new mesh for a quadratic bezier curve from `from` to `to`.
+    ///
+    /// Filling the curve fills the area between it and the straight
+    /// line from `from` to `to`.
+    ///
+    /// For the meaning of the `tolerance` parameter, [see here](https://docs.rs/lyon_geom/0.11.0/lyon_geom/#flattening).
+    pub fn quadratic_bezier<P>(
+        &mut self,
+        mode: DrawMode,
+        from: P,
+        ctrl: P,
+        to: P,
+        tolerance: f32,
+        color: Color,
+    ) -> GameResult<&mut Self>
+    where
+        P: Into<mint::Point2<f32>>,
+    {
+        let mut path = PathBuilder::new();
+        let _ = path.move_to(from).quadratic_bezier_to(ctrl, to);
+        if let DrawMode::Fill(_) = mode {
+            let _ = path.close();
+        }
+        self.path(with_tolerance(mode, tolerance), &path, color)
+    }
+
+    /// Create a new mesh for a cubic bezier curve from `from` to `to`.
+    ///
+    /// Filling the curve fills the area between it and the straight
+    /// line from `from` to `to`.
+    ///
+    /// For the meaning of the `tolerance` parameter, [see here](https://docs.rs/lyon_geom/0.11.0/lyon_geom/#flattening).
+    pub fn cubic_bezier<P>(
+        &mut self,
+        mode: DrawMode,
+        from: P,
+        ctrl1: P,
+        ctrl2: P,
+        to: P,
+        tolerance: f32,
+        color: Color,
+    ) -> GameResult<&mut Self>
+    where
+        P: Into<mint::Point2<f32>>,
+    {
+        let mut path = PathBuilder::new();
+        let _ = path.move_to(from).cubic_bezier_to(ctrl1, ctrl2, to);
+        if let DrawMode::Fill(_) = mode {
+            let _ = path.close();
+        }
+        self.path(with_tolerance(mode, tolerance), &path, color)
+    }
+
+    /// Create a new mesh for a dashed line of one or more connected segments.
+    ///
+    /// The line alternates between `dash_length` units drawn and
+    /// `gap_length` units skipped, continuing the pattern around corners.
+    pub fn dashed_line<P>(
+        &mut self,
+        points: &[P],
+        width: f32,
+        dash_length: f32,
+        gap_length: f32,
+        color: Color,
+    ) -> GameResult<&mut Self>
+    where
+        P: Into<mint::Point2<f32>> + Clone,
+    {
+        let options = StrokeOptions::default().with_line_width(width);
+        self.dashed_polyline(options, points, dash_length, gap_length, color)
+    }
+
+    /// Create a new mesh for a dashed series of connected lines, stroked
+    /// with the given `StrokeOptions`.
+    pub fn dashed_polyline<P>(
+        &mut self,
+        options: StrokeOptions,
+        points: &[P],
+        dash_length: f32,
+        gap_length: f32,
+        color: Color,
+    ) -> GameResult<&mut Self>
+    where
+        P: Into<mint::Point2<f32>> + Clone,
+    {
+        if points.len() < 2 {
+            return Err(GameError::LyonError(
+                "MeshBuilder::dashed_polyline() got a list of < 2 points".to_string(),
+            ));
+        }
+        if dash_length <= 0.0 || gap_length < 0.0 {
+            return Err(GameError::LyonError(
+                "MeshBuilder::dashed_polyline() needs a positive dash length and a non-negative gap length"
+                    .to_string(),
+            ));
+        }
+        let points = points
+            .iter()
+            .cloned()
+            .map(|p| {
+                let p: mint::Point2<f32> = p.into();
+                Point2::from(p)
+            })
+            .collect::<Vec<_>>();
+        for dash in dash_polyline(&points, dash_length, gap_length) {
+            if dash.len() >= 2 {
+                let _ = self.polyline_inner(DrawMode::Stroke(options), &dash, false, color)?;
+            }
+        }
+        Ok(self)
+    }
+
+    /// Create a new mesh from an arbitrary [`PathBuilder`](struct.PathBuilder.html)
+    /// path, which can be made of any mix of lines, bezier curves and arcs.
+    ///
+    /// ```rust,no_run
+    /// # use ggez::*;
+    /// # use ggez::graphics::*;
+    /// # fn main() -> GameResult {
+    /// # let ctx = &mut ContextBuilder::new("foo", "bar").build().unwrap().0;
+    /// let mut path = PathBuilder::new();
+    /// path.move_to([0.0, 0.0])
+    ///     .line_to([100.0, 0.0])
+    ///     .quadratic_bezier_to([150.0, 50.0], [100.0, 100.0])
+    ///     .close();
+    /// let mesh = MeshBuilder::new()
+    ///     .path(DrawMode::fill(), &path, WHITE)?
+    ///     .build(ctx)?;
+    /// # Ok(()) }
+    /// ```
+    pub fn path(&mut self, mode: DrawMode, path: &PathBuilder, color: Color) -> GameResult<&mut Self> {
+        {
+            let path = path.to_lyon_path()?;
+            let buffers = &mut self.buffer;
+            let vb = VertexBuilder::new(color, &self.gradient, self.uv_bounds);
+            match mode {
+                DrawMode::Fill(options) => {
+                    let builder = &mut t::BuffersBuilder::new(buffers, vb);
+                    let tessellator = &mut t::FillTessellator::new();
+                    let _ = tessellator.tessellate_path(&path, &options, builder)?;
+                }
+                DrawMode::Stroke(options) => {
+                    let builder = &mut t::BuffersBuilder::new(buffers, vb);
+                    let tessellator = &mut t::StrokeTessellator::new();
+                    let _ = tessellator.tessellate_path(&path, &options, builder);
+                }
+            };
+        }
+        Ok(self)
+    }
+
     /// Create a new [`Mesh`](struct.Mesh.html) from a raw list of triangles.
     /// The length of the list must be a multiple of 3.
     ///
@@ -313,9 +627,7 @@ impl MeshBuilder {
                 // nicer, so we'll just live with it.
                 .collect::<Vec<_>>();
             let tris = tris.chunks(3);
-            let vb = VertexBuilder {
-                color: LinearColor::from(color),
-            };
+            let vb = VertexBuilder::new(color, &self.gradient, self.uv_bounds);
             let builder: &mut t::BuffersBuilder<_, _, _, _> =
                 &mut t::BuffersBuilder::new(&mut self.buffer, vb);
             use lyon::tessellation::GeometryBuilder;
@@ -368,6 +680,41 @@ impl MeshBuilder {
         self
     }
 
+    /// The vertices accumulated so far.
+    pub(crate) fn vertices(&self) -> &[Vertex] {
+        &self.buffer.vertices
+    }
+
+    /// The indices accumulated so far.
+    pub(crate) fn indices(&self) -> &[u32] {
+        &self.buffer.indices
+    }
+
+    /// Appends all the geometry of `other` to this builder, passing every
+    /// vertex position through `transform`.  Fill UVs follow the position,
+    /// as they do for geometry tessellated directly into this builder.
+    pub(crate) fn append_transformed<F>(&mut self, other: &MeshBuilder, transform: F) -> &mut Self
+    where
+        F: Fn([f32; 2]) -> [f32; 2],
+    {
+        let next_idx = self.buffer.vertices.len() as u32;
+        self.buffer
+            .vertices
+            .extend(other.buffer.vertices.iter().map(|v| {
+                let pos = transform(v.pos);
+                let uv = if v.uv == v.pos { pos } else { v.uv };
+                Vertex {
+                    pos,
+                    uv,
+                    color: v.color,
+                }
+            }));
+        self.buffer
+            .indices
+            .extend(other.buffer.indices.iter().map(|i| i + next_idx));
+        self
+    }
+
     /// Takes the accumulated geometry and load it into GPU memory,
     /// creating a single `Mesh`.
     pub fn build(&self, ctx: &mut Context) -> GameResult<Mesh> {
@@ -380,27 +727,270 @@ impl MeshBuilder {
     }
 }
 
-#[derive(Copy, Clone, PartialEq, Debug)]
+/// A builder for arbitrary 2D paths made of lines, bezier curves and arcs,
+/// which can then be filled or stroked with
+/// [`MeshBuilder::path()`](struct.MeshBuilder.html#method.path).
+///
+/// This mirrors [lyon's path builder](https://docs.rs/lyon_path/),
+/// but takes any point type that converts into a `mint::Point2`.
+/// Every sub-path has to start with a [`move_to()`](#method.move_to).
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PathBuilder {
+    commands: Vec<PathCommand>,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum PathCommand {
+    MoveTo(mint::Point2<f32>),
+    LineTo(mint::Point2<f32>),
+    QuadraticTo(mint::Point2<f32>, mint::Point2<f32>),
+    CubicTo(mint::Point2<f32>, mint::Point2<f32>, mint::Point2<f32>),
+    Arc {
+        center: mint::Point2<f32>,
+        radii: mint::Vector2<f32>,
+        sweep_angle: f32,
+        x_rotation: f32,
+    },
+    Close,
+}
+
+impl PathBuilder {
+    /// Create a new, empty `PathBuilder`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Starts a new sub-path at the given point.
+    pub fn move_to<P>(&mut self, to: P) -> &mut Self
+    where
+        P: Into<mint::Point2<f32>>,
+    {
+        self.commands.push(PathCommand::MoveTo(to.into()));
+        self
+    }
+
+    /// Adds a straight line from the current position to the given point.
+    pub fn line_to<P>(&mut self, to: P) -> &mut Self
+    where
+        P: Into<mint::Point2<f32>>,
+    {
+        self.commands.push(PathCommand::LineTo(to.into()));
+        self
+    }
+
+    /// Adds a quadratic bezier curve from the current position to `to`,
+    /// using `ctrl` as the control point.
+    pub fn quadratic_bezier_to<P>(&mut self, ctrl: P, to: P) -> &mut Self
+    where
+        P: Into<mint::Point2<f32>>,
+    {
+        self.commands
+            .push(PathCommand::QuadraticTo(ctrl.into(), to.into()));
+        self
+    }
+
+    /// Adds a cubic bezier curve from the current position to `to`,
+    /// using `ctrl1` and `ctrl2` as the control points.
+    pub fn cubic_bezier_to<P>(&mut self, ctrl1: P, ctrl2: P, to: P) -> &mut Self
+    where
+        P: Into<mint::Point2<f32>>,
+    {
+        self.commands
+            .push(PathCommand::CubicTo(ctrl1.into(), ctrl2.into(), to.into()));
+        self
+    }
+
+    /// Adds an elliptic arc around `center`, starting from the current
+    /// position and sweeping `sweep_angle` radians.  `x_rotation` rotates
+    /// the ellipse's axes, in radians.
+    pub fn arc<P, V>(&mut self, center: P, radii: V, sweep_angle: f32, x_rotation: f32) -> &mut Self
+    where
+        P: Into<mint::Point2<f32>>,
+        V: Into<mint::Vector2<f32>>,
+    {
+        self.commands.push(PathCommand::Arc {
+            center: center.into(),
+            radii: radii.into(),
+            sweep_angle,
+            x_rotation,
+        });
+        self
+    }
+
+    /// Closes the current sub-path with a straight line back to its start.
+    pub fn close(&mut self) -> &mut Self {
+        self.commands.push(PathCommand::Close);
+        self
+    }
+
+    /// Returns `true` if nothing has been added to the path yet.
+    pub fn is_empty(&self) -> bool {
+        self.commands.is_empty()
+    }
+
+    /// Turns the recorded commands into an actual lyon path.
+    pub(crate) fn to_lyon_path(&self) -> GameResult<lyon::path::Path> {
+        use lyon::path::builder::*;
+
+        fn p(p: mint::Point2<f32>) -> t::math::Point {
+            t::math::point(p.x, p.y)
+        }
+
+        match self.commands.first() {
+            Some(PathCommand::MoveTo(_)) => (),
+            Some(_) => {
+                return Err(GameError::LyonError(
+                    "PathBuilder paths must start with move_to()".to_string(),
+                ))
+            }
+            None => {
+                return Err(GameError::LyonError(
+                    "Tried to build an empty PathBuilder path".to_string(),
+                ))
+            }
+        }
+
+        let mut builder = lyon::path::Path::builder();
+        for command in &self.commands {
+            match *command {
+                PathCommand::MoveTo(to) => builder.move_to(p(to)),
+                PathCommand::LineTo(to) => builder.line_to(p(to)),
+                PathCommand::QuadraticTo(ctrl, to) => builder.quadratic_bezier_to(p(ctrl), p(to)),
+                PathCommand::CubicTo(ctrl1, ctrl2, to) => {
+                    builder.cubic_bezier_to(p(ctrl1), p(ctrl2), p(to))
+                }
+                PathCommand::Arc {
+                    center,
+                    radii,
+                    sweep_angle,
+                    x_rotation,
+                } => builder.arc(
+                    p(center),
+                    t::math::vector(radii.x, radii.y),
+                    t::math::Angle {
+                        radians: sweep_angle,
+                    },
+                    t::math::Angle {
+                        radians: x_rotation,
+                    },
+                ),
+                PathCommand::Close => builder.close(),
+            }
+        }
+        Ok(builder.build())
+    }
+}
+
+/// Applies a flattening tolerance to whichever options a `DrawMode` carries.
+fn with_tolerance(mode: DrawMode, tolerance: f32) -> DrawMode {
+    match mode {
+        DrawMode::Fill(options) => DrawMode::Fill(options.with_tolerance(tolerance)),
+        DrawMode::Stroke(options) => DrawMode::Stroke(options.with_tolerance(tolerance)),
+    }
+}
+
+/// Splits a polyline into the pieces that are "on" when it is drawn
+/// with a dash pattern of `dash` units on and `gap` units off.
+/// The pattern carries over from one segment to the next, so dashes
+/// bend around corners.
+fn dash_polyline(points: &[Point2], dash: f32, gap: f32) -> Vec<Vec<Point2>> {
+    let mut dashes = Vec::new();
+    let mut current = vec![points[0]];
+    // How far into the current dash or gap we are, and which one it is.
+    let mut drawing = true;
+    let mut remaining = dash;
+    for segment in points.windows(2) {
+        let (start, end) = (segment[0], segment[1]);
+        let length = na::distance(&start, &end);
+        let mut travelled = 0.0;
+        while length - travelled > remaining {
+            travelled += remaining;
+            let point = start + (end - start) * (travelled / length);
+            if drawing {
+                current.push(point);
+                dashes.push(std::mem::replace(&mut current, Vec::new()));
+                remaining = gap;
+            } else {
+                current.push(point);
+                remaining = dash;
+            }
+            drawing = !drawing;
+        }
+        remaining -= length - travelled;
+        if drawing {
+            current.push(end);
+        }
+    }
+    if drawing && current.len() >= 2 {
+        dashes.push(current);
+    }
+    dashes
+}
+
+#[derive(Clone, PartialEq, Debug)]
 struct VertexBuilder {
-    color: LinearColor,
+    color: Color,
+    gradient: Option<Gradient>,
+    uv_bounds: Option<Rect>,
+}
+
+impl VertexBuilder {
+    fn new(color: Color, gradient: &Option<Gradient>, uv_bounds: Option<Rect>) -> Self {
+        VertexBuilder {
+            color,
+            gradient: gradient.clone(),
+            uv_bounds,
+        }
+    }
+
+    /// The vertex color at the given position.
+    fn color_at(&self, position: t::math::Point) -> [f32; 4] {
+        let color = match self.gradient {
+            Some(ref gradient) => {
+                let g = gradient.color_at([position.x, position.y]);
+                Color::new(
+                    g.r * self.color.r,
+                    g.g * self.color.g,
+                    g.b * self.color.b,
+                    g.a * self.color.a,
+                )
+            }
+            None => self.color,
+        };
+        color.into()
+    }
+
+    /// Texture coordinates that map `uv_bounds` onto the whole texture.
+    fn bounded_uv(&self, position: t::math::Point) -> Option<[f32; 2]> {
+        self.uv_bounds.map(|b| {
+            [
+                (position.x - b.x) / b.w,
+                (position.y - b.y) / b.h,
+            ]
+        })
+    }
 }
 
 impl t::VertexConstructor<t::FillVertex, Vertex> for VertexBuilder {
     fn new_vertex(&mut self, vertex: t::FillVertex) -> Vertex {
+        let position = vertex.position;
         Vertex {
-            pos: [vertex.position.x, vertex.position.y],
-            uv: [vertex.position.x, vertex.position.y],
-            color: self.color.into(),
+            pos: [position.x, position.y],
+            uv: self
+                .bounded_uv(position)
+                .unwrap_or([position.x, position.y]),
+            color: self.color_at(position),
         }
     }
 }
 
 impl t::VertexConstructor<t::StrokeVertex, Vertex> for VertexBuilder {
     fn new_vertex(&mut self, vertex: t::StrokeVertex) -> Vertex {
+        let position = vertex.position;
         Vertex {
-            pos: [vertex.position.x, vertex.position.y],
-            uv: [0.0, 0.0],
-            color: self.color.into(),
+            pos: [position.x, position.y],
+            uv: self.bounded_uv(position).unwrap_or([0.0, 0.0]),
+            color: self.color_at(position),
         }
     }
 }
@@ -519,6 +1109,139 @@ impl Mesh {
         mb.build(ctx)
     }
 
+    /// Create a new mesh for a rectangle filled or outlined with a
+    /// [`Gradient`](struct.Gradient.html).  The gradient is rendered into
+    /// a texture, so any number of stops comes out smooth.
+    pub fn new_rectangle_gradient(
+        ctx: &mut Context,
+        mode: DrawMode,
+        bounds: Rect,
+        gradient: &Gradient,
+    ) -> GameResult<Mesh> {
+        let mut mb = MeshBuilder::new();
+        let _ = mb
+            .texture_gradient(ctx, gradient, bounds)?
+            .rectangle(mode, bounds, WHITE);
+        mb.build(ctx)
+    }
+
+    /// Create a new mesh for a rectangle with rounded corners.
+    pub fn new_rounded_rectangle(
+        ctx: &mut Context,
+        mode: DrawMode,
+        bounds: Rect,
+        radius: f32,
+        color: Color,
+    ) -> GameResult<Mesh> {
+        let mut mb = MeshBuilder::new();
+        let _ = mb.rounded_rectangle(mode, bounds, radius, color);
+        mb.build(ctx)
+    }
+
+    /// Create a new mesh for a circular arc.
+    pub fn new_arc<P>(
+        ctx: &mut Context,
+        mode: DrawMode,
+        center: P,
+        radius: f32,
+        start_angle: f32,
+        sweep_angle: f32,
+        tolerance: f32,
+        color: Color,
+    ) -> GameResult<Mesh>
+    where
+        P: Into<mint::Point2<f32>>,
+    {
+        let mut mb = MeshBuilder::new();
+        let _ = mb.arc(mode, center, radius, start_angle, sweep_angle, tolerance, color)?;
+        mb.build(ctx)
+    }
+
+    /// Create a new mesh for a pie slice.
+    pub fn new_pie<P>(
+        ctx: &mut Context,
+        mode: DrawMode,
+        center: P,
+        radius: f32,
+        start_angle: f32,
+        sweep_angle: f32,
+        tolerance: f32,
+        color: Color,
+    ) -> GameResult<Mesh>
+    where
+        P: Into<mint::Point2<f32>>,
+    {
+        let mut mb = MeshBuilder::new();
+        let _ = mb.pie(mode, center, radius, start_angle, sweep_angle, tolerance, color)?;
+        mb.build(ctx)
+    }
+
+    /// Create a new mesh for a quadratic bezier curve.
+    pub fn new_quadratic_bezier<P>(
+        ctx: &mut Context,
+        mode: DrawMode,
+        from: P,
+        ctrl: P,
+        to: P,
+        tolerance: f32,
+        color: Color,
+    ) -> GameResult<Mesh>
+    where
+        P: Into<mint::Point2<f32>>,
+    {
+        let mut mb = MeshBuilder::new();
+        let _ = mb.quadratic_bezier(mode, from, ctrl, to, tolerance, color)?;
+        mb.build(ctx)
+    }
+
+    /// Create a new mesh for a cubic bezier curve.
+    pub fn new_cubic_bezier<P>(
+        ctx: &mut Context,
+        mode: DrawMode,
+        from: P,
+        ctrl1: P,
+        ctrl2: P,
+        to: P,
+        tolerance: f32,
+        color: Color,
+    ) -> GameResult<Mesh>
+    where
+        P: Into<mint::Point2<f32>>,
+    {
+        let mut mb = MeshBuilder::new();
+        let _ = mb.cubic_bezier(mode, from, ctrl1, ctrl2, to, tolerance, color)?;
+        mb.build(ctx)
+    }
+
+    /// Create a new mesh for a dashed line of one or more connected segments.
+    pub fn new_dashed_line<P>(
+        ctx: &mut Context,
+        points: &[P],
+        width: f32,
+        dash_length: f32,
+        gap_length: f32,
+        color: Color,
+    ) -> GameResult<Mesh>
+    where
+        P: Into<mint::Point2<f32>> + Clone,
+    {
+        let mut mb = MeshBuilder::new();
+        let _ = mb.dashed_line(points, width, dash_length, gap_length, color)?;
+        mb.build(ctx)
+    }
+
+    /// Create a new mesh from a [`PathBuilder`](struct.PathBuilder.html) path.
+    pub fn new_path(
+        ctx: &mut Context,
+        mode: DrawMode,
+        path: &PathBuilder,
+        color: Color,
+    ) -> GameResult<Mesh> {
+        let mut mb = MeshBuilder::new();
+        let _ = mb.path(mode, path, color)?;
+        mb.build(ctx)
+    }
+
     /// Create a new `Mesh` from a raw list of triangle points.
     pub fn from_triangles<P>(ctx: &mut Context, triangles: &[P], color: Color) -> GameResult<Mesh>
     where
@@ -581,8 +1304,13 @@ impl Mesh {
             return Err(GameError::LyonError(msg));
         }
 
-        let verts: Vec<Vertex> = verts.iter().cloned().map(Into::into).collect();
+        let mut verts: Vec<Vertex> = verts.iter().cloned().map(Into::into).collect();
         let rect = bbox_for_vertices(&verts).expect("No vertices in MeshBuilder");
+        if ctx.gfx_context.is_srgb() {
+            for vert in &mut verts {
+                vert.color = linearize_vertex_color(vert.color);
+            }
+        }
         let (vbuf, slice) = ctx
             .gfx_context
             .factory
@@ -617,10 +1345,11 @@ impl Mesh {
         // start at `Factory::create_vertex_buffer_with_slice()`, drill down to
         // <https://docs.rs/gfx/0.17.1/gfx/traits/trait.Factory.html#tymethod.create_buffer_raw>,
         // and fill in the bits between with the appropriate values.
+        let verts = gpu_vertices(ctx, verts);
         let (vbuf, slice) = ctx
             .gfx_context
             .factory
-            .create_vertex_buffer_with_slice(verts, indices);
+            .create_vertex_buffer_with_slice(&verts[..], indices);
         self.buffer = vbuf;
         self.slice = slice;
     }
@@ -629,6 +1358,7 @@ impl Mesh {
 impl Drawable for Mesh {
     fn draw(&self, ctx: &mut Context, param: DrawParam) -> GameResult {
         self.debug_id.assert(ctx);
+        self.image.resolve_multisample(ctx)?;
         let gfx = &mut ctx.gfx_context;
         gfx.update_instance_properties(param.into())?;
 
@@ -656,6 +1386,344 @@ impl Drawable for Mesh {
     }
 }
 
+/// A mesh backed by preallocated, writable GPU buffers, for geometry
+/// that changes often, such as trails, deformable terrain or debug shapes.
+///
+/// Unlike a [`Mesh`](struct.Mesh.html), which is immutable and has to be
+/// rebuilt to change, a `DynamicMesh` lets you overwrite parts of its
+/// vertex and index data in place with
+/// [`update_range()`](#method.update_range) and
+/// [`update_index_range()`](#method.update_index_range).  Writing past
+/// the current capacity grows the buffers geometrically, keeping the
+/// data that was already there.
+///
+/// You can also restrict drawing to a sub-range of the index buffer with
+/// [`set_draw_range()`](#method.set_draw_range).
+#[derive(Debug, Clone, PartialEq)]
+pub struct DynamicMesh {
+    vertex_buffer: gfx::handle::Buffer<gfx_device_gl::Resources, Vertex>,
+    index_buffer: gfx::handle::Buffer<gfx_device_gl::Resources, u32>,
+    vertex_count: usize,
+    index_count: usize,
+    draw_range: Option<(u32, u32)>,
+    blend_mode: Option<BlendMode>,
+    image: Image,
+    debug_id: DebugId,
+    rect: Option<Rect>,
+}
+
+impl DynamicMesh {
+    /// Create a new, empty `DynamicMesh` with room for the given number
+    /// of vertices and indices.  You may also supply an `Image` to use
+    /// as a texture, if you pass `None`, it will just use a pure white
+    /// texture.
+    pub fn new(
+        ctx: &mut Context,
+        vertex_capacity: usize,
+        index_capacity: usize,
+        texture: Option<Image>,
+    ) -> GameResult<DynamicMesh> {
+        let factory = &mut *ctx.gfx_context.factory;
+        let vertex_buffer =
+            create_dynamic_buffer(factory, vertex_capacity, gfx::buffer::Role::Vertex)?;
+        let index_buffer =
+            create_dynamic_buffer(factory, index_capacity, gfx::buffer::Role::Index)?;
+        Ok(DynamicMesh {
+            vertex_buffer,
+            index_buffer,
+            vertex_count: 0,
+            index_count: 0,
+            draw_range: None,
+            blend_mode: None,
+            image: texture.unwrap_or_else(|| ctx.gfx_context.white_image.clone()),
+            debug_id: DebugId::get(ctx),
+            rect: None,
+        })
+    }
+
+    /// Returns how many vertices fit in the vertex buffer before it has to grow.
+    pub fn vertex_capacity(&self) -> usize {
+        self.vertex_buffer.len()
+    }
+
+    /// Returns how many indices fit in the index buffer before it has to grow.
+    pub fn index_capacity(&self) -> usize {
+        self.index_buffer.len()
+    }
+
+    /// Returns the number of vertices that have been written so far.
+    pub fn vertex_count(&self) -> usize {
+        self.vertex_count
+    }
+
+    /// Returns the number of indices that have been written so far.
+    pub fn index_count(&self) -> usize {
+        self.index_count
+    }
+
+    /// Overwrites the vertices starting at `offset` with the given ones,
+    /// growing the vertex buffer if they don't fit.
+    ///
+    /// The bounding box reported by `dimensions()` only ever grows
+    /// with this method; use [`set_data()`](#method.set_data) to
+    /// recalculate it from scratch.
+    pub fn update_range(&mut self, ctx: &mut Context, offset: usize, verts: &[Vertex]) -> GameResult {
+        self.debug_id.assert(ctx);
+        let end = offset + verts.len();
+        if let Some(capacity) = grown_capacity(self.vertex_capacity(), end) {
+            self.vertex_buffer = grow_dynamic_buffer(
+                ctx,
+                &self.vertex_buffer,
+                self.vertex_count,
+                capacity,
+                gfx::buffer::Role::Vertex,
+            )?;
+        }
+        let gpu_verts = gpu_vertices(ctx, verts);
+        ctx.gfx_context
+            .encoder
+            .update_buffer(&self.vertex_buffer, &gpu_verts[..], offset)?;
+        self.vertex_count = self.vertex_count.max(end);
+        if let Some(rect) = bbox_for_vertices(verts) {
+            self.rect = Some(match self.rect {
+                Some(r) => r.combine_with(rect),
+                None => rect,
+            });
+        }
+        Ok(())
+    }
+
+    /// Overwrites the indices starting at `offset` with the given ones,
+    /// growing the index buffer if they don't fit.
+    ///
+    /// The indices must refer to vertices that have already been written,
+    /// and should describe triangles in clockwise order, same as for
+    /// [`Mesh::from_raw()`](struct.Mesh.html#method.from_raw).  Both
+    /// `offset` and the number of indices must be multiples of 3, so
+    /// whole triangles are written.
+    pub fn update_index_range(
+        &mut self,
+        ctx: &mut Context,
+        offset: usize,
+        indices: &[u32],
+    ) -> GameResult {
+        self.debug_id.assert(ctx);
+        check_dynamic_indices(offset, indices, self.vertex_count)?;
+        let end = offset + indices.len();
+        if let Some(capacity) = grown_capacity(self.index_capacity(), end) {
+            self.index_buffer = grow_dynamic_buffer(
+                ctx,
+                &self.index_buffer,
+                self.index_count,
+                capacity,
+                gfx::buffer::Role::Index,
+            )?;
+        }
+        ctx.gfx_context
+            .encoder
+            .update_buffer(&self.index_buffer, indices, offset)?;
+        self.index_count = self.index_count.max(end);
+        Ok(())
+    }
+
+    /// Replaces all the geometry in the mesh with the given vertices and
+    /// indices, reusing the existing buffers when they are big enough.
+    pub fn set_data(&mut self, ctx: &mut Context, verts: &[Vertex], indices: &[u32]) -> GameResult {
+        // Check before clearing, so bad data leaves the mesh as it was.
+        check_dynamic_indices(0, indices, verts.len())?;
+        self.clear();
+        self.update_range(ctx, 0, verts)?;
+        self.update_index_range(ctx, 0, indices)
+    }
+
+    /// Forgets all the geometry in the mesh, without releasing the
+    /// GPU buffers.
+    pub fn clear(&mut self) {
+        self.vertex_count = 0;
+        self.index_count = 0;
+        self.rect = None;
+    }
+
+    /// Restricts drawing to the indices in `start..end`, or draws all
+    /// written indices if `None` is given.  The range is clamped to
+    /// the number of indices actually written when drawing.
+    pub fn set_draw_range(&mut self, range: Option<std::ops::Range<u32>>) {
+        self.draw_range = range.map(|r| (r.start, r.end));
+    }
+
+    /// Returns the range of indices that will be drawn, if restricted.
+    pub fn draw_range(&self) -> Option<std::ops::Range<u32>> {
+        self.draw_range.map(|(start, end)| start..end)
+    }
+
+    /// Replaces the texture used to draw the mesh.
+    pub fn set_texture(&mut self, texture: Image) {
+        self.image = texture;
+    }
+
+    fn slice(&self) -> gfx::Slice<gfx_device_gl::Resources> {
+        let (start, end) = draw_bounds(self.index_count, self.draw_range);
+        gfx::Slice {
+            start,
+            end,
+            base_vertex: 0,
+            instances: None,
+            buffer: gfx::IndexBuffer::Index32(self.index_buffer.clone()),
+        }
+    }
+}
+
+impl Drawable for DynamicMesh {
+    fn draw(&self, ctx: &mut Context, param: DrawParam) -> GameResult {
+        self.debug_id.assert(ctx);
+        let slice = self.slice();
+        if slice.start == slice.end {
+            return Ok(());
+        }
+        self.image.resolve_multisample(ctx)?;
+        let gfx = &mut ctx.gfx_context;
+        gfx.update_instance_properties(param.into())?;
+
+        gfx.data.vbuf = self.vertex_buffer.clone();
+        let texture = self.image.texture.clone();
+        let sampler = gfx
+            .samplers
+            .get_or_insert(self.image.sampler_info, gfx.factory.as_mut());
+
+        let typed_thingy = gfx.backend_spec.raw_to_typed_shader_resource(texture);
+        gfx.data.tex = (typed_thingy, sampler);
+
+        let previous_mode: Option<BlendMode> = if let Some(mode) = self.blend_mode {
+            let current_mode = gfx.blend_mode();
+            if current_mode != mode {
+                gfx.set_blend_mode(mode)?;
+                Some(current_mode)
+            } else {
+                None
+            }
+        } else {
+            None
+        };
+
+        gfx.draw(Some(&slice))?;
+        if let Some(mode) = previous_mode {
+            gfx.set_blend_mode(mode)?;
+        }
+
+        Ok(())
+    }
+    fn dimensions(&self, _ctx: &mut Context) -> Option<Rect> {
+        self.rect
+    }
+    fn set_blend_mode(&mut self, mode: Option<BlendMode>) {
+        self.blend_mode = mode;
+    }
+    fn blend_mode(&self) -> Option<BlendMode> {
+        self.blend_mode
+    }
+}
+
+/// Creates a GPU buffer that can be both written to and copied from,
+/// which is what `DynamicMesh` needs to update and grow it.
+fn create_dynamic_buffer<T>(
+    factory: &mut gfx_device_gl::Factory,
+    capacity: usize,
+    role: gfx::buffer::Role,
+) -> GameResult<gfx::handle::Buffer<gfx_device_gl::Resources, T>> {
+    use gfx::memory::Bind;
+    // Zero-sized buffers upset some drivers, so always allocate something.
+    let buffer = factory.create_buffer(
+        capacity.max(1),
+        role,
+        gfx::memory::Usage::Dynamic,
+        Bind::TRANSFER_SRC | Bind::TRANSFER_DST,
+    )?;
+    Ok(buffer)
+}
+
+/// Returns the capacity a dynamic buffer of `capacity` elements has to
+/// grow to for `needed` elements to fit, at least doubling it, or
+/// `None` if they already fit.
+fn grown_capacity(capacity: usize, needed: usize) -> Option<usize> {
+    if needed > capacity {
+        Some(needed.max(capacity * 2))
+    } else {
+        None
+    }
+}
+
+/// Checks that `indices`, written at `offset`, make whole triangles of
+/// the first `vertex_count` vertices.
+fn check_dynamic_indices(offset: usize, indices: &[u32], vertex_count: usize) -> GameResult {
+    if offset % 3 != 0 || indices.len() % 3 != 0 {
+        let msg = format!(
+            "Tried to write {} indices at offset {} of a DynamicMesh, both must be multiples of 3",
+            indices.len(),
+            offset
+        );
+        return Err(GameError::LyonError(msg));
+    }
+    if let Some(index) = indices.iter().find(|&&i| i as usize >= vertex_count) {
+        let msg = format!(
+            "Tried to write index {} to a DynamicMesh with only {} vertices",
+            index, vertex_count
+        );
+        return Err(GameError::LyonError(msg));
+    }
+    Ok(())
+}
+
+/// Returns the indices to draw out of the `index_count` written ones,
+/// limited to `draw_range` if there is one.
+fn draw_bounds(index_count: usize, draw_range: Option<(u32, u32)>) -> (u32, u32) {
+    let count = index_count as u32;
+    let (start, end) = draw_range.unwrap_or((0, count));
+    let end = end.min(count);
+    (start.min(end), end)
+}
+
+/// Replaces `buffer` with one of `capacity` elements and copies over
+/// the first `used` elements.
+fn grow_dynamic_buffer<T>(
+    ctx: &mut Context,
+    buffer: &gfx::handle::Buffer<gfx_device_gl::Resources, T>,
+    used: usize,
+    capacity: usize,
+    role: gfx::buffer::Role,
+) -> GameResult<gfx::handle::Buffer<gfx_device_gl::Resources, T>>
+where
+    T: gfx::traits::Pod,
+{
+    let gfx = &mut ctx.gfx_context;
+    let new_buffer = create_dynamic_buffer(&mut *gfx.factory, capacity, role)?;
+    if used > 0 {
+        gfx.encoder
+            .copy_buffer(buffer, &new_buffer, 0, 0, used.min(buffer.len()))?;
+    }
+    Ok(new_buffer)
+}
+
+/// Vertex colors are sRGB like `Color`, so they have to be made linear
+/// before uploading if the render targets are sRGB.
+fn gpu_vertices<'a>(ctx: &Context, verts: &'a [Vertex]) -> Cow<'a, [Vertex]> {
+    if ctx.gfx_context.is_srgb() {
+        let verts = verts
+            .iter()
+            .map(|v| Vertex {
+                color: linearize_vertex_color(v.color),
+                ..*v
+            })
+            .collect::<Vec<_>>();
+        Cow::Owned(verts)
+    } else {
+        Cow::Borrowed(verts)
+    }
+}
+
+fn linearize_vertex_color(color: [f32; 4]) -> [f32; 4] {
+    LinearColor::from(Color::from(color)).into()
+}
+
 fn bbox_for_vertices(verts: &[Vertex]) -> Option<Rect> {
     if verts.is_empty() {
         return None;
@@ -680,3 +1748,101 @@ fn bbox_for_vertices(verts: &[Vertex]) -> Option<Rect> {
         y: y_min,
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn headless_test_dash_polyline() {
+        let points = [Point2::new(0.0, 0.0), Point2::new(16.0, 0.0)];
+        let dashes = dash_polyline(&points, 2.0, 6.0);
+        assert_eq!(dashes.len(), 2);
+        assert_eq!(dashes[0], vec![Point2::new(0.0, 0.0), Point2::new(2.0, 0.0)]);
+        assert_eq!(dashes[1], vec![Point2::new(8.0, 0.0), Point2::new(10.0, 0.0)]);
+
+        // The pattern continues around corners.
+        let points = [
+            Point2::new(0.0, 0.0),
+            Point2::new(4.0, 0.0),
+            Point2::new(4.0, 4.0),
+        ];
+        let dashes = dash_polyline(&points, 6.0, 1.0);
+        assert_eq!(dashes.len(), 2);
+        assert_eq!(
+            dashes[0],
+            vec![
+                Point2::new(0.0, 0.0),
+                Point2::new(4.0, 0.0),
+                Point2::new(4.0, 2.0)
+            ]
+        );
+        assert_eq!(dashes[1], vec![Point2::new(4.0, 3.0), Point2::new(4.0, 4.0)]);
+    }
+
+    #[test]
+    fn headless_test_dynamic_buffer_growth() {
+        assert_eq!(grown_capacity(16, 0), None);
+        assert_eq!(grown_capacity(16, 16), None);
+        // Growing at least doubles, so appending one at a time is cheap...
+        assert_eq!(grown_capacity(16, 17), Some(32));
+        assert_eq!(grown_capacity(32, 33), Some(64));
+        // ...but a big write gets exactly the room it needs.
+        assert_eq!(grown_capacity(16, 100), Some(100));
+        // Empty meshes still have a 1 element buffer.
+        assert_eq!(grown_capacity(1, 3), Some(3));
+    }
+
+    #[test]
+    fn headless_test_dynamic_index_checks() {
+        assert!(check_dynamic_indices(0, &[], 0).is_ok());
+        assert!(check_dynamic_indices(3, &[0, 1, 2], 3).is_ok());
+        // Only whole triangles...
+        assert!(check_dynamic_indices(0, &[0, 1], 3).is_err());
+        assert!(check_dynamic_indices(1, &[0, 1, 2], 3).is_err());
+        // ...of vertices that were written.
+        assert!(check_dynamic_indices(0, &[0, 1, 3], 3).is_err());
+    }
+
+    #[test]
+    fn headless_test_dynamic_draw_bounds() {
+        assert_eq!(draw_bounds(0, None), (0, 0));
+        assert_eq!(draw_bounds(12, None), (0, 12));
+        assert_eq!(draw_bounds(12, Some((3, 9))), (3, 9));
+        // Ranges are clamped to what has been written.
+        assert_eq!(draw_bounds(12, Some((6, 30))), (6, 12));
+        assert_eq!(draw_bounds(12, Some((20, 30))), (12, 12));
+        assert_eq!(draw_bounds(12, Some((9, 3))), (3, 3));
+    }
+
+    #[test]
+    fn headless_test_dynamic_mesh_bbox() {
+        let vertex = |x, y| Vertex {
+            pos: [x, y],
+            uv: [0.0, 0.0],
+            color: [1.0, 1.0, 1.0, 1.0],
+        };
+        assert_eq!(bbox_for_vertices(&[]), None);
+        let first = bbox_for_vertices(&[vertex(1.0, 2.0), vertex(3.0, -2.0)]).unwrap();
+        assert_eq!(first, Rect::new(1.0, -2.0, 2.0, 4.0));
+        // update_range() only grows the box.
+        let second = bbox_for_vertices(&[vertex(-1.0, 0.0)]).unwrap();
+        assert_eq!(first.combine_with(second), Rect::new(-1.0, -2.0, 4.0, 4.0));
+    }
+
+    #[test]
+    fn headless_test_path_builder_needs_move_to() {
+        let mut path = PathBuilder::new();
+        assert!(path.to_lyon_path().is_err());
+        let _ = path.line_to([1.0, 1.0]);
+        assert!(path.to_lyon_path().is_err());
+
+        let mut path = PathBuilder::new();
+        let _ = path
+            .move_to([0.0, 0.0])
+            .line_to([1.0, 0.0])
+            .cubic_bezier_to([1.0, 1.0], [0.0, 1.0], [0.0, 0.5])
+            .close();
+        assert!(path.to_lyon_path().is_ok());
+    }
+}
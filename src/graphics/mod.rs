@@ -33,7 +33,9 @@ use glutin;
 use glutin::{NotCurrent, PossiblyCurrent};
 pub use mint;
 pub(crate) use nalgebra as na;
+use winit::dpi;
 use winit::event_loop::EventLoopWindowTarget;
+use winit::window::UserAttentionType;
 
 use glutin_ext::*;
 
@@ -45,36 +47,48 @@ use crate::GameError;
 use crate::GameResult;
 pub use crate::graphics::canvas::*;
 pub use crate::graphics::drawparam::*;
+pub use crate::graphics::gradient::*;
 pub use crate::graphics::image::*;
+pub use crate::graphics::image_data::*;
+pub use crate::graphics::ktx::*;
+pub use crate::graphics::lifecycle::*;
 pub use crate::graphics::mesh::*;
+pub use crate::graphics::monitor::*;
+pub use crate::graphics::readback::*;
+pub use crate::graphics::recorder::*;
 pub use crate::graphics::shader::*;
+pub use crate::graphics::textures::*;
+pub use crate::graphics::tonemap::*;
 pub use crate::graphics::types::*;
+pub use crate::graphics::windows::*;
 
+pub(crate) mod block_decode;
 pub(crate) mod canvas;
+pub(crate) mod codecs;
 pub(crate) mod context;
 pub(crate) mod drawparam;
+pub(crate) mod gradient;
 pub(crate) mod image;
+pub(crate) mod image_data;
+pub(crate) mod ktx;
+pub(crate) mod lifecycle;
 pub(crate) mod mesh;
+pub(crate) mod monitor;
+pub(crate) mod readback;
+pub(crate) mod recorder;
 pub(crate) mod shader;
+pub(crate) mod svg;
+pub(crate) mod textures;
+pub(crate) mod tonemap;
 pub(crate) mod types;
+pub(crate) mod windows;
 
+pub mod debug;
 pub mod glutin_ext;
 pub mod spritebatch;
 
-// This isn't really particularly nice, but it's only used
-// in a couple places and it's not very easy to change or configure.
-// Since the next major project is "rewrite the graphics engine" I think
-// we're fine just leaving it.
-//
-// It exists basically because gfx-rs is incomplete and we can't *always*
-// specify texture formats and such entirely at runtime, which we need to
-// do to make sRGB handling work properly.
-pub(crate) type BuggoSurfaceFormat = gfx::format::Rgba8;
 type ShaderResourceType = [f32; 4];
 
-type ColorFormat = gfx::format::Rgba8;
-type DepthFormat = gfx::format::DepthStencil;
-
 /// A trait providing methods for working with a particular backend, such as OpenGL,
 /// with associated gfx-rs types for that backend.  As a user you probably
 /// don't need to touch this unless you want to write a new graphics backend
@@ -243,7 +257,8 @@ impl BackendSpec for GlBackendSpec {
         glutin::CreationError,
     > {
         Ok(gl_builder
-            .with_gfx_color_depth::<ColorFormat, DepthFormat>()
+            .with_gfx_color_raw(color_format)
+            .with_gfx_depth_raw(depth_format)
             .build_windowed(window_builder, &events_loop)?
             .init_gfx_raw(color_format, depth_format))
     }
@@ -313,7 +328,8 @@ const QUAD_VERTS: [Vertex; 4] = [
 const QUAD_INDICES: [u16; 6] = [0, 1, 2, 0, 2, 3];
 
 gfx_defines! {
-    /// Structure containing fundamental vertex data.
+    /// Structure containing fundamental vertex data.  The color is
+    /// in the sRGB color space, like `Color`.
     vertex Vertex {
         pos: [f32; 2] = "a_Pos",
         uv: [f32; 2] = "a_Uv",
@@ -448,7 +464,7 @@ impl From<gfx::buffer::CreationError> for GameError {
 /// Clear the screen to the background color.
 pub fn clear(ctx: &mut Context, color: Color) {
     let gfx = &mut ctx.gfx_context;
-    let c: [f32; 4] = color.into();
+    let c: [f32; 4] = gfx.shader_color(color);
     gfx.encoder.clear_raw(&gfx.data.out, c.into());
 }
 
@@ -467,20 +483,149 @@ pub fn draw<D, T>(ctx: &mut Context, drawable: &D, params: T) -> GameResult
 /// Call this at the end of your [`EventHandler`](../event/trait.EventHandler.html)'s
 /// [`draw()`](../event/trait.EventHandler.html#tymethod.draw) method.
 ///
-/// Unsets any active canvas.
+/// Unsets any active canvas.  With several windows open, only the
+/// [active one](fn.set_active_window.html) is presented.  Nothing is
+/// shown while the graphics are [suspended](fn.suspend.html), or once
+/// the GL context is [lost](fn.is_context_lost.html).
 pub fn present(ctx: &mut Context) -> GameResult<()> {
+    canvas::leave_canvas(ctx);
+    {
+        let gfx = &mut ctx.gfx_context;
+        let screen = gfx.screen_target();
+        gfx.set_render_target(screen);
+    }
+    debug::flush(ctx)?;
+    draw_cursor_image(ctx)?;
+    canvas::draw_pixel_canvas(ctx)?;
+    draw_letterbox_bars(ctx)?;
+    readback::dump_frame(ctx)?;
+    recorder::capture(ctx)?;
     let gfx = &mut ctx.gfx_context;
-    gfx.data.out = gfx.screen_render_target.clone();
     // We might want to give the user more control over when the
     // encoder gets flushed eventually, if we want them to be able
     // to do their own gfx drawing.  HOWEVER, the whole pipeline type
     // thing is a bigger hurdle, so this is fine for now.
     gfx.encoder.flush(&mut *gfx.device);
-    gfx.window.swap_buffers()?;
+    gfx.readbacks.insert_fences();
+    gfx.texture_compression.delete_dropped();
+    if !gfx.lifecycle.suspended {
+        match gfx.window.swap_buffers() {
+            Err(glutin::ContextError::ContextLost) => gfx.lifecycle.context_lost = true,
+            result => result?,
+        }
+    }
     gfx.device.cleanup();
+    gfx.readbacks.frame += 1;
+    let screen = gfx.screen_target();
+    gfx.set_render_target(screen);
+    // Other windows are presented along with the main one in the same
+    // frame, which is only limited once.
+    if gfx.active_window() == gfx.main_window {
+        crate::timer::limit_frame_rate(ctx);
+    }
     Ok(())
 }
 
+/// Draws the image set with `mouse::set_cursor_image()` at the mouse
+/// position, which is in the scaling mode's screen coordinates whatever
+/// was given to `set_screen_coordinates()`.
+fn draw_cursor_image(ctx: &mut Context) -> GameResult {
+    let (image, dest) = match ctx.mouse_context.cursor_image() {
+        Some((image, dest)) => (image.clone(), dest),
+        None => return Ok(()),
+    };
+    let rect = ctx.gfx_context.scaling_screen_rect();
+    if rect.w <= 0.0 || rect.h <= 0.0 {
+        return Ok(());
+    }
+    with_screen_rect(ctx, rect, true, |ctx| {
+        image.draw(ctx, DrawParam::new().dest([dest.x, dest.y]))
+    })
+}
+
+/// Covers what was drawn outside the screen coordinates of a
+/// letterboxing `ScalingMode` with bars of the active window's border
+/// color.
+fn draw_letterbox_bars(ctx: &mut Context) -> GameResult {
+    let (width, height) = drawable_size(ctx);
+    let v = ctx.gfx_context.scaling_viewport;
+    let bars: Vec<Rect> = [
+        Rect::new(0.0, 0.0, width, v.y),
+        Rect::new(0.0, v.bottom(), width, height - v.bottom()),
+        Rect::new(0.0, v.y, v.x, v.h),
+        Rect::new(v.right(), v.y, width - v.right(), v.h),
+    ]
+    .iter()
+    .cloned()
+    .filter(|bar| bar.w >= 1.0 && bar.h >= 1.0)
+    .collect();
+    if bars.is_empty() {
+        return Ok(());
+    }
+    let color = ctx.gfx_context.border_color;
+    let white = ctx.gfx_context.white_image.clone();
+    with_window_pixels(ctx, |ctx| {
+        bars.iter().try_for_each(|bar| {
+            let param = DrawParam::new()
+                .dest([bar.x, bar.y])
+                .scale([bar.w, bar.h])
+                .color(color);
+            white.draw(ctx, param)
+        })
+    })
+}
+
+/// Calls `f` to draw in the window's physical pixels, without the
+/// scaling mode's letterbox and with the default shader, then puts
+/// everything back.
+pub(crate) fn with_window_pixels<F>(ctx: &mut Context, f: F) -> GameResult
+where
+    F: FnOnce(&mut Context) -> GameResult,
+{
+    let (width, height) = drawable_size(ctx);
+    with_screen_rect(ctx, Rect::new(0.0, 0.0, width, height), false, f)
+}
+
+/// Calls `f` to draw in the given screen coordinates, with or without
+/// the scaling mode's letterbox, with no transform and the default
+/// shader, then puts everything back.
+fn with_screen_rect<F>(ctx: &mut Context, rect: Rect, letterbox: bool, f: F) -> GameResult
+where
+    F: FnOnce(&mut Context) -> GameResult,
+{
+    let (old_projection, old_transform, old_screen_rect) = {
+        let gfx = &ctx.gfx_context;
+        (gfx.projection(), gfx.transform(), gfx.screen_rect)
+    };
+    let old_shader = ctx.gfx_context.current_shader.borrow_mut().take();
+    let old_letterbox = if letterbox {
+        None
+    } else {
+        Some(ctx.gfx_context.replace_letterbox(Matrix4::identity()))
+    };
+    {
+        let gfx = &mut ctx.gfx_context;
+        gfx.set_projection_rect(rect);
+        gfx.set_transform(Matrix4::identity());
+        gfx.calculate_transform_matrix();
+        gfx.update_globals()?;
+    }
+    let result = f(ctx);
+    {
+        let gfx = &mut ctx.gfx_context;
+        *gfx.current_shader.borrow_mut() = old_shader;
+        if let Some(old_letterbox) = old_letterbox {
+            let _ = gfx.replace_letterbox(old_letterbox);
+        }
+        gfx.screen_rect = old_screen_rect;
+        gfx.set_projection(old_projection);
+        gfx.set_transform(old_transform);
+        gfx.calculate_transform_matrix();
+        gfx.update_globals()?;
+    }
+    result
+}
+
 /// Take a screenshot by outputting the current render surface
 /// (screen or selected canvas) to an `Image`.
 pub fn screenshot(ctx: &mut Context) -> GameResult<Image> {
@@ -489,15 +634,14 @@ pub fn screenshot(ctx: &mut Context) -> GameResult<Image> {
 
     let gfx = &mut ctx.gfx_context;
     let (w, h, _depth, aa) = gfx.data.out.get_dimensions();
-    if aa != gfx_core::texture::AaMode::Single {
-        // Details see https://github.com/ggez/ggez/issues/751
-        return Err(GameError::RenderError("Can't take screenshots of anti aliased textures.\n(since neither copying or resolving them is supported right now)".to_string()));
-    }
+    let multisampled = aa != gfx_core::texture::AaMode::Single;
 
     let surface_format = gfx.color_format();
     let gfx::format::Format(surface_type, channel_type) = surface_format;
 
-    let texture_kind = gfx::texture::Kind::D2(w, h, aa);
+    // The screenshot itself is always single-sampled; anti-aliased
+    // targets get resolved into it below.
+    let texture_kind = gfx::texture::Kind::D2(w, h, gfx_core::texture::AaMode::Single);
     let texture_info = gfx::texture::Info {
         kind: texture_kind,
         levels: 1,
@@ -519,19 +663,40 @@ pub fn screenshot(ctx: &mut Context) -> GameResult<Image> {
         mipmap: 0,
     };
 
-    let mut local_encoder: gfx::Encoder<gfx_device_gl::Resources, gfx_device_gl::CommandBuffer> =
-        gfx.factory.create_command_buffer().into();
-
-    local_encoder.copy_texture_to_texture_raw(
-        gfx.data.out.get_texture(),
-        None,
-        image_info,
-        &target_texture,
-        None,
-        image_info,
-    )?;
+    if multisampled {
+        // Copying can't resolve samples, so blit instead, after
+        // submitting everything drawn to the target so far.
+        gfx.encoder.flush(&mut *gfx.device);
+        let resolver = gfx.multisample_resolver.as_ref().ok_or_else(|| {
+            GameError::RenderError(
+                "Taking screenshots of anti-aliased targets needs OpenGL 3.0 or OpenGL ES 3.0"
+                    .to_string(),
+            )
+        })?;
+        resolver
+            .resolve(
+                gfx.data.out.get_texture().resource(),
+                true,
+                target_texture.resource(),
+                w,
+                h,
+            )
+            .map_err(GameError::RenderError)?;
+    } else {
+        let mut local_encoder: gfx::Encoder<gfx_device_gl::Resources, gfx_device_gl::CommandBuffer> =
+            gfx.factory.create_command_buffer().into();
+
+        local_encoder.copy_texture_to_texture_raw(
+            gfx.data.out.get_texture(),
+            None,
+            image_info,
+            &target_texture,
+            None,
+            image_info,
+        )?;
 
-    local_encoder.flush(&mut *gfx.device);
+        local_encoder.flush(&mut *gfx.device);
+    }
 
     let resource_desc = gfx::texture::ResourceDesc {
         channel: channel_type,
@@ -550,6 +715,8 @@ pub fn screenshot(ctx: &mut Context) -> GameResult<Image> {
         blend_mode: None,
         width: w,
         height: h,
+        multisample_source: None,
+        compressed: None,
         debug_id,
     };
 
@@ -572,11 +739,12 @@ pub fn default_filter(ctx: &Context) -> FilterMode {
 pub fn renderer_info(ctx: &Context) -> GameResult<String> {
     let backend_info = ctx.gfx_context.backend_spec.info(&*ctx.gfx_context.device);
     Ok(format!(
-        "Requested {:?} {}.{} Core profile, actually got {}.",
+        "Requested {:?} {}.{} Core profile, actually got {}. {}.",
         ctx.gfx_context.backend_spec.api,
         ctx.gfx_context.backend_spec.major,
         ctx.gfx_context.backend_spec.minor,
-        backend_info
+        backend_info,
+        ctx.gfx_context.texture_compression.describe()
     ))
 }
 
@@ -606,14 +774,21 @@ pub fn set_default_filter(ctx: &mut Context, mode: FilterMode) {
 ///
 /// The default coordinate system has (0,0) at the top-left corner
 /// with X increasing to the right and Y increasing down, with the
-/// viewport scaled such that one coordinate unit is one pixel on the
-/// screen.  This function lets you change this coordinate system to
-/// be whatever you prefer.
+/// viewport scaled such that one coordinate unit is one logical pixel
+/// of the window, or one pixel of the virtual resolution of
+/// [`WindowMode::scaling`](../conf/struct.WindowMode.html#structfield.scaling).
+/// This function lets you change this coordinate system to be whatever
+/// you prefer.
 ///
 /// The `Rect`'s x and y will define the top-left corner of the screen,
 /// and that plus its w and h will define the bottom-right corner.
+///
+/// The coordinates stay the same when the window is resized or its DPI
+/// scale factor changes, until [`set_mode()`](fn.set_mode.html) fits
+/// them to the window again.
 pub fn set_screen_coordinates(context: &mut Context, rect: Rect) -> GameResult {
     let gfx = &mut context.gfx_context;
+    gfx.custom_screen_rect = Some(rect);
     gfx.set_projection_rect(rect);
     gfx.calculate_transform_matrix();
     gfx.update_globals()
@@ -798,18 +973,19 @@ pub fn set_blend_mode(ctx: &mut Context, mode: BlendMode) -> GameResult {
 /// Sets the window mode, such as the size and other properties.
 ///
 /// Setting the window mode may have side effects, such as clearing
-/// the screen or setting the screen coordinates viewport to some
-/// undefined value (for example, the window was resized).  It is
-/// recommended to call
-/// [`set_screen_coordinates()`](fn.set_screen_coordinates.html) after
-/// changing the window size to make sure everything is what you want
-/// it to be.
+/// the screen.  It resets the screen coordinates to fit its
+/// [`scaling`](../conf/struct.WindowMode.html#structfield.scaling).
+///
+/// Like the other window functions, it acts on the
+/// [active window](fn.set_active_window.html).
 pub fn set_mode(context: &mut Context, mode: WindowMode) -> GameResult {
     let gfx = &mut context.gfx_context;
     gfx.set_window_mode(mode)?;
     // Save updated mode.
-    context.conf.window_mode = mode;
-    Ok(())
+    if gfx.active_window() == gfx.main_window {
+        context.conf.window_mode = mode;
+    }
+    canvas::update_pixel_canvas(context)
 }
 
 /// Sets the window to fullscreen or back.
@@ -853,7 +1029,74 @@ pub fn set_window_title(context: &Context, title: &str) {
     context.gfx_context.window.window().set_title(title);
 }
 
-/// Returns a reference to the Glutin window.
+/// Returns the position of the window's top left corner, including
+/// borders and titlebar, on the desktop in physical pixels.  Returns an
+/// error on platforms where windows can't tell, like Wayland.
+pub fn window_position(context: &Context) -> GameResult<(i32, i32)> {
+    let position = context
+        .gfx_context
+        .window
+        .window()
+        .outer_position()
+        .map_err(|e| GameError::WindowError(e.to_string()))?;
+    Ok((position.x, position.y))
+}
+
+/// Moves the window's top left corner, including borders and titlebar,
+/// to the given position on the desktop in physical pixels.
+pub fn set_window_position(context: &Context, x: i32, y: i32) {
+    context
+        .gfx_context
+        .window
+        .window()
+        .set_outer_position(dpi::PhysicalPosition::new(x, y));
+}
+
+/// Minimizes the window.
+pub fn minimize(context: &Context) {
+    context.gfx_context.window.window().set_minimized(true);
+}
+
+/// Restores the window after it was minimized.
+pub fn restore(context: &Context) {
+    context.gfx_context.window.window().set_minimized(false);
+}
+
+/// Returns whether the main window is minimized, as far as the platform
+/// says.  Only Windows tells, by resizing minimized windows to nothing;
+/// elsewhere this is always `false`.  A window hidden behind others,
+/// or on another virtual desktop, doesn't count as minimized anywhere.
+pub fn is_minimized(context: &Context) -> bool {
+    context.gfx_context.minimized
+}
+
+/// Sets whether the window stays on top of other windows.
+pub fn set_always_on_top(context: &Context, always_on_top: bool) {
+    context
+        .gfx_context
+        .window
+        .window()
+        .set_always_on_top(always_on_top);
+}
+
+/// Asks for the user's attention if the window isn't focused, e.g. by
+/// flashing it in the taskbar or bouncing the dock icon, until it is
+/// focused.  `critical` attention is more insistent on some platforms.
+pub fn request_attention(context: &Context, critical: bool) {
+    let attention = if critical {
+        UserAttentionType::Critical
+    } else {
+        UserAttentionType::Informational
+    };
+    context
+        .gfx_context
+        .window
+        .window()
+        .request_user_attention(Some(attention));
+}
+
+/// Returns a reference to the Glutin window that is
+/// [active](fn.set_active_window.html).
 /// Ideally you should not need to use this because ggez
 /// would provide all the functions you need without having
 /// to dip into Glutin itself.  But life isn't always ideal.
@@ -879,6 +1122,48 @@ pub fn drawable_size(context: &Context) -> (f32, f32) {
     (size.width as f32, size.height as f32)
 }
 
+/// Returns the window's DPI scale factor: how many physical pixels
+/// make up one logical pixel.
+pub fn scale_factor(context: &Context) -> f64 {
+    context.gfx_context.window.window().scale_factor()
+}
+
+/// Returns the active window's vsync mode.
+pub fn vsync(context: &Context) -> Vsync {
+    match context.gfx_context.swap_interval() {
+        0 => Vsync::Off,
+        i if i > 0 => Vsync::On,
+        _ => Vsync::Adaptive,
+    }
+}
+
+/// Changes the active window's vsync mode, which
+/// [`WindowSetup::vsync`](../conf/struct.WindowSetup.html#structfield.vsync)
+/// sets at startup.  Where the driver doesn't support
+/// `Vsync::Adaptive`, this falls back to `Vsync::On`, which
+/// [`vsync()`](fn.vsync.html) then returns.
+///
+/// Returns an error on platforms where vsync can't be changed at
+/// runtime, which includes macOS.
+pub fn set_vsync(context: &mut Context, vsync: Vsync) -> GameResult {
+    match vsync {
+        Vsync::Off => set_swap_interval(context, 0),
+        Vsync::On => set_swap_interval(context, 1),
+        Vsync::Adaptive => set_swap_interval(context, -1).or_else(|e| {
+            warn!("Adaptive vsync isn't available, using normal vsync: {}", e);
+            set_swap_interval(context, 1)
+        }),
+    }
+}
+
+/// Sets the active window's swap interval directly: how many vertical
+/// blanks presenting waits for, e.g. 2 for 30 FPS on a 60 Hz monitor,
+/// 0 for none, or -1 for adaptive vsync.  Returns an error if the
+/// driver refuses it.
+pub fn set_swap_interval(context: &mut Context, interval: i32) -> GameResult {
+    context.gfx_context.set_swap_interval(interval)
+}
+
 /// Returns raw `gfx-rs` state objects, if you want to use `gfx-rs` to write
 /// your own graphics pipeline then this gets you the interfaces you need
 /// to do so.
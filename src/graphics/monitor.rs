@@ -0,0 +1,151 @@
+//! Listing monitors and their video modes, for exclusive fullscreen.
+
+use winit::monitor::MonitorHandle;
+use winit::window::Window;
+
+use crate::conf::VideoMode;
+use crate::context::Context;
+use crate::error::{GameError, GameResult};
+
+/// A monitor connected to the computer, as listed by
+/// [`monitors()`](fn.monitors.html).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Monitor {
+    /// The monitor's position in the list returned by `monitors()`,
+    /// which is what [`WindowMode::monitor`](../conf/struct.WindowMode.html#structfield.monitor)
+    /// takes.
+    pub index: usize,
+    /// The monitor's name, if the platform gives it one.
+    pub name: Option<String>,
+    /// Width of the monitor's current resolution, in physical pixels.
+    pub width: u32,
+    /// Height of the monitor's current resolution, in physical pixels.
+    pub height: u32,
+    /// X position of the monitor's top left corner on the desktop.
+    pub x: i32,
+    /// Y position of the monitor's top left corner on the desktop.
+    pub y: i32,
+    /// The monitor's DPI scale factor.
+    pub scale_factor: f64,
+    /// Whether this is the system's primary monitor.
+    pub primary: bool,
+}
+
+/// Returns the monitors connected to the computer.  The order stays
+/// the same as long as the monitors do.
+pub fn monitors(ctx: &Context) -> Vec<Monitor> {
+    let window = ctx.gfx_context.window.window();
+    let primary = window.primary_monitor();
+    window
+        .available_monitors()
+        .enumerate()
+        .map(|(index, handle)| {
+            let size = handle.size();
+            let position = handle.position();
+            Monitor {
+                index,
+                name: handle.name(),
+                width: size.width,
+                height: size.height,
+                x: position.x,
+                y: position.y,
+                scale_factor: handle.scale_factor(),
+                primary: primary.as_ref() == Some(&handle),
+            }
+        })
+        .collect()
+}
+
+/// Returns the video modes the given monitor supports for
+/// `FullscreenType::True`, from the largest and fastest to the smallest.
+pub fn video_modes(ctx: &Context, monitor: &Monitor) -> GameResult<Vec<VideoMode>> {
+    let handle = monitor_handle(ctx.gfx_context.window.window(), monitor.index)?;
+    let mut modes: Vec<VideoMode> = handle.video_modes().map(|m| describe(&m)).collect();
+    modes.sort_by(|a, b| b.cmp(a));
+    modes.dedup();
+    Ok(modes)
+}
+
+/// Finds the monitor at `index` in the list `monitors()` returns.
+pub(crate) fn monitor_handle(window: &Window, index: usize) -> GameResult<MonitorHandle> {
+    window.available_monitors().nth(index).ok_or_else(|| {
+        GameError::WindowError(format!(
+            "There is no monitor {}; {} are connected",
+            index,
+            window.available_monitors().count()
+        ))
+    })
+}
+
+/// Finds the winit video mode of `monitor` to go into exclusive
+/// fullscreen with: exactly `wanted` if given, otherwise the fastest
+/// mode of the given size.
+pub(crate) fn exclusive_mode(
+    monitor: &MonitorHandle,
+    wanted: Option<VideoMode>,
+    width: u32,
+    height: u32,
+) -> Option<winit::monitor::VideoMode> {
+    let modes: Vec<winit::monitor::VideoMode> = monitor.video_modes().collect();
+    let described: Vec<VideoMode> = modes.iter().map(describe).collect();
+    choose_video_mode(&described, wanted, width, height).map(|i| modes[i].clone())
+}
+
+fn describe(mode: &winit::monitor::VideoMode) -> VideoMode {
+    let size = mode.size();
+    VideoMode {
+        width: size.width,
+        height: size.height,
+        refresh_rate: mode.refresh_rate(),
+        bit_depth: mode.bit_depth(),
+    }
+}
+
+fn choose_video_mode(
+    modes: &[VideoMode],
+    wanted: Option<VideoMode>,
+    width: u32,
+    height: u32,
+) -> Option<usize> {
+    match wanted {
+        Some(wanted) => modes.iter().position(|&m| m == wanted),
+        None => modes
+            .iter()
+            .enumerate()
+            .filter(|&(_, m)| m.width == width && m.height == height)
+            .max_by_key(|&(_, m)| (m.refresh_rate, m.bit_depth))
+            .map(|(i, _)| i),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mode(width: u32, height: u32, refresh_rate: u16) -> VideoMode {
+        VideoMode {
+            width,
+            height,
+            refresh_rate,
+            bit_depth: 32,
+        }
+    }
+
+    #[test]
+    fn headless_test_choose_video_mode() {
+        let modes = [
+            mode(1920, 1080, 60),
+            mode(1280, 720, 60),
+            mode(1920, 1080, 144),
+            mode(1920, 1080, 120),
+        ];
+        assert_eq!(choose_video_mode(&modes, None, 1920, 1080), Some(2));
+        assert_eq!(choose_video_mode(&modes, None, 800, 600), None);
+        let wanted = Some(mode(1920, 1080, 120));
+        assert_eq!(choose_video_mode(&modes, wanted, 1280, 720), Some(3));
+        assert_eq!(
+            choose_video_mode(&modes, Some(mode(1280, 720, 75)), 0, 0),
+            None
+        );
+    }
+}
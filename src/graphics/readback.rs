@@ -0,0 +1,527 @@
+//! Asynchronous reading of pixels back from the GPU.
+//!
+//! [`screenshot()`](fn.screenshot.html) and
+//! [`Image::to_rgba8()`](struct.Image.html#method.to_rgba8) wait for the
+//! GPU to finish everything it has been asked to do before they return,
+//! which stalls the whole pipeline.  The functions here instead queue a
+//! copy into one of a small ring of staging buffers and return a
+//! [`ReadbackHandle`](struct.ReadbackHandle.html) right away.  A frame or
+//! two later the GPU has caught up and the pixels can be picked up
+//! without waiting.
+//!
+//! Whether it has is checked with a GL fence, on OpenGL 3.2 and OpenGL
+//! ES 3.0 and later.  Older versions have no way to tell, so there a
+//! readback is taken to be done two presented frames after it was
+//! queued, and picking it up may still block if the GPU is further
+//! behind than that.
+
+use std::collections::VecDeque;
+use std::fmt;
+
+use gfx::memory::Typed;
+use gfx::traits::FactoryExt;
+
+use crate::context::Context;
+use crate::error::{GameError, GameResult};
+use crate::graphics::glutin_ext::{Fence, FenceSync};
+use crate::graphics::*;
+
+type R = gfx_device_gl::Resources;
+
+/// Without fences, how many presented frames a readback is given before
+/// polling it is expected to no longer block.
+const READBACK_LATENCY: usize = 2;
+
+/// The number of staging buffers in a new context's readback ring.
+const DEFAULT_RING_SIZE: usize = 4;
+
+/// A frame captured by the hook set with
+/// [`set_frame_dump_hook()`](fn.set_frame_dump_hook.html).
+#[derive(Debug, Clone, PartialEq)]
+pub struct CapturedFrame {
+    /// The number of the frame, counting calls to `present()` from 0.
+    pub frame: usize,
+    /// Width of the frame in pixels.
+    pub width: u16,
+    /// Height of the frame in pixels.
+    pub height: u16,
+    /// The frame's pixels as RGBA bytes, row by row from the top.
+    pub rgba: Vec<u8>,
+}
+
+/// A pending asynchronous readback, returned by
+/// [`screenshot_async()`](fn.screenshot_async.html) and
+/// [`Image::to_rgba8_async()`](struct.Image.html#method.to_rgba8_async).
+///
+/// The readback occupies one of the context's staging buffers until its
+/// pixels are picked up.  If so many readbacks are queued that the ring
+/// of buffers wraps around, the oldest one is overwritten and its handle
+/// returns an error.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ReadbackHandle {
+    slot: usize,
+    generation: u64,
+    width: u16,
+    height: u16,
+}
+
+/// One staging buffer in the ring.
+#[derive(Default)]
+struct Slot {
+    /// Download buffer the pixels get copied into.
+    buffer: Option<gfx::handle::Buffer<R, [u8; 4]>>,
+    /// Texture for copying render targets into first, along with its size.
+    staging: Option<(gfx::handle::RawTexture<R>, u16, u16)>,
+    /// The frame the readback was queued in.
+    queued_frame: usize,
+    /// Signaled once the copy is done, set when it's submitted.
+    fence: Option<Fence>,
+    /// Matches the handle of the readback currently using this slot.
+    generation: u64,
+    in_use: bool,
+}
+
+/// The ring of staging buffers and the frame dump hook, kept in the
+/// graphics context.
+pub(crate) struct ReadbackRing {
+    slots: Vec<Slot>,
+    fences: Option<FenceSync>,
+    next: usize,
+    generation: u64,
+    /// Counts calls to `present()`.
+    pub(crate) frame: usize,
+    hook: Option<Box<dyn FnMut(CapturedFrame)>>,
+    /// Readbacks queued for the hook, along with their frame numbers.
+    hook_pending: VecDeque<(usize, ReadbackHandle)>,
+}
+
+impl fmt::Debug for ReadbackRing {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "<ReadbackRing: {} slots, frame {}>",
+            self.slots.len(),
+            self.frame
+        )
+    }
+}
+
+impl ReadbackRing {
+    pub(crate) fn new(fences: Option<FenceSync>) -> Self {
+        ReadbackRing {
+            slots: (0..DEFAULT_RING_SIZE).map(|_| Slot::default()).collect(),
+            fences,
+            next: 0,
+            generation: 0,
+            frame: 0,
+            hook: None,
+            hook_pending: VecDeque::new(),
+        }
+    }
+
+    /// Picks the next slot to use, preferring free ones and otherwise
+    /// taking over the oldest readback.
+    fn acquire(&mut self) -> usize {
+        let len = self.slots.len();
+        let index = (0..len)
+            .map(|i| (self.next + i) % len)
+            .find(|&i| !self.slots[i].in_use)
+            .unwrap_or(self.next);
+        self.next = (index + 1) % len;
+        self.generation += 1;
+        self.release_fence(index);
+        let slot = &mut self.slots[index];
+        slot.generation = self.generation;
+        slot.queued_frame = self.frame;
+        slot.in_use = true;
+        index
+    }
+
+    /// Deletes the fence of a slot that's being reused or freed.
+    fn release_fence(&mut self, index: usize) {
+        if let Some(fence) = self.slots.get_mut(index).and_then(|slot| slot.fence.take()) {
+            if let Some(ref fences) = self.fences {
+                fences.delete(fence);
+            }
+        }
+    }
+
+    /// Puts fences after the copies queued since the last call, which
+    /// have just been submitted.  Called by `present()`.
+    pub(crate) fn insert_fences(&mut self) {
+        if let Some(ref fences) = self.fences {
+            for slot in self.slots.iter_mut() {
+                if slot.in_use && slot.fence.is_none() {
+                    slot.fence = Some(fences.insert());
+                }
+            }
+        }
+    }
+
+    /// Changes the number of slots, freeing the ones that are dropped.
+    fn resize(&mut self, count: usize) {
+        for index in count..self.slots.len() {
+            self.release_fence(index);
+        }
+        self.slots.resize_with(count, Slot::default);
+        self.next %= count;
+    }
+
+    fn slot(&self, handle: &ReadbackHandle) -> GameResult<&Slot> {
+        match self.slots.get(handle.slot) {
+            Some(slot) if slot.in_use && slot.generation == handle.generation => Ok(slot),
+            _ => Err(GameError::RenderError(
+                "Readback is no longer available; it was already read or overwritten by newer readbacks"
+                    .to_string(),
+            )),
+        }
+    }
+}
+
+impl ReadbackHandle {
+    /// Width of the pixel data in pixels.
+    pub fn width(&self) -> u16 {
+        self.width
+    }
+
+    /// Height of the pixel data in pixels.
+    pub fn height(&self) -> u16 {
+        self.height
+    }
+
+    /// Returns `true` once the GPU has finished the copy, so that
+    /// [`poll()`](#method.poll) will return the pixels.  Without GL
+    /// fences this is a guess; see the [module docs](index.html).
+    pub fn is_ready(&self, ctx: &Context) -> GameResult<bool> {
+        let ring = &ctx.gfx_context.readbacks;
+        let slot = ring.slot(self)?;
+        Ok(match (&ring.fences, &slot.fence) {
+            (Some(fences), Some(fence)) => fences.is_signaled(fence),
+            // Not even submitted yet.
+            (Some(_), None) => false,
+            (None, _) => ring.frame >= slot.queued_frame + READBACK_LATENCY,
+        })
+    }
+
+    /// Returns the RGBA bytes, row by row from the top, if the readback
+    /// is complete, or `None` if it is too early to get them without
+    /// waiting.  Once the bytes have been returned the staging buffer
+    /// is freed, and the handle can't be used any more.
+    pub fn poll(&self, ctx: &mut Context) -> GameResult<Option<Vec<u8>>> {
+        if self.is_ready(ctx)? {
+            self.read(ctx).map(Some)
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Returns the RGBA bytes right away, waiting for the GPU if it is
+    /// not done yet.  Like [`poll()`](#method.poll) this frees the
+    /// staging buffer.
+    pub fn wait(&self, ctx: &mut Context) -> GameResult<Vec<u8>> {
+        let gfx = &mut ctx.gfx_context;
+        if gfx.readbacks.slot(self)?.queued_frame == gfx.readbacks.frame {
+            // The copy is still sitting in the encoder.
+            gfx.encoder.flush(&mut *gfx.device);
+        }
+        self.read(ctx)
+    }
+
+    fn read(&self, ctx: &mut Context) -> GameResult<Vec<u8>> {
+        let gfx = &mut ctx.gfx_context;
+        let buffer = gfx
+            .readbacks
+            .slot(self)?
+            .buffer
+            .clone()
+            .expect("Readback slot in use without a buffer; should never happen");
+        let data = {
+            let reader = gfx.factory.read_mapping(&buffer)?;
+            flip_rows(&reader, self.width, self.height)
+        };
+        gfx.readbacks.slots[self.slot].in_use = false;
+        gfx.readbacks.release_fence(self.slot);
+        Ok(data)
+    }
+}
+
+/// Turns rows of pixels read from OpenGL, which start at the bottom,
+/// into RGBA bytes starting at the top.
+pub(crate) fn flip_rows(pixels: &[[u8; 4]], width: u16, height: u16) -> Vec<u8> {
+    let (width, height) = (usize::from(width), usize::from(height));
+    let mut data = Vec::with_capacity(width * height * 4);
+    for y in (0..height).rev() {
+        data.extend(pixels[y * width..(y + 1) * width].iter().flatten());
+    }
+    data
+}
+
+/// The format to copy a texture's pixels in or out in.  Only 8-bit
+/// formats can be copied as RGBA bytes anyway, and for those the channel
+/// type makes no difference, so all that matters is the texture's surface.
+pub(crate) fn download_format(texture: &gfx::handle::RawTexture<R>) -> gfx::format::Format {
+    gfx::format::Format(texture.get_info().format, gfx::format::ChannelType::Unorm)
+}
+
+/// Gets a staging slot ready for a `width` by `height` readback, with
+/// a staging texture in the given format if there is one.
+fn prepare_slot(
+    ctx: &mut Context,
+    width: u16,
+    height: u16,
+    staging: Option<gfx::format::Format>,
+) -> GameResult<ReadbackHandle> {
+    use gfx::memory::Bind;
+
+    let gfx = &mut ctx.gfx_context;
+    let index = gfx.readbacks.acquire();
+    let pixels = usize::from(width) * usize::from(height);
+    let slot = &mut gfx.readbacks.slots[index];
+    if slot.buffer.as_ref().map_or(true, |b| b.len() < pixels) {
+        slot.buffer = Some(gfx.factory.create_download_buffer::<[u8; 4]>(pixels)?);
+    }
+    if let Some(gfx::format::Format(surface_type, channel_type)) = staging {
+        let staging_fits = match slot.staging {
+            Some((ref texture, w, h)) => {
+                w == width && h == height && texture.get_info().format == surface_type
+            }
+            None => false,
+        };
+        if !staging_fits {
+            let info = gfx::texture::Info {
+                kind: gfx::texture::Kind::D2(width, height, gfx::texture::AaMode::Single),
+                levels: 1,
+                format: surface_type,
+                bind: Bind::TRANSFER_SRC | Bind::TRANSFER_DST | Bind::SHADER_RESOURCE,
+                usage: gfx::memory::Usage::Data,
+            };
+            let texture = gfx
+                .factory
+                .create_texture_raw(info, Some(channel_type), None)?;
+            slot.staging = Some((texture, width, height));
+        }
+    }
+    Ok(ReadbackHandle {
+        slot: index,
+        generation: slot.generation,
+        width,
+        height,
+    })
+}
+
+/// Queues copying `texture` into the handle's download buffer.
+fn queue_download(
+    ctx: &mut Context,
+    handle: &ReadbackHandle,
+    texture: &gfx::handle::RawTexture<R>,
+) -> GameResult {
+    let gfx = &mut ctx.gfx_context;
+    let format = download_format(texture);
+    let buffer = gfx.readbacks.slots[handle.slot]
+        .buffer
+        .clone()
+        .expect("Readback slot prepared without a buffer; should never happen");
+    gfx.encoder.copy_texture_to_buffer_raw(
+        texture,
+        None,
+        gfx::texture::RawImageInfo {
+            xoffset: 0,
+            yoffset: 0,
+            zoffset: 0,
+            width: handle.width,
+            height: handle.height,
+            depth: 0,
+            format,
+            mipmap: 0,
+        },
+        buffer.raw(),
+        0,
+    )?;
+    Ok(())
+}
+
+/// Queues an asynchronous screenshot of the current render target
+/// (screen or selected canvas), to be picked up with
+/// [`ReadbackHandle::poll()`](struct.ReadbackHandle.html#method.poll)
+/// a couple of frames later.  HDR canvases can't be read back this
+/// way; use [`Image::to_rgba8()`](struct.Image.html#method.to_rgba8)
+/// on their image.
+pub fn screenshot_async(ctx: &mut Context) -> GameResult<ReadbackHandle> {
+    let (w, h, _depth, aa) = ctx.gfx_context.data.out.get_dimensions();
+    let source = ctx.gfx_context.data.out.get_texture().clone();
+    // The download buffers hold 4 bytes per pixel.
+    if crate::graphics::image::is_hdr_surface(source.get_info().format) {
+        return Err(GameError::RenderError(
+            "HDR canvases can't be read back asynchronously; use Image::to_rgba8()".to_string(),
+        ));
+    }
+    let format = download_format(&source);
+    let handle = prepare_slot(ctx, w, h, Some(format))?;
+
+    let gfx = &mut ctx.gfx_context;
+    let staging = gfx.readbacks.slots[handle.slot]
+        .staging
+        .clone()
+        .expect("Readback slot prepared without a staging texture; should never happen")
+        .0;
+    if aa != gfx::texture::AaMode::Single {
+        gfx.encoder.flush(&mut *gfx.device);
+        let resolver = gfx.multisample_resolver.as_ref().ok_or_else(|| {
+            GameError::RenderError(
+                "Reading back anti-aliased targets needs OpenGL 3.0 or OpenGL ES 3.0".to_string(),
+            )
+        })?;
+        resolver
+            .resolve(source.resource(), true, staging.resource(), w, h)
+            .map_err(GameError::RenderError)?;
+    } else {
+        let image_info = gfx::texture::ImageInfoCommon {
+            xoffset: 0,
+            yoffset: 0,
+            zoffset: 0,
+            width: w,
+            height: h,
+            depth: 0,
+            format,
+            mipmap: 0,
+        };
+        gfx.encoder
+            .copy_texture_to_texture_raw(&source, None, image_info, &staging, None, image_info)?;
+    }
+    queue_download(ctx, &handle, &staging)?;
+    Ok(handle)
+}
+
+impl Image {
+    /// Queues an asynchronous copy of the image's pixels, to be picked
+    /// up with [`ReadbackHandle::poll()`](struct.ReadbackHandle.html#method.poll)
+    /// a couple of frames later.  This is the non-blocking version of
+    /// [`to_rgba8()`](#method.to_rgba8).  HDR images can only be read
+    /// back with `to_rgba8()`.
+    pub fn to_rgba8_async(&self, ctx: &mut Context) -> GameResult<ReadbackHandle> {
+        self.debug_id.assert(ctx);
+        self.check_readable()?;
+        if self.is_hdr() {
+            return Err(GameError::RenderError(
+                "HDR images can't be read back asynchronously; use to_rgba8()".to_string(),
+            ));
+        }
+        self.resolve_multisample(ctx)?;
+        let handle = prepare_slot(ctx, self.width, self.height, None)?;
+        queue_download(ctx, &handle, &self.texture_handle)?;
+        Ok(handle)
+    }
+}
+
+/// Sets a hook that gets every frame shown by
+/// [`present()`](fn.present.html), for dumping gameplay to disk or
+/// feeding a video encoder.  Pass `None` to remove it.
+///
+/// Frames are read back asynchronously, so the hook receives each one
+/// a couple of frames after it was presented, from inside a later call
+/// to `present()`.  Frames still in flight when the hook is removed are
+/// dropped.
+pub fn set_frame_dump_hook(ctx: &mut Context, hook: Option<Box<dyn FnMut(CapturedFrame)>>) {
+    let ring = &mut ctx.gfx_context.readbacks;
+    if hook.is_none() {
+        for (_, handle) in ring.hook_pending.drain(..) {
+            if let Some(slot) = ring.slots.get_mut(handle.slot) {
+                if slot.generation == handle.generation {
+                    slot.in_use = false;
+                }
+            }
+        }
+    }
+    ring.hook = hook;
+}
+
+/// Sets how many staging buffers the readback ring has.  More buffers
+/// allow more readbacks to be in flight at once.  The default is 4.
+pub fn set_readback_buffers(ctx: &mut Context, count: usize) {
+    ctx.gfx_context.readbacks.resize(count.max(1));
+}
+
+/// Grows the readback ring to at least `count` staging buffers.
+pub(crate) fn reserve_readback_buffers(ctx: &mut Context, count: usize) {
+    let ring = &mut ctx.gfx_context.readbacks;
+    if ring.slots.len() < count {
+        ring.resize(count);
+    }
+}
+
+/// Called by `present()` with the screen as render target, before the
+/// frame is submitted: hands finished frames to the frame dump hook and
+/// queues a readback of the frame being presented.
+pub(crate) fn dump_frame(ctx: &mut Context) -> GameResult {
+    if ctx.gfx_context.readbacks.hook.is_none() {
+        return Ok(());
+    }
+    loop {
+        let (frame, handle) = match ctx.gfx_context.readbacks.hook_pending.front() {
+            Some((frame, handle)) => (*frame, handle.clone()),
+            None => break,
+        };
+        match handle.poll(ctx) {
+            Ok(Some(rgba)) => {
+                let _ = ctx.gfx_context.readbacks.hook_pending.pop_front();
+                let captured = CapturedFrame {
+                    frame,
+                    width: handle.width,
+                    height: handle.height,
+                    rgba,
+                };
+                if let Some(hook) = ctx.gfx_context.readbacks.hook.as_mut() {
+                    hook(captured);
+                }
+            }
+            Ok(None) => break,
+            // Overwritten by other readbacks; nothing to be done about it.
+            Err(_) => {
+                let _ = ctx.gfx_context.readbacks.hook_pending.pop_front();
+            }
+        }
+    }
+    let frame = ctx.gfx_context.readbacks.frame;
+    let handle = screenshot_async(ctx)?;
+    ctx.gfx_context
+        .readbacks
+        .hook_pending
+        .push_back((frame, handle));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn headless_test_flip_rows() {
+        let pixels = [[1, 1, 1, 1], [2, 2, 2, 2], [3, 3, 3, 3], [4, 4, 4, 4]];
+        assert_eq!(
+            flip_rows(&pixels, 2, 2),
+            vec![3, 3, 3, 3, 4, 4, 4, 4, 1, 1, 1, 1, 2, 2, 2, 2]
+        );
+    }
+
+    #[test]
+    fn headless_test_ring_reuses_oldest() {
+        let mut ring = ReadbackRing::new(None);
+        let first: Vec<usize> = (0..DEFAULT_RING_SIZE).map(|_| ring.acquire()).collect();
+        assert_eq!(first, (0..DEFAULT_RING_SIZE).collect::<Vec<_>>());
+
+        // Every slot is busy, so the oldest gets taken over...
+        let stale = ReadbackHandle {
+            slot: 0,
+            generation: 1,
+            width: 1,
+            height: 1,
+        };
+        assert!(ring.slot(&stale).is_ok());
+        assert_eq!(ring.acquire(), 0);
+        assert!(ring.slot(&stale).is_err());
+
+        // ...but free slots are preferred.
+        ring.slots[2].in_use = false;
+        assert_eq!(ring.acquire(), 2);
+    }
+}
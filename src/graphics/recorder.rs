@@ -0,0 +1,566 @@
+//! Recording gameplay to image sequences and animations.
+//!
+//! A recording captures every frame shown by [`present()`](fn.present.html)
+//! using the asynchronous readback, so it doesn't stall the game.  While
+//! it runs the timer is switched to a fixed frame length with
+//! [`timer::set_fixed_delta()`](../timer/fn.set_fixed_delta.html), so each
+//! presented frame is exactly one frame of the recording and the result
+//! plays back smoothly no matter how fast the game actually ran.
+//!
+//! Frames are encoded on a background thread, and the encoded files are
+//! written to the user directory from inside `present()`.
+
+use std::collections::VecDeque;
+use std::fmt;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::thread;
+use std::time;
+
+use crate::context::Context;
+use crate::error::{GameError, GameResult};
+use crate::filesystem;
+use crate::graphics::*;
+use crate::timer;
+
+/// How many readback buffers a recording needs, next to anything else
+/// using the readback ring.
+const RECORDING_BUFFERS: usize = 8;
+
+const PNG_SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A];
+
+/// The file formats a recording can be saved as.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum RecordingFormat {
+    /// Numbered PNG files, `00000.png`, `00001.png` and so on, in the
+    /// directory given as the recording's path.
+    PngSequence,
+    /// An animated GIF.  Each frame is reduced to 256 colors.  Needs
+    /// the `gif` cargo feature.
+    Gif,
+    /// An animated PNG.
+    Apng,
+}
+
+/// Settings for a recording, started with
+/// [`start_recording()`](fn.start_recording.html).
+///
+/// ```rust,no_run
+/// # use ggez::*;
+/// # use ggez::graphics::*;
+/// # fn t(ctx: &mut Context) -> GameResult {
+/// let recording = Recording::new("/recordings/bug.gif")
+///     .format(RecordingFormat::Gif)
+///     .seconds(3.0)
+///     .fps(25);
+/// start_recording(ctx, recording)?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Recording {
+    path: PathBuf,
+    format: RecordingFormat,
+    seconds: f32,
+    fps: u32,
+}
+
+impl Recording {
+    /// Creates settings for recording 5 seconds at 30 frames per second
+    /// as a PNG sequence, to the given path in the user directory.
+    pub fn new<P: AsRef<Path>>(path: P) -> Self {
+        Recording {
+            path: path.as_ref().to_path_buf(),
+            format: RecordingFormat::PngSequence,
+            seconds: 5.0,
+            fps: 30,
+        }
+    }
+
+    /// Sets the format to save the recording as.
+    pub fn format(mut self, format: RecordingFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Sets how many seconds of gameplay to record.
+    pub fn seconds(mut self, seconds: f32) -> Self {
+        self.seconds = seconds;
+        self
+    }
+
+    /// Sets how many frames per second to record at.  This is also the
+    /// fixed rate the timer runs at while recording.
+    pub fn fps(mut self, fps: u32) -> Self {
+        self.fps = fps;
+        self
+    }
+
+    /// The number of frames the recording will have.
+    fn frame_count(&self) -> usize {
+        (self.seconds * self.fps as f32).round().max(1.0) as usize
+    }
+}
+
+/// Pixels read back for one frame, sent to the encoder thread.
+struct RawFrame {
+    rgba: Vec<u8>,
+    width: u16,
+    height: u16,
+}
+
+/// A file produced by the encoder thread, or what went wrong.
+type EncodedFile = Result<(PathBuf, Vec<u8>), String>;
+
+/// The state of a running recording, kept in the graphics context.
+pub(crate) struct ActiveRecording {
+    frames_left: usize,
+    pending: VecDeque<ReadbackHandle>,
+    /// Dropped once every frame has been sent, which ends the encoder.
+    frames: Option<mpsc::Sender<RawFrame>>,
+    files: mpsc::Receiver<EncodedFile>,
+    previous_fixed_delta: Option<time::Duration>,
+}
+
+impl fmt::Debug for ActiveRecording {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "<ActiveRecording: {} frames left, {} pending>",
+            self.frames_left,
+            self.pending.len()
+        )
+    }
+}
+
+/// Starts recording the frames shown by [`present()`](fn.present.html).
+///
+/// The recording stops by itself once it has enough frames, and the
+/// files are written a little while later, once they are encoded.  Call
+/// [`stop_recording()`](fn.stop_recording.html) to end it early.
+pub fn start_recording(ctx: &mut Context, recording: Recording) -> GameResult {
+    if ctx.gfx_context.recording.is_some() {
+        return Err(GameError::RenderError(
+            "A recording is already running".to_string(),
+        ));
+    }
+    if recording.fps == 0 || recording.seconds.is_nan() || recording.seconds <= 0.0 {
+        return Err(GameError::InvalidValue(format!(
+            "Can't record {} seconds at {} fps",
+            recording.seconds, recording.fps
+        )));
+    }
+    #[cfg(not(feature = "gif"))]
+    {
+        if recording.format == RecordingFormat::Gif {
+            return Err(GameError::InvalidValue(
+                "Recording GIFs needs the `gif` feature of ggez".to_string(),
+            ));
+        }
+    }
+    if recording.format == RecordingFormat::PngSequence {
+        filesystem::user_create_dir(ctx, &recording.path)?;
+    }
+    crate::graphics::readback::reserve_readback_buffers(ctx, RECORDING_BUFFERS);
+
+    let (frame_sender, frame_receiver) = mpsc::channel();
+    let (file_sender, file_receiver) = mpsc::channel();
+    let frames_left = recording.frame_count();
+    let Recording {
+        path, format, fps, ..
+    } = recording;
+    let _ = thread::Builder::new()
+        .name("ggez recorder".to_string())
+        .spawn(move || run_encoder(format, path, fps, &frame_receiver, &file_sender))?;
+
+    let previous_fixed_delta = timer::fixed_delta(ctx);
+    timer::set_fixed_delta(ctx, Some(timer::fps_as_duration(fps)));
+    ctx.gfx_context.recording = Some(ActiveRecording {
+        frames_left,
+        pending: VecDeque::new(),
+        frames: Some(frame_sender),
+        files: file_receiver,
+        previous_fixed_delta,
+    });
+    Ok(())
+}
+
+/// Ends the current recording, if any, and writes out what has been
+/// recorded so far.  This waits for the remaining frames to be read
+/// back and encoded.
+pub fn stop_recording(ctx: &mut Context) -> GameResult {
+    let pending = match ctx.gfx_context.recording {
+        Some(ref mut recording) => {
+            recording.frames_left = 0;
+            recording.pending.drain(..).collect::<Vec<_>>()
+        }
+        None => return Ok(()),
+    };
+    for handle in pending {
+        // Frames that were overwritten by other readbacks are lost.
+        if let Ok(rgba) = handle.wait(ctx) {
+            send_frame(ctx, &handle, rgba);
+        }
+    }
+    if let Some(ref mut recording) = ctx.gfx_context.recording {
+        recording.frames = None;
+    }
+    let result = write_files(ctx, true);
+    end_recording(ctx);
+    result.map(|_| ())
+}
+
+/// Returns `true` while a recording is running or still being written.
+pub fn is_recording(ctx: &Context) -> bool {
+    ctx.gfx_context.recording.is_some()
+}
+
+/// Called by `present()` with the screen as render target, before the
+/// frame is submitted: passes finished readbacks to the encoder, queues
+/// a readback of the frame being presented and writes encoded files.
+pub(crate) fn capture(ctx: &mut Context) -> GameResult {
+    if ctx.gfx_context.recording.is_none() {
+        return Ok(());
+    }
+    loop {
+        let handle = match ctx
+            .gfx_context
+            .recording
+            .as_ref()
+            .and_then(|r| r.pending.front())
+        {
+            Some(handle) => handle.clone(),
+            None => break,
+        };
+        match handle.poll(ctx) {
+            Ok(Some(rgba)) => {
+                let _ = recording_mut(ctx).pending.pop_front();
+                send_frame(ctx, &handle, rgba);
+            }
+            Ok(None) => break,
+            Err(e) => {
+                warn!("Dropped a recorded frame: {}", e);
+                let _ = recording_mut(ctx).pending.pop_front();
+            }
+        }
+    }
+    if recording_mut(ctx).frames_left > 0 {
+        let handle = screenshot_async(ctx)?;
+        let recording = recording_mut(ctx);
+        recording.pending.push_back(handle);
+        recording.frames_left -= 1;
+    }
+    {
+        let recording = recording_mut(ctx);
+        if recording.frames_left == 0 && recording.pending.is_empty() {
+            recording.frames = None;
+        }
+    }
+    match write_files(ctx, false) {
+        Ok(false) => Ok(()),
+        Ok(true) => {
+            end_recording(ctx);
+            Ok(())
+        }
+        Err(e) => {
+            end_recording(ctx);
+            Err(e)
+        }
+    }
+}
+
+fn recording_mut(ctx: &mut Context) -> &mut ActiveRecording {
+    ctx.gfx_context
+        .recording
+        .as_mut()
+        .expect("No recording running; should never happen")
+}
+
+fn send_frame(ctx: &mut Context, handle: &ReadbackHandle, rgba: Vec<u8>) {
+    if let Some(ref sender) = recording_mut(ctx).frames {
+        // If the encoder is gone it has already reported why.
+        let _ = sender.send(RawFrame {
+            rgba,
+            width: handle.width(),
+            height: handle.height(),
+        });
+    }
+}
+
+/// Writes the files the encoder has finished, waiting for all of them
+/// if `wait` is set.  Returns `true` once the encoder is done.
+fn write_files(ctx: &mut Context, wait: bool) -> GameResult<bool> {
+    loop {
+        let received = {
+            let files = &recording_mut(ctx).files;
+            if wait {
+                files.recv().map_err(|_| mpsc::TryRecvError::Disconnected)
+            } else {
+                files.try_recv()
+            }
+        };
+        match received {
+            Ok(Ok((path, bytes))) => {
+                let mut file = filesystem::user_create(ctx, path)?;
+                file.write_all(&bytes)?;
+            }
+            Ok(Err(e)) => {
+                return Err(GameError::RenderError(format!(
+                    "Could not encode recording: {}",
+                    e
+                )))
+            }
+            Err(mpsc::TryRecvError::Empty) => return Ok(false),
+            Err(mpsc::TryRecvError::Disconnected) => return Ok(true),
+        }
+    }
+}
+
+/// Throws away the recording state and gives the timer back its old
+/// frame length.  A still running encoder finishes on its own.
+fn end_recording(ctx: &mut Context) {
+    if let Some(recording) = ctx.gfx_context.recording.take() {
+        timer::set_fixed_delta(ctx, recording.previous_fixed_delta);
+    }
+}
+
+/// The body of the encoder thread.
+fn run_encoder(
+    format: RecordingFormat,
+    path: PathBuf,
+    fps: u32,
+    frames: &mpsc::Receiver<RawFrame>,
+    files: &mpsc::Sender<EncodedFile>,
+) {
+    let result = match format {
+        RecordingFormat::PngSequence => frames.iter().enumerate().try_for_each(|(i, frame)| {
+            let bytes = encode_png(&frame)?;
+            let _ = files.send(Ok((path.join(format!("{:05}.png", i)), bytes)));
+            Ok(())
+        }),
+        #[cfg(feature = "gif")]
+        RecordingFormat::Gif => encode_gif(frames, fps).map(|bytes| {
+            if let Some(bytes) = bytes {
+                let _ = files.send(Ok((path.clone(), bytes)));
+            }
+        }),
+        // Turned down by `start_recording()`.
+        #[cfg(not(feature = "gif"))]
+        RecordingFormat::Gif => Err("Recording GIFs needs the `gif` feature".to_string()),
+        RecordingFormat::Apng => encode_apng(frames, fps).map(|bytes| {
+            if let Some(bytes) = bytes {
+                let _ = files.send(Ok((path.clone(), bytes)));
+            }
+        }),
+    };
+    if let Err(e) = result {
+        let _ = files.send(Err(e));
+    }
+}
+
+fn encode_png(frame: &RawFrame) -> Result<Vec<u8>, String> {
+    let mut bytes = Vec::new();
+    crate::graphics::image::encode_rgba8(
+        &mut bytes,
+        ImageFormat::Png,
+        &frame.rgba,
+        frame.width,
+        frame.height,
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(bytes)
+}
+
+/// Encodes all frames into a looping GIF, or returns `None` if there
+/// were no frames.  Frames of a different size than the first one are
+/// skipped.
+#[cfg(feature = "gif")]
+fn encode_gif(frames: &mpsc::Receiver<RawFrame>, fps: u32) -> Result<Option<Vec<u8>>, String> {
+    use gif::SetParameter;
+
+    let first = match frames.recv() {
+        Ok(frame) => frame,
+        Err(_) => return Ok(None),
+    };
+    let (width, height) = (first.width, first.height);
+    // GIF delays are in hundredths of a second, and most viewers treat
+    // anything below 2 as "as slow as we like".
+    let delay = ((100.0 / fps as f32).round() as u16).max(2);
+    let mut bytes = Vec::new();
+    {
+        let mut encoder =
+            gif::Encoder::new(&mut bytes, width, height, &[]).map_err(|e| e.to_string())?;
+        encoder
+            .set(gif::Repeat::Infinite)
+            .map_err(|e| e.to_string())?;
+        for mut frame in std::iter::once(first).chain(frames.iter()) {
+            if (frame.width, frame.height) != (width, height) {
+                continue;
+            }
+            let mut gif_frame = gif::Frame::from_rgba_speed(width, height, &mut frame.rgba, 10);
+            gif_frame.delay = delay;
+            encoder.write_frame(&gif_frame).map_err(|e| e.to_string())?;
+        }
+    }
+    Ok(Some(bytes))
+}
+
+/// Encodes all frames into a looping APNG, or returns `None` if there
+/// were no frames.  Frames of a different size than the first one are
+/// skipped.
+///
+/// Each frame is encoded as a normal PNG first; the APNG is then made
+/// out of the first one's header and everyone's image data.
+fn encode_apng(frames: &mpsc::Receiver<RawFrame>, fps: u32) -> Result<Option<Vec<u8>>, String> {
+    let mut size = None;
+    let mut header = None;
+    let mut frame_data = Vec::new();
+    for frame in frames.iter() {
+        match size {
+            None => size = Some((frame.width, frame.height)),
+            Some(size) if size != (frame.width, frame.height) => continue,
+            Some(_) => (),
+        }
+        let png = encode_png(&frame)?;
+        let mut data = Vec::new();
+        for (kind, body) in png_chunks(&png)? {
+            if &kind == b"IHDR" && header.is_none() {
+                header = Some(body.to_vec());
+            } else if &kind == b"IDAT" {
+                data.extend_from_slice(body);
+            }
+        }
+        frame_data.push(data);
+    }
+    let ((width, height), header) = match (size, header) {
+        (Some(size), Some(header)) => (size, header),
+        _ => return Ok(None),
+    };
+
+    let mut bytes = PNG_SIGNATURE.to_vec();
+    write_chunk(&mut bytes, b"IHDR", &header);
+    let mut animation_control = Vec::with_capacity(8);
+    animation_control.extend_from_slice(&(frame_data.len() as u32).to_be_bytes());
+    // Loop forever.
+    animation_control.extend_from_slice(&0u32.to_be_bytes());
+    write_chunk(&mut bytes, b"acTL", &animation_control);
+
+    let delay_denominator = fps.min(u32::from(u16::max_value())) as u16;
+    let mut sequence = 0u32;
+    for (i, data) in frame_data.iter().enumerate() {
+        let mut frame_control = Vec::with_capacity(26);
+        frame_control.extend_from_slice(&sequence.to_be_bytes());
+        frame_control.extend_from_slice(&u32::from(width).to_be_bytes());
+        frame_control.extend_from_slice(&u32::from(height).to_be_bytes());
+        // x and y offset
+        frame_control.extend_from_slice(&[0; 8]);
+        // Each frame lasts 1/fps seconds.
+        frame_control.extend_from_slice(&1u16.to_be_bytes());
+        frame_control.extend_from_slice(&delay_denominator.to_be_bytes());
+        // Dispose and blend ops: leave the frame as is, overwrite it.
+        frame_control.extend_from_slice(&[0, 0]);
+        write_chunk(&mut bytes, b"fcTL", &frame_control);
+        sequence += 1;
+
+        if i == 0 {
+            write_chunk(&mut bytes, b"IDAT", data);
+        } else {
+            let mut frame_data = Vec::with_capacity(data.len() + 4);
+            frame_data.extend_from_slice(&sequence.to_be_bytes());
+            frame_data.extend_from_slice(data);
+            write_chunk(&mut bytes, b"fdAT", &frame_data);
+            sequence += 1;
+        }
+    }
+    write_chunk(&mut bytes, b"IEND", &[]);
+    Ok(Some(bytes))
+}
+
+/// Splits a PNG file into its chunks' types and data.
+fn png_chunks(png: &[u8]) -> Result<Vec<([u8; 4], &[u8])>, String> {
+    if !png.starts_with(&PNG_SIGNATURE) {
+        return Err("Not a PNG file".to_string());
+    }
+    let mut chunks = Vec::new();
+    let mut rest = &png[PNG_SIGNATURE.len()..];
+    while !rest.is_empty() {
+        if rest.len() < 12 {
+            return Err("Truncated PNG chunk".to_string());
+        }
+        let len = u32::from_be_bytes([rest[0], rest[1], rest[2], rest[3]]) as usize;
+        if rest.len() < 12 + len {
+            return Err("Truncated PNG chunk".to_string());
+        }
+        let kind = [rest[4], rest[5], rest[6], rest[7]];
+        chunks.push((kind, &rest[8..8 + len]));
+        rest = &rest[12 + len..];
+    }
+    Ok(chunks)
+}
+
+fn write_chunk(out: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    out.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    out.extend_from_slice(kind);
+    out.extend_from_slice(data);
+    out.extend_from_slice(&crc32(&[kind, data]).to_be_bytes());
+}
+
+/// The CRC-32 that PNG chunks end with.
+fn crc32(parts: &[&[u8]]) -> u32 {
+    let mut crc = 0xffff_ffff_u32;
+    for &byte in parts.iter().flat_map(|part| part.iter()) {
+        crc ^= u32::from(byte);
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0xedb8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn headless_test_crc32() {
+        assert_eq!(crc32(&[b"IEND"]), 0xae42_6082);
+        assert_eq!(crc32(&[b"IE", b"ND"]), 0xae42_6082);
+    }
+
+    #[test]
+    fn headless_test_apng_chunks() {
+        let (sender, receiver) = mpsc::channel();
+        for &(width, height) in &[(2, 2), (3, 3), (2, 2)] {
+            sender
+                .send(RawFrame {
+                    rgba: vec![255; width * height * 4],
+                    width: width as u16,
+                    height: height as u16,
+                })
+                .unwrap();
+        }
+        drop(sender);
+        let apng = encode_apng(&receiver, 30).unwrap().unwrap();
+        let kinds: Vec<[u8; 4]> = png_chunks(&apng)
+            .unwrap()
+            .into_iter()
+            .map(|(kind, _)| kind)
+            .collect();
+        // The 3x3 frame is skipped.
+        assert_eq!(
+            kinds,
+            vec![*b"IHDR", *b"acTL", *b"fcTL", *b"IDAT", *b"fcTL", *b"fdAT", *b"IEND"]
+        );
+    }
+
+    #[test]
+    fn headless_test_recording_frame_count() {
+        assert_eq!(Recording::new("/r").seconds(2.0).fps(25).frame_count(), 50);
+        assert_eq!(Recording::new("/r").seconds(0.001).frame_count(), 1);
+    }
+}
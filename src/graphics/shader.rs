@@ -198,6 +198,7 @@ pub(crate) fn create_shader<C, S, Spec>(
     multisample_samples: u8,
     blend_modes: Option<&[BlendMode]>,
     color_format: format::Format,
+    default_texture: Option<ShaderTexture<Spec::Resources>>,
     debug_id: DebugId,
 ) -> GameResult<(ShaderGeneric<Spec, C>, Box<dyn ShaderHandle<Spec>>)>
 where
@@ -250,6 +251,8 @@ where
         buffer: buffer.clone(),
         psos,
         active_blend_mode: blend_modes[0],
+        textures: HashMap::new(),
+        default_texture,
     };
     let draw: Box<dyn ShaderHandle<Spec>> = Box::new(program);
 
@@ -320,6 +323,14 @@ where
     ) -> GameResult<Shader<C>> {
         let debug_id = DebugId::get(ctx);
         let color_format = ctx.gfx_context.color_format();
+        // Extra texture inputs sample white until something is sent.
+        let white = &ctx.gfx_context.white_image;
+        let default_texture = (
+            white.texture.clone(),
+            ctx.gfx_context
+                .samplers
+                .get_or_insert(white.sampler_info, ctx.gfx_context.factory.as_mut()),
+        );
         let (mut shader, draw) = create_shader(
             vertex_source,
             pixel_source,
@@ -330,6 +341,7 @@ where
             ctx.gfx_context.multisample_samples,
             blend_modes,
             color_format,
+            Some(default_texture),
             debug_id,
         )?;
         shader.id = ctx.gfx_context.shaders.len();
@@ -351,6 +363,46 @@ where
         Ok(())
     }
 
+    /// Sends a [`Texture`](struct.Texture.html) to the sampler uniform
+    /// with the given name, such as a `sampler1D` palette, a
+    /// `sampler2DArray` of tiles or a `sampler3D` color grading table.
+    /// It stays bound until another texture is sent to the same name.
+    ///
+    /// Every sampler uniform other than `t_Texture`, which gets the
+    /// image being drawn, is such an extra input.  Until a texture is
+    /// sent to it, it gets a 1x1 white image, which reads as white from
+    /// a `sampler2D`.
+    pub fn send_texture(
+        &self,
+        ctx: &mut Context,
+        name: &str,
+        texture: &graphics::Texture,
+    ) -> GameResult {
+        self.debug_id.assert(ctx);
+        texture.debug_id.assert(ctx);
+        let gfx = &mut ctx.gfx_context;
+        let sampler = gfx
+            .samplers
+            .get_or_insert(texture.sampler_info, gfx.factory.as_mut());
+        gfx.shaders[self.id].set_texture(name, texture.texture.clone(), sampler);
+        Ok(())
+    }
+
+    /// Sends an [`Image`](struct.Image.html) to the `sampler2D` uniform
+    /// with the given name, sampled with the image's filter and wrap
+    /// modes, like [`send_texture()`](#method.send_texture).
+    pub fn send_image(&self, ctx: &mut Context, name: &str, image: &graphics::Image) -> GameResult {
+        self.debug_id.assert(ctx);
+        image.debug_id.assert(ctx);
+        image.resolve_multisample(ctx)?;
+        let gfx = &mut ctx.gfx_context;
+        let sampler = gfx
+            .samplers
+            .get_or_insert(image.sampler_info, gfx.factory.as_mut());
+        gfx.shaders[self.id].set_texture(name, image.texture.clone(), sampler);
+        Ok(())
+    }
+
     /// Gets the shader ID for the `Shader` which is used by the
     /// graphics context for identifying shaders in its cache
     pub fn shader_id(&self) -> ShaderId {
@@ -368,10 +420,18 @@ where
     }
 }
 
+/// A texture sent to a shader, and the sampler to sample it with.
+type ShaderTexture<R> = (RawShaderResourceView<R>, gfx::handle::Sampler<R>);
+
 struct ShaderProgram<Spec: graphics::BackendSpec, C: Structure<ConstFormat>> {
     buffer: Buffer<Spec::Resources, C>,
     psos: PsoSet<Spec, C>,
     active_blend_mode: BlendMode,
+    /// Textures sent with `Shader::send_texture()` and friends, by the
+    /// name of their sampler uniform.
+    textures: HashMap<String, ShaderTexture<Spec::Resources>>,
+    /// The texture for extra inputs that nothing was sent to.
+    default_texture: Option<ShaderTexture<Spec::Resources>>,
 }
 
 impl<Spec, C> fmt::Debug for ShaderProgram<Spec, C>
@@ -400,6 +460,14 @@ pub trait ShaderHandle<Spec: graphics::BackendSpec>: fmt::Debug {
 
     /// Gets the shader program's current blend mode
     fn blend_mode(&self) -> BlendMode;
+
+    /// Sets the texture for the sampler uniform with the given name
+    fn set_texture(
+        &mut self,
+        name: &str,
+        view: RawShaderResourceView<Spec::Resources>,
+        sampler: gfx::handle::Sampler<Spec::Resources>,
+    );
 }
 
 impl<Spec, C> ShaderHandle<Spec> for ShaderProgram<Spec, C>
@@ -414,7 +482,11 @@ where
         data: &graphics::pipe::Data<Spec::Resources>,
     ) -> GameResult {
         let pso = self.psos.mode(self.active_blend_mode)?;
-        encoder.draw(slice, pso, &ConstData(data, &self.buffer));
+        encoder.draw(
+            slice,
+            pso,
+            &ConstData(data, &self.buffer, &self.textures, &self.default_texture),
+        );
         Ok(())
     }
 
@@ -427,6 +499,15 @@ where
     fn blend_mode(&self) -> BlendMode {
         self.active_blend_mode
     }
+
+    fn set_texture(
+        &mut self,
+        name: &str,
+        view: RawShaderResourceView<Spec::Resources>,
+        sampler: gfx::handle::Sampler<Spec::Resources>,
+    ) {
+        let _ = self.textures.insert(name.to_string(), (view, sampler));
+    }
 }
 
 /// A lock for RAII shader regions. The shader automatically gets cleared once
@@ -479,11 +560,32 @@ pub fn clear_shader(ctx: &mut Context) {
     *ctx.gfx_context.current_shader.borrow_mut() = None;
 }
 
+/// The name of the sampler uniform that gets the texture being drawn.
+/// Any other sampler uniform in a shader is an extra texture input.
+const DRAW_TEXTURE_NAME: &str = "t_Texture";
+
+/// The resource view and sampler slots of an extra texture input.
+#[derive(Debug)]
+struct ExtraTextureMeta {
+    name: String,
+    view: gfx::pso::resource::RawShaderResource,
+    sampler: gfx::pso::resource::Sampler,
+}
+
 #[derive(Debug)]
-struct ConstMeta<C: Structure<ConstFormat>>(graphics::pipe::Meta, ConstantBuffer<C>);
+struct ConstMeta<C: Structure<ConstFormat>>(
+    graphics::pipe::Meta,
+    ConstantBuffer<C>,
+    Vec<ExtraTextureMeta>,
+);
 
 #[derive(Debug)]
-struct ConstData<'a, R: Resources, C: 'a>(&'a graphics::pipe::Data<R>, &'a Buffer<R, C>);
+struct ConstData<'a, R: Resources, C: 'a>(
+    &'a graphics::pipe::Data<R>,
+    &'a Buffer<R, C>,
+    &'a HashMap<String, ShaderTexture<R>>,
+    &'a Option<ShaderTexture<R>>,
+);
 
 impl<'a, R, C> PipelineData<R> for ConstData<'a, R, C>
 where
@@ -501,6 +603,13 @@ where
     ) {
         self.0.bake_to(out, &meta.0, man, access);
         meta.1.bind_to(out, self.1, man, access);
+        // Extra textures that were never sent get the default texture.
+        for extra in &meta.2 {
+            if let Some((view, sampler)) = self.2.get(&extra.name).or_else(|| self.3.as_ref()) {
+                extra.view.bind_to(out, view, man, access);
+                extra.sampler.bind_to(out, sampler, man, access);
+            }
+        }
     }
 }
 
@@ -534,11 +643,53 @@ where
             }
         }
 
-        if let Some(index) = index {
+        // Link any sampler uniforms besides the one for the texture being
+        // drawn as extra texture inputs.
+        let mut extras: Vec<ExtraTextureMeta> = Vec::new();
+        for tex in info
+            .textures
+            .iter()
+            .filter(|tex| tex.name != DRAW_TEXTURE_NAME)
+        {
+            let mut view = gfx::pso::resource::RawShaderResource::new();
+            match view.link_resource_view(tex, &tex.name.as_str()) {
+                Some(Ok(d)) => desc.resource_views[tex.slot as usize] = Some(d),
+                _ => return Err(InitError::ResourceView(&tex.name, None)),
+            }
+            extras.push(ExtraTextureMeta {
+                name: tex.name.clone(),
+                view,
+                sampler: gfx::pso::resource::Sampler::new(),
+            });
+        }
+        for sm in info
+            .samplers
+            .iter()
+            .filter(|sm| sm.name != DRAW_TEXTURE_NAME)
+        {
+            let extra = extras
+                .iter_mut()
+                .find(|extra| extra.name == sm.name)
+                .ok_or(InitError::Sampler(&sm.name[..], None))?;
+            match extra.sampler.link_sampler(sm, &sm.name.as_str()) {
+                Some(d) => desc.samplers[sm.slot as usize] = Some(d),
+                None => return Err(InitError::Sampler(&sm.name, None)),
+            }
+        }
+
+        if index.is_some() || !extras.is_empty() {
             // create a local clone of the program info so that we can remove
-            // the var we found from the `constant_buffer`
+            // the vars we linked ourselves
             let mut program_info = info.clone();
-            let _ = program_info.constant_buffers.remove(index);
+            if let Some(index) = index {
+                let _ = program_info.constant_buffers.remove(index);
+            }
+            program_info
+                .textures
+                .retain(|tex| tex.name == DRAW_TEXTURE_NAME);
+            program_info
+                .samplers
+                .retain(|sm| sm.name == DRAW_TEXTURE_NAME);
 
             let meta0 = match self.0.link_to(desc, &program_info) {
                 Ok(m) => m,
@@ -573,9 +724,9 @@ where
                 }
             };
 
-            Ok(ConstMeta(meta0, meta1))
+            Ok(ConstMeta(meta0, meta1, extras))
         } else {
-            Ok(ConstMeta(self.0.link_to(desc, info)?, meta1))
+            Ok(ConstMeta(self.0.link_to(desc, info)?, meta1, extras))
         }
     }
 }
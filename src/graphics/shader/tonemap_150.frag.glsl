@@ -0,0 +1,29 @@
+#version 150 core
+
+uniform sampler2D t_Texture;
+in vec2 v_Uv;
+in vec4 v_Color;
+out vec4 Target0;
+
+layout (std140) uniform Globals {
+    mat4 u_MVP;
+};
+
+layout (std140) uniform Tonemap {
+    float u_Exposure;
+};
+
+vec3 tonemap(vec3 c) {
+#ifdef TONEMAP_ACES
+    // Krzysztof Narkowicz's fit of the ACES filmic curve.
+    c *= 0.6;
+    return clamp((c * (2.51 * c + 0.03)) / (c * (2.43 * c + 0.59) + 0.14), 0.0, 1.0);
+#else
+    return c / (1.0 + c);
+#endif
+}
+
+void main() {
+    vec4 color = texture(t_Texture, v_Uv) * v_Color;
+    Target0 = vec4(tonemap(max(color.rgb, 0.0) * u_Exposure), clamp(color.a, 0.0, 1.0));
+}
@@ -0,0 +1,25 @@
+#version 300 es
+
+uniform mediump sampler2D t_Texture;
+in mediump vec2 v_Uv;
+in mediump vec4 v_Color;
+out mediump vec4 Target0;
+
+layout (std140) uniform Tonemap {
+    mediump float u_Exposure;
+};
+
+mediump vec3 tonemap(mediump vec3 c) {
+#ifdef TONEMAP_ACES
+    // Krzysztof Narkowicz's fit of the ACES filmic curve.
+    c *= 0.6;
+    return clamp((c * (2.51 * c + 0.03)) / (c * (2.43 * c + 0.59) + 0.14), 0.0, 1.0);
+#else
+    return c / (1.0 + c);
+#endif
+}
+
+void main() {
+    mediump vec4 color = texture(t_Texture, v_Uv) * v_Color;
+    Target0 = vec4(tonemap(max(color.rgb, 0.0) * u_Exposure), clamp(color.a, 0.0, 1.0));
+}
@@ -93,6 +93,7 @@ impl SpriteBatch {
         // Though we do awkwardly have to allocate a new vector.
         // ...though upon benchmarking, the actual allocation is basically nothing,
         // the cost in debug mode is alllll math.
+        let srgb = ctx.gfx_context.is_srgb();
         let new_sprites = self
             .sprites
             .iter()
@@ -108,7 +109,7 @@ impl SpriteBatch {
                 new_param.scale = real_scale.into();
                 new_param.color = new_param.color;
                 let primitive_param = graphics::DrawTransform::from(new_param);
-                primitive_param.to_instance_properties()
+                primitive_param.to_instance_properties(srgb)
             })
             .collect::<Vec<_>>();
 
@@ -159,6 +160,7 @@ impl graphics::Drawable for SpriteBatch {
             return Ok(());
         }
 
+        self.image.resolve_multisample(ctx)?;
         // Awkwardly we must update values on all sprites and such.
         // Also awkwardly we have this chain of colors with differing priorities.
         self.flush(ctx, &self.image)?;
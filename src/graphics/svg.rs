@@ -0,0 +1,1161 @@
+//! Loading of a practical subset of SVG into a [`Mesh`](struct.Mesh.html).
+//!
+//! Supported are `<path>`, `<rect>` (including rounded corners),
+//! `<circle>`, `<ellipse>`, `<line>`, `<polyline>` and `<polygon>`
+//! elements, nested `<g>` groups, the `transform` attribute, and
+//! solid fill and stroke colors with their opacities, given either as
+//! presentation attributes or in a `style` attribute.  Colors can be
+//! hex, `rgb()`/`rgba()` or any CSS color name; a fill or stroke in
+//! any other color is left out with a warning.
+//!
+//! Anything else (gradients, patterns, text, clipping, masks, images,
+//! CSS stylesheets, `<use>` references) is ignored.  Coordinates are
+//! used as they are, so one SVG user unit becomes one unit in the mesh;
+//! the root element's `width`, `height` and `viewBox` are not applied.
+
+use std::io::Read;
+use std::path;
+
+use roxmltree;
+
+use crate::context::Context;
+use crate::error::{GameError, GameResult};
+use crate::graphics::*;
+
+/// An affine 2D transform in SVG's `matrix(a, b, c, d, e, f)` order.
+type Transform = [f32; 6];
+
+const IDENTITY: Transform = [1.0, 0.0, 0.0, 1.0, 0.0, 0.0];
+
+impl Mesh {
+    /// Loads an SVG file from the given path in the [filesystem](../filesystem/index.html)
+    /// and tessellates all of its shapes into a single `Mesh`.
+    ///
+    /// Only a subset of SVG is understood: paths, basic shapes, groups,
+    /// transforms and solid fill/stroke colors.  See
+    /// [`MeshBuilder::svg()`](struct.MeshBuilder.html#method.svg) for details.
+    ///
+    /// For the meaning of the `tolerance` parameter, [see here](https://docs.rs/lyon_geom/0.11.0/lyon_geom/#flattening).
+    pub fn from_svg<P: AsRef<path::Path>>(
+        ctx: &mut Context,
+        path: P,
+        tolerance: f32,
+    ) -> GameResult<Mesh> {
+        let mut data = String::new();
+        let mut reader = ctx.filesystem.open(path)?;
+        let _ = reader.read_to_string(&mut data)?;
+        let mut mb = MeshBuilder::new();
+        let _ = mb.svg(&data, tolerance)?;
+        mb.build(ctx)
+    }
+}
+
+impl MeshBuilder {
+    /// Parses an SVG document and adds all of its shapes to the mesh.
+    ///
+    /// Supported are `<path>`, `<rect>`, `<circle>`, `<ellipse>`,
+    /// `<line>`, `<polyline>` and `<polygon>` elements inside nested
+    /// `<g>` groups, with `transform`s and solid `fill`/`stroke` colors.
+    /// Unsupported elements and paints such as gradients are skipped.
+    /// SVG user units map directly to mesh units.
+    ///
+    /// For the meaning of the `tolerance` parameter, [see here](https://docs.rs/lyon_geom/0.11.0/lyon_geom/#flattening).
+    /// It is measured after transforms are applied.
+    pub fn svg(&mut self, data: &str, tolerance: f32) -> GameResult<&mut Self> {
+        let document = roxmltree::Document::parse(data)
+            .map_err(|e| GameError::ResourceLoadError(format!("Could not parse SVG: {}", e)))?;
+        let root = document.root_element();
+        if root.tag_name().name() != "svg" {
+            return Err(GameError::ResourceLoadError(format!(
+                "Expected an <svg> root element, got <{}>",
+                root.tag_name().name()
+            )));
+        }
+        let style = Style::default().inherit(root)?;
+        let transform = parse_transform(attribute(root, "transform"))?;
+        add_children(self, root, &style, &transform, tolerance)?;
+        Ok(self)
+    }
+}
+
+/// The inheritable presentation properties we care about.
+#[derive(Debug, Copy, Clone, PartialEq)]
+struct Style {
+    fill: Option<Color>,
+    stroke: Option<Color>,
+    stroke_width: f32,
+    fill_opacity: f32,
+    stroke_opacity: f32,
+    opacity: f32,
+    fill_rule: FillRule,
+    line_cap: LineCap,
+    line_join: LineJoin,
+    miter_limit: f32,
+}
+
+impl Default for Style {
+    fn default() -> Self {
+        Style {
+            fill: Some(BLACK),
+            stroke: None,
+            stroke_width: 1.0,
+            fill_opacity: 1.0,
+            stroke_opacity: 1.0,
+            opacity: 1.0,
+            fill_rule: FillRule::NonZero,
+            line_cap: LineCap::Butt,
+            line_join: LineJoin::Miter,
+            miter_limit: 4.0,
+        }
+    }
+}
+
+impl Style {
+    /// Returns the style of `node`, given that `self` is its parent's style.
+    fn inherit(&self, node: roxmltree::Node) -> GameResult<Style> {
+        let mut style = *self;
+        if let Some(fill) = attribute(node, "fill") {
+            style.fill = paint_or_none(fill);
+        }
+        if let Some(stroke) = attribute(node, "stroke") {
+            style.stroke = paint_or_none(stroke);
+        }
+        if let Some(width) = attribute(node, "stroke-width") {
+            style.stroke_width = parse_length(width)?;
+        }
+        if let Some(opacity) = attribute(node, "fill-opacity") {
+            style.fill_opacity = parse_number(opacity)?;
+        }
+        if let Some(opacity) = attribute(node, "stroke-opacity") {
+            style.stroke_opacity = parse_number(opacity)?;
+        }
+        // Strictly speaking `opacity` applies to a group as a whole rather
+        // than being inherited, but multiplying it down is a close match
+        // for shapes that don't overlap.
+        if let Some(opacity) = attribute(node, "opacity") {
+            style.opacity *= parse_number(opacity)?;
+        }
+        match attribute(node, "fill-rule") {
+            Some("evenodd") => style.fill_rule = FillRule::EvenOdd,
+            Some("nonzero") => style.fill_rule = FillRule::NonZero,
+            _ => (),
+        }
+        match attribute(node, "stroke-linecap") {
+            Some("butt") => style.line_cap = LineCap::Butt,
+            Some("round") => style.line_cap = LineCap::Round,
+            Some("square") => style.line_cap = LineCap::Square,
+            _ => (),
+        }
+        match attribute(node, "stroke-linejoin") {
+            Some("miter") => style.line_join = LineJoin::Miter,
+            Some("round") => style.line_join = LineJoin::Round,
+            Some("bevel") => style.line_join = LineJoin::Bevel,
+            _ => (),
+        }
+        if let Some(limit) = attribute(node, "stroke-miterlimit") {
+            style.miter_limit = parse_number(limit)?;
+        }
+        Ok(style)
+    }
+
+    fn fill_color(&self) -> Option<Color> {
+        self.fill.map(|c| Color {
+            a: c.a * self.fill_opacity * self.opacity,
+            ..c
+        })
+    }
+
+    fn stroke_color(&self) -> Option<Color> {
+        self.stroke.map(|c| Color {
+            a: c.a * self.stroke_opacity * self.opacity,
+            ..c
+        })
+    }
+}
+
+/// Looks up a presentation property on a node, preferring a declaration
+/// in its `style` attribute over a plain attribute, as CSS does.
+fn attribute<'a>(node: roxmltree::Node<'a, '_>, name: &str) -> Option<&'a str> {
+    let from_style = node.attribute("style").and_then(|style| {
+        style
+            .split(';')
+            .filter_map(|declaration| {
+                let mut parts = declaration.splitn(2, ':');
+                let key = parts.next()?.trim();
+                let value = parts.next()?.trim();
+                Some((key, value))
+            })
+            .filter(|(key, _)| *key == name)
+            .map(|(_, value)| value)
+            .last()
+    });
+    from_style.or_else(|| node.attribute(name)).map(str::trim)
+}
+
+fn add_children(
+    builder: &mut MeshBuilder,
+    node: roxmltree::Node,
+    style: &Style,
+    transform: &Transform,
+    tolerance: f32,
+) -> GameResult {
+    for child in node.children().filter(|n| n.is_element()) {
+        if attribute(child, "display") == Some("none") {
+            continue;
+        }
+        let style = style.inherit(child)?;
+        let transform = multiply(transform, &parse_transform(attribute(child, "transform"))?);
+        let number = |name: &str| -> GameResult<f32> {
+            child.attribute(name).map(parse_length).unwrap_or(Ok(0.0))
+        };
+        let mut writer = PathWriter::new();
+        match child.tag_name().name() {
+            "g" | "svg" | "a" | "switch" => {
+                add_children(builder, child, &style, &transform, tolerance)?;
+                continue;
+            }
+            "path" => {
+                writer.path_data(child.attribute("d").unwrap_or(""))?;
+            }
+            "rect" => {
+                let (x, y) = (number("x")?, number("y")?);
+                let (w, h) = (number("width")?, number("height")?);
+                let (rx, ry) = match (child.attribute("rx"), child.attribute("ry")) {
+                    (None, None) => (0.0, 0.0),
+                    (Some(_), None) => (number("rx")?, number("rx")?),
+                    (None, Some(_)) => (number("ry")?, number("ry")?),
+                    (Some(_), Some(_)) => (number("rx")?, number("ry")?),
+                };
+                writer.rectangle(x, y, w, h, rx.min(w / 2.0), ry.min(h / 2.0));
+            }
+            "circle" => {
+                let r = number("r")?;
+                writer.ellipse(number("cx")?, number("cy")?, r, r);
+            }
+            "ellipse" => {
+                writer.ellipse(number("cx")?, number("cy")?, number("rx")?, number("ry")?);
+            }
+            "line" => {
+                writer.move_to(Point2::new(number("x1")?, number("y1")?));
+                writer.line_to(Point2::new(number("x2")?, number("y2")?));
+            }
+            "polyline" | "polygon" => {
+                let points = parse_numbers(child.attribute("points").unwrap_or(""))?;
+                for (i, pair) in points.chunks_exact(2).enumerate() {
+                    let point = Point2::new(pair[0], pair[1]);
+                    if i == 0 {
+                        writer.move_to(point);
+                    } else {
+                        writer.line_to(point);
+                    }
+                }
+                if child.tag_name().name() == "polygon" {
+                    writer.close();
+                }
+            }
+            // Everything else (defs, text, images, gradients...) is skipped.
+            _ => continue,
+        }
+        if !writer.path.is_empty() {
+            add_shape(builder, &writer.path, &style, &transform, tolerance)?;
+        }
+    }
+    Ok(())
+}
+
+/// Tessellates one shape in its own coordinate system and appends it,
+/// transformed, to the builder.  Tessellating before transforming means
+/// that arcs and stroke widths get skewed and scaled the way SVG does it.
+fn add_shape(
+    builder: &mut MeshBuilder,
+    path: &PathBuilder,
+    style: &Style,
+    transform: &Transform,
+    tolerance: f32,
+) -> GameResult {
+    let scale = (transform[0] * transform[3] - transform[1] * transform[2])
+        .abs()
+        .sqrt();
+    if scale <= std::f32::EPSILON {
+        return Ok(());
+    }
+    let tolerance = tolerance / scale;
+    let to_parent = |p: [f32; 2]| apply(transform, p);
+
+    if let Some(color) = style.fill_color() {
+        let options = FillOptions::tolerance(tolerance).with_fill_rule(style.fill_rule);
+        let mut shape = MeshBuilder::new();
+        if shape.path(DrawMode::Fill(options), path, color).is_err() {
+            // Not every fill rule is supported by every tessellator
+            // version, so fall back to even-odd rather than losing the shape.
+            shape = MeshBuilder::new();
+            let options = options.with_fill_rule(FillRule::EvenOdd);
+            let _ = shape.path(DrawMode::Fill(options), path, color)?;
+        }
+        let _ = builder.append_transformed(&shape, to_parent);
+    }
+    if let Some(color) = style.stroke_color() {
+        if style.stroke_width > 0.0 {
+            let options = StrokeOptions::tolerance(tolerance)
+                .with_line_width(style.stroke_width)
+                .with_line_cap(style.line_cap)
+                .with_line_join(style.line_join)
+                .with_miter_limit(style.miter_limit.max(1.0));
+            let mut shape = MeshBuilder::new();
+            let _ = shape.path(DrawMode::Stroke(options), path, color)?;
+            let _ = builder.append_transformed(&shape, to_parent);
+        }
+    }
+    Ok(())
+}
+
+/// Wraps a `PathBuilder`, keeping track of the state that SVG path data
+/// needs: the current point, the sub-path start and the last control point.
+#[derive(Debug, Clone)]
+struct PathWriter {
+    path: PathBuilder,
+    current: Point2,
+    start: Point2,
+    last_ctrl: Option<Point2>,
+}
+
+impl PathWriter {
+    fn new() -> Self {
+        PathWriter {
+            path: PathBuilder::new(),
+            current: Point2::origin(),
+            start: Point2::origin(),
+            last_ctrl: None,
+        }
+    }
+
+    fn move_to(&mut self, to: Point2) {
+        let _ = self.path.move_to(to);
+        self.current = to;
+        self.start = to;
+        self.last_ctrl = None;
+    }
+
+    fn line_to(&mut self, to: Point2) {
+        let _ = self.path.line_to(to);
+        self.current = to;
+        self.last_ctrl = None;
+    }
+
+    fn quadratic_to(&mut self, ctrl: Point2, to: Point2) {
+        let _ = self.path.quadratic_bezier_to(ctrl, to);
+        self.current = to;
+        self.last_ctrl = Some(ctrl);
+    }
+
+    fn cubic_to(&mut self, ctrl1: Point2, ctrl2: Point2, to: Point2) {
+        let _ = self.path.cubic_bezier_to(ctrl1, ctrl2, to);
+        self.current = to;
+        self.last_ctrl = Some(ctrl2);
+    }
+
+    /// An SVG elliptical arc, in endpoint parameterization.
+    fn arc_to(&mut self, radii: Vector2, x_rotation: f32, large_arc: bool, sweep: bool, to: Point2) {
+        match arc_to_cubics(self.current, radii, x_rotation, large_arc, sweep, to) {
+            Some(curves) => {
+                for [ctrl1, ctrl2, end] in curves {
+                    let _ = self.path.cubic_bezier_to(ctrl1, ctrl2, end);
+                }
+                self.current = to;
+                self.last_ctrl = None;
+            }
+            None => self.line_to(to),
+        }
+    }
+
+    fn close(&mut self) {
+        let _ = self.path.close();
+        self.current = self.start;
+        self.last_ctrl = None;
+    }
+
+    /// The reflection of the last control point, for `S` and `T` commands.
+    fn reflected_ctrl(&self) -> Point2 {
+        match self.last_ctrl {
+            Some(ctrl) => self.current + (self.current - ctrl),
+            None => self.current,
+        }
+    }
+
+    fn rectangle(&mut self, x: f32, y: f32, w: f32, h: f32, rx: f32, ry: f32) {
+        if w <= 0.0 || h <= 0.0 {
+            return;
+        }
+        let radii = Vector2::new(rx, ry);
+        self.move_to(Point2::new(x + rx, y));
+        self.line_to(Point2::new(x + w - rx, y));
+        self.arc_to(radii, 0.0, false, true, Point2::new(x + w, y + ry));
+        self.line_to(Point2::new(x + w, y + h - ry));
+        self.arc_to(radii, 0.0, false, true, Point2::new(x + w - rx, y + h));
+        self.line_to(Point2::new(x + rx, y + h));
+        self.arc_to(radii, 0.0, false, true, Point2::new(x, y + h - ry));
+        self.line_to(Point2::new(x, y + ry));
+        self.arc_to(radii, 0.0, false, true, Point2::new(x + rx, y));
+        self.close();
+    }
+
+    fn ellipse(&mut self, cx: f32, cy: f32, rx: f32, ry: f32) {
+        if rx <= 0.0 || ry <= 0.0 {
+            return;
+        }
+        let radii = Vector2::new(rx, ry);
+        self.move_to(Point2::new(cx + rx, cy));
+        self.arc_to(radii, 0.0, false, true, Point2::new(cx - rx, cy));
+        self.arc_to(radii, 0.0, false, true, Point2::new(cx + rx, cy));
+        self.close();
+    }
+
+    /// Appends the commands of an SVG path `d` attribute.
+    fn path_data(&mut self, data: &str) -> GameResult {
+        let mut parser = Parser::new(data);
+        let mut command = None;
+        loop {
+            parser.skip_separators();
+            if parser.is_done() {
+                break;
+            }
+            let c = match parser.command() {
+                Some(c) => c,
+                // Repeated arguments without a new letter continue the last
+                // command, except that `M` continues as `L`.
+                None => match command {
+                    Some(b'M') => b'L',
+                    Some(b'm') => b'l',
+                    Some(b'Z') | Some(b'z') | None => {
+                        return Err(parser.error("expected a command"));
+                    }
+                    Some(c) => c,
+                },
+            };
+            command = Some(c);
+            let relative = c.is_ascii_lowercase();
+            let origin = if relative {
+                self.current.coords
+            } else {
+                Vector2::zeros()
+            };
+            let point = |parser: &mut Parser| -> GameResult<Point2> {
+                let x = parser.number()?;
+                let y = parser.number()?;
+                Ok(Point2::new(x, y) + origin)
+            };
+            match c.to_ascii_uppercase() {
+                b'M' => {
+                    let to = point(&mut parser)?;
+                    self.move_to(to);
+                }
+                b'L' => {
+                    let to = point(&mut parser)?;
+                    self.line_to(to);
+                }
+                b'H' => {
+                    let x = parser.number()? + origin.x;
+                    self.line_to(Point2::new(x, self.current.y));
+                }
+                b'V' => {
+                    let y = parser.number()? + origin.y;
+                    self.line_to(Point2::new(self.current.x, y));
+                }
+                b'C' => {
+                    let ctrl1 = point(&mut parser)?;
+                    let ctrl2 = point(&mut parser)?;
+                    let to = point(&mut parser)?;
+                    self.cubic_to(ctrl1, ctrl2, to);
+                }
+                b'S' => {
+                    let ctrl1 = self.reflected_ctrl();
+                    let ctrl2 = point(&mut parser)?;
+                    let to = point(&mut parser)?;
+                    self.cubic_to(ctrl1, ctrl2, to);
+                }
+                b'Q' => {
+                    let ctrl = point(&mut parser)?;
+                    let to = point(&mut parser)?;
+                    self.quadratic_to(ctrl, to);
+                }
+                b'T' => {
+                    let ctrl = self.reflected_ctrl();
+                    let to = point(&mut parser)?;
+                    self.quadratic_to(ctrl, to);
+                }
+                b'A' => {
+                    let rx = parser.number()?;
+                    let ry = parser.number()?;
+                    let x_rotation = parser.number()?;
+                    let large_arc = parser.flag()?;
+                    let sweep = parser.flag()?;
+                    let to = point(&mut parser)?;
+                    self.arc_to(Vector2::new(rx, ry), x_rotation, large_arc, sweep, to);
+                }
+                b'Z' => self.close(),
+                _ => return Err(parser.error("unknown command")),
+            }
+            // `S` and `T` only reflect control points of their own kind.
+            match c.to_ascii_uppercase() {
+                b'C' | b'S' | b'Q' | b'T' => (),
+                _ => self.last_ctrl = None,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Approximates an SVG endpoint-parameterized elliptical arc with cubic
+/// bezier curves of at most 90 degrees each, following the conversion in
+/// the SVG spec's implementation notes.  Returns `None` if the arc
+/// degenerates into a straight line.
+fn arc_to_cubics(
+    from: Point2,
+    radii: Vector2,
+    x_rotation: f32,
+    large_arc: bool,
+    sweep: bool,
+    to: Point2,
+) -> Option<Vec<[Point2; 3]>> {
+    use std::f32::consts::PI;
+
+    if from == to {
+        return Some(Vec::new());
+    }
+    let (mut rx, mut ry) = (radii.x.abs(), radii.y.abs());
+    if rx <= std::f32::EPSILON || ry <= std::f32::EPSILON {
+        return None;
+    }
+    let phi = x_rotation.to_radians();
+    let (sin_phi, cos_phi) = phi.sin_cos();
+
+    let half = (from - to) / 2.0;
+    let x1 = cos_phi * half.x + sin_phi * half.y;
+    let y1 = -sin_phi * half.x + cos_phi * half.y;
+
+    // Scale the radii up if they are too small to reach the endpoint.
+    let lambda = (x1 * x1) / (rx * rx) + (y1 * y1) / (ry * ry);
+    if lambda > 1.0 {
+        rx *= lambda.sqrt();
+        ry *= lambda.sqrt();
+    }
+
+    let numerator = rx * rx * ry * ry - rx * rx * y1 * y1 - ry * ry * x1 * x1;
+    let denominator = rx * rx * y1 * y1 + ry * ry * x1 * x1;
+    let mut coefficient = (numerator / denominator).max(0.0).sqrt();
+    if large_arc == sweep {
+        coefficient = -coefficient;
+    }
+    let cx1 = coefficient * rx * y1 / ry;
+    let cy1 = -coefficient * ry * x1 / rx;
+    let mid = na::center(&from, &to);
+    let center = Point2::new(
+        cos_phi * cx1 - sin_phi * cy1 + mid.x,
+        sin_phi * cx1 + cos_phi * cy1 + mid.y,
+    );
+
+    let angle = |u: Vector2, v: Vector2| (u.x * v.y - u.y * v.x).atan2(u.x * v.x + u.y * v.y);
+    let start_vector = Vector2::new((x1 - cx1) / rx, (y1 - cy1) / ry);
+    let end_vector = Vector2::new((-x1 - cx1) / rx, (-y1 - cy1) / ry);
+    let start_angle = angle(Vector2::new(1.0, 0.0), start_vector);
+    let mut sweep_angle = angle(start_vector, end_vector);
+    if !sweep && sweep_angle > 0.0 {
+        sweep_angle -= 2.0 * PI;
+    } else if sweep && sweep_angle < 0.0 {
+        sweep_angle += 2.0 * PI;
+    }
+
+    let on_ellipse = |t: f32| {
+        let (sin_t, cos_t) = t.sin_cos();
+        Point2::new(
+            center.x + rx * cos_t * cos_phi - ry * sin_t * sin_phi,
+            center.y + rx * cos_t * sin_phi + ry * sin_t * cos_phi,
+        )
+    };
+    let derivative = |t: f32| {
+        let (sin_t, cos_t) = t.sin_cos();
+        Vector2::new(
+            -rx * sin_t * cos_phi - ry * cos_t * sin_phi,
+            -rx * sin_t * sin_phi + ry * cos_t * cos_phi,
+        )
+    };
+
+    let segments = (sweep_angle.abs() / (PI / 2.0) - 0.001).ceil().max(1.0) as usize;
+    let delta = sweep_angle / segments as f32;
+    let k = 4.0 / 3.0 * (delta / 4.0).tan();
+    let curves = (0..segments)
+        .map(|i| {
+            let t1 = start_angle + delta * i as f32;
+            let t2 = t1 + delta;
+            let (p1, p2) = (on_ellipse(t1), on_ellipse(t2));
+            // Land exactly on the requested endpoint at the end.
+            let end = if i + 1 == segments { to } else { p2 };
+            [p1 + derivative(t1) * k, p2 - derivative(t2) * k, end]
+        })
+        .collect();
+    Some(curves)
+}
+
+/// A tiny scanner for the number lists used by SVG attributes.
+#[derive(Debug)]
+struct Parser<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn new(data: &'a str) -> Self {
+        Parser {
+            data: data.as_bytes(),
+            pos: 0,
+        }
+    }
+
+    fn is_done(&self) -> bool {
+        self.pos >= self.data.len()
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.data.get(self.pos).cloned()
+    }
+
+    fn skip_separators(&mut self) {
+        while let Some(c) = self.peek() {
+            if c.is_ascii_whitespace() || c == b',' {
+                self.pos += 1;
+            } else {
+                break;
+            }
+        }
+    }
+
+    /// Consumes a command letter, if there is one.
+    fn command(&mut self) -> Option<u8> {
+        match self.peek() {
+            // `e` and `E` never start a command, only exponents.
+            Some(c) if c.is_ascii_alphabetic() && c != b'e' && c != b'E' => {
+                self.pos += 1;
+                Some(c)
+            }
+            _ => None,
+        }
+    }
+
+    fn number(&mut self) -> GameResult<f32> {
+        self.skip_separators();
+        let start = self.pos;
+        if let Some(b'+') | Some(b'-') = self.peek() {
+            self.pos += 1;
+        }
+        let mut seen_dot = false;
+        let mut seen_exponent = false;
+        while let Some(c) = self.peek() {
+            match c {
+                b'0'..=b'9' => (),
+                // A second dot starts the next number, as in `0.5.5`.
+                b'.' if !seen_dot && !seen_exponent => seen_dot = true,
+                b'e' | b'E' if !seen_exponent && self.pos > start => {
+                    seen_exponent = true;
+                    if let Some(b'+') | Some(b'-') = self.data.get(self.pos + 1) {
+                        self.pos += 1;
+                    }
+                }
+                _ => break,
+            }
+            self.pos += 1;
+        }
+        std::str::from_utf8(&self.data[start..self.pos])
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .ok_or_else(|| self.error("expected a number"))
+    }
+
+    /// Arc flags are a single `0` or `1` and may be written without separators.
+    fn flag(&mut self) -> GameResult<bool> {
+        self.skip_separators();
+        let flag = match self.peek() {
+            Some(b'0') => false,
+            Some(b'1') => true,
+            _ => return Err(self.error("expected an arc flag")),
+        };
+        self.pos += 1;
+        Ok(flag)
+    }
+
+    fn error(&self, message: &str) -> GameError {
+        GameError::ResourceLoadError(format!(
+            "Invalid SVG data at offset {}: {}",
+            self.pos, message
+        ))
+    }
+}
+
+fn parse_numbers(s: &str) -> GameResult<Vec<f32>> {
+    let mut parser = Parser::new(s);
+    let mut numbers = Vec::new();
+    loop {
+        parser.skip_separators();
+        if parser.is_done() {
+            return Ok(numbers);
+        }
+        numbers.push(parser.number()?);
+    }
+}
+
+fn parse_number(s: &str) -> GameResult<f32> {
+    s.trim()
+        .parse()
+        .map_err(|_| GameError::ResourceLoadError(format!("Invalid SVG number: {:?}", s)))
+}
+
+/// Parses a length, ignoring any unit; `px` is the only one that makes sense here anyway.
+fn parse_length(s: &str) -> GameResult<f32> {
+    let s = s.trim();
+    let number = s.trim_end_matches(|c: char| c.is_ascii_alphabetic() || c == '%');
+    parse_number(number)
+}
+
+/// Parses a `fill` or `stroke` value like `parse_paint()`, but leaves
+/// out the fill or stroke with a warning if it's a color this doesn't
+/// understand, rather than failing to load the whole file.
+fn paint_or_none(s: &str) -> Option<Color> {
+    parse_paint(s).unwrap_or_else(|e| {
+        warn!("Skipping a shape's fill or stroke: {}", e);
+        None
+    })
+}
+
+/// Parses a `fill` or `stroke` value; `None` means nothing should be drawn.
+fn parse_paint(s: &str) -> GameResult<Option<Color>> {
+    let s = s.trim();
+    let error = || GameError::ResourceLoadError(format!("Unsupported SVG color: {:?}", s));
+    if s == "none" || s.starts_with("url(") {
+        return Ok(None);
+    }
+    if s.starts_with('#') {
+        let hex = &s[1..];
+        let value = u32::from_str_radix(hex, 16).map_err(|_| error())?;
+        let expand = |v: u32| ((v & 0xf) * 0x11) as u8;
+        let color = match hex.len() {
+            3 => Color::from_rgb(expand(value >> 8), expand(value >> 4), expand(value)),
+            4 => Color::from_rgba(
+                expand(value >> 12),
+                expand(value >> 8),
+                expand(value >> 4),
+                expand(value),
+            ),
+            6 => Color::from_rgb_u32(value),
+            8 => Color::from_rgba_u32(value),
+            _ => return Err(error()),
+        };
+        return Ok(Some(color));
+    }
+    let function = if s.starts_with("rgb(") {
+        Some(4)
+    } else if s.starts_with("rgba(") {
+        Some(5)
+    } else {
+        None
+    };
+    if let Some(start) = function {
+        if !s.ends_with(')') {
+            return Err(error());
+        }
+        // Both take an optional alpha since CSS Color 4, with commas or
+        // with spaces and a slash.
+        let channels = s[start..s.len() - 1]
+            .split(|c: char| c == ',' || c == '/' || c.is_whitespace())
+            .filter(|channel| !channel.is_empty())
+            .enumerate()
+            .map(|(i, channel)| {
+                if channel.ends_with('%') {
+                    parse_number(&channel[..channel.len() - 1]).map(|v| v / 100.0)
+                } else if i == 3 {
+                    parse_number(channel)
+                } else {
+                    parse_number(channel).map(|v| v / 255.0)
+                }
+            })
+            .collect::<GameResult<Vec<f32>>>()?;
+        if channels.len() != 3 && channels.len() != 4 {
+            return Err(error());
+        }
+        let clamp = |v: f32| v.max(0.0).min(1.0);
+        let alpha = channels.get(3).cloned().unwrap_or(1.0);
+        return Ok(Some(Color::new(
+            clamp(channels[0]),
+            clamp(channels[1]),
+            clamp(channels[2]),
+            clamp(alpha),
+        )));
+    }
+    // Color names are case-insensitive.
+    let name = s.to_ascii_lowercase();
+    match name.as_str() {
+        "transparent" => return Ok(None),
+        // There's no `color` property to take it from.
+        "currentcolor" => return Ok(Some(BLACK)),
+        _ => (),
+    }
+    NAMED_COLORS
+        .binary_search_by(|&(n, _)| n.cmp(&name))
+        .map(|i| Some(Color::from_rgb_u32(NAMED_COLORS[i].1)))
+        .map_err(|_| error())
+}
+
+/// The named colors of CSS, sorted by name for a binary search.
+const NAMED_COLORS: [(&str, u32); 148] = [
+    ("aliceblue", 0xf0f8ff),
+    ("antiquewhite", 0xfaebd7),
+    ("aqua", 0x00ffff),
+    ("aquamarine", 0x7fffd4),
+    ("azure", 0xf0ffff),
+    ("beige", 0xf5f5dc),
+    ("bisque", 0xffe4c4),
+    ("black", 0x000000),
+    ("blanchedalmond", 0xffebcd),
+    ("blue", 0x0000ff),
+    ("blueviolet", 0x8a2be2),
+    ("brown", 0xa52a2a),
+    ("burlywood", 0xdeb887),
+    ("cadetblue", 0x5f9ea0),
+    ("chartreuse", 0x7fff00),
+    ("chocolate", 0xd2691e),
+    ("coral", 0xff7f50),
+    ("cornflowerblue", 0x6495ed),
+    ("cornsilk", 0xfff8dc),
+    ("crimson", 0xdc143c),
+    ("cyan", 0x00ffff),
+    ("darkblue", 0x00008b),
+    ("darkcyan", 0x008b8b),
+    ("darkgoldenrod", 0xb8860b),
+    ("darkgray", 0xa9a9a9),
+    ("darkgreen", 0x006400),
+    ("darkgrey", 0xa9a9a9),
+    ("darkkhaki", 0xbdb76b),
+    ("darkmagenta", 0x8b008b),
+    ("darkolivegreen", 0x556b2f),
+    ("darkorange", 0xff8c00),
+    ("darkorchid", 0x9932cc),
+    ("darkred", 0x8b0000),
+    ("darksalmon", 0xe9967a),
+    ("darkseagreen", 0x8fbc8f),
+    ("darkslateblue", 0x483d8b),
+    ("darkslategray", 0x2f4f4f),
+    ("darkslategrey", 0x2f4f4f),
+    ("darkturquoise", 0x00ced1),
+    ("darkviolet", 0x9400d3),
+    ("deeppink", 0xff1493),
+    ("deepskyblue", 0x00bfff),
+    ("dimgray", 0x696969),
+    ("dimgrey", 0x696969),
+    ("dodgerblue", 0x1e90ff),
+    ("firebrick", 0xb22222),
+    ("floralwhite", 0xfffaf0),
+    ("forestgreen", 0x228b22),
+    ("fuchsia", 0xff00ff),
+    ("gainsboro", 0xdcdcdc),
+    ("ghostwhite", 0xf8f8ff),
+    ("gold", 0xffd700),
+    ("goldenrod", 0xdaa520),
+    ("gray", 0x808080),
+    ("green", 0x008000),
+    ("greenyellow", 0xadff2f),
+    ("grey", 0x808080),
+    ("honeydew", 0xf0fff0),
+    ("hotpink", 0xff69b4),
+    ("indianred", 0xcd5c5c),
+    ("indigo", 0x4b0082),
+    ("ivory", 0xfffff0),
+    ("khaki", 0xf0e68c),
+    ("lavender", 0xe6e6fa),
+    ("lavenderblush", 0xfff0f5),
+    ("lawngreen", 0x7cfc00),
+    ("lemonchiffon", 0xfffacd),
+    ("lightblue", 0xadd8e6),
+    ("lightcoral", 0xf08080),
+    ("lightcyan", 0xe0ffff),
+    ("lightgoldenrodyellow", 0xfafad2),
+    ("lightgray", 0xd3d3d3),
+    ("lightgreen", 0x90ee90),
+    ("lightgrey", 0xd3d3d3),
+    ("lightpink", 0xffb6c1),
+    ("lightsalmon", 0xffa07a),
+    ("lightseagreen", 0x20b2aa),
+    ("lightskyblue", 0x87cefa),
+    ("lightslategray", 0x778899),
+    ("lightslategrey", 0x778899),
+    ("lightsteelblue", 0xb0c4de),
+    ("lightyellow", 0xffffe0),
+    ("lime", 0x00ff00),
+    ("limegreen", 0x32cd32),
+    ("linen", 0xfaf0e6),
+    ("magenta", 0xff00ff),
+    ("maroon", 0x800000),
+    ("mediumaquamarine", 0x66cdaa),
+    ("mediumblue", 0x0000cd),
+    ("mediumorchid", 0xba55d3),
+    ("mediumpurple", 0x9370db),
+    ("mediumseagreen", 0x3cb371),
+    ("mediumslateblue", 0x7b68ee),
+    ("mediumspringgreen", 0x00fa9a),
+    ("mediumturquoise", 0x48d1cc),
+    ("mediumvioletred", 0xc71585),
+    ("midnightblue", 0x191970),
+    ("mintcream", 0xf5fffa),
+    ("mistyrose", 0xffe4e1),
+    ("moccasin", 0xffe4b5),
+    ("navajowhite", 0xffdead),
+    ("navy", 0x000080),
+    ("oldlace", 0xfdf5e6),
+    ("olive", 0x808000),
+    ("olivedrab", 0x6b8e23),
+    ("orange", 0xffa500),
+    ("orangered", 0xff4500),
+    ("orchid", 0xda70d6),
+    ("palegoldenrod", 0xeee8aa),
+    ("palegreen", 0x98fb98),
+    ("paleturquoise", 0xafeeee),
+    ("palevioletred", 0xdb7093),
+    ("papayawhip", 0xffefd5),
+    ("peachpuff", 0xffdab9),
+    ("peru", 0xcd853f),
+    ("pink", 0xffc0cb),
+    ("plum", 0xdda0dd),
+    ("powderblue", 0xb0e0e6),
+    ("purple", 0x800080),
+    ("rebeccapurple", 0x663399),
+    ("red", 0xff0000),
+    ("rosybrown", 0xbc8f8f),
+    ("royalblue", 0x4169e1),
+    ("saddlebrown", 0x8b4513),
+    ("salmon", 0xfa8072),
+    ("sandybrown", 0xf4a460),
+    ("seagreen", 0x2e8b57),
+    ("seashell", 0xfff5ee),
+    ("sienna", 0xa0522d),
+    ("silver", 0xc0c0c0),
+    ("skyblue", 0x87ceeb),
+    ("slateblue", 0x6a5acd),
+    ("slategray", 0x708090),
+    ("slategrey", 0x708090),
+    ("snow", 0xfffafa),
+    ("springgreen", 0x00ff7f),
+    ("steelblue", 0x4682b4),
+    ("tan", 0xd2b48c),
+    ("teal", 0x008080),
+    ("thistle", 0xd8bfd8),
+    ("tomato", 0xff6347),
+    ("turquoise", 0x40e0d0),
+    ("violet", 0xee82ee),
+    ("wheat", 0xf5deb3),
+    ("white", 0xffffff),
+    ("whitesmoke", 0xf5f5f5),
+    ("yellow", 0xffff00),
+    ("yellowgreen", 0x9acd32),
+];
+
+/// Parses a `transform` attribute into a single matrix.
+fn parse_transform(s: Option<&str>) -> GameResult<Transform> {
+    let mut result = IDENTITY;
+    let mut rest = match s {
+        Some(s) => s.trim(),
+        None => return Ok(result),
+    };
+    while !rest.is_empty() {
+        let error = || GameError::ResourceLoadError(format!("Invalid SVG transform: {:?}", s));
+        let open = rest.find('(').ok_or_else(error)?;
+        let close = rest.find(')').ok_or_else(error)?;
+        if close < open {
+            return Err(error());
+        }
+        let name = rest[..open].trim();
+        let args = parse_numbers(&rest[open + 1..close])?;
+        let arg = |i: usize, default: f32| args.get(i).cloned().unwrap_or(default);
+        let transform = match (name, args.len()) {
+            ("matrix", 6) => [args[0], args[1], args[2], args[3], args[4], args[5]],
+            ("translate", 1) | ("translate", 2) => [1.0, 0.0, 0.0, 1.0, args[0], arg(1, 0.0)],
+            ("scale", 1) | ("scale", 2) => [args[0], 0.0, 0.0, arg(1, args[0]), 0.0, 0.0],
+            ("rotate", 1) | ("rotate", 3) => {
+                let (sin, cos) = args[0].to_radians().sin_cos();
+                let (cx, cy) = (arg(1, 0.0), arg(2, 0.0));
+                let rotation = [cos, sin, -sin, cos, 0.0, 0.0];
+                let to_center = [1.0, 0.0, 0.0, 1.0, cx, cy];
+                let from_center = [1.0, 0.0, 0.0, 1.0, -cx, -cy];
+                multiply(&multiply(&to_center, &rotation), &from_center)
+            }
+            ("skewX", 1) => [1.0, 0.0, args[0].to_radians().tan(), 1.0, 0.0, 0.0],
+            ("skewY", 1) => [1.0, args[0].to_radians().tan(), 0.0, 1.0, 0.0, 0.0],
+            _ => return Err(error()),
+        };
+        result = multiply(&result, &transform);
+        rest = rest[close + 1..].trim_start_matches(|c: char| c.is_whitespace() || c == ',');
+    }
+    Ok(result)
+}
+
+/// Returns the transform that applies `b` first, then `a`.
+fn multiply(a: &Transform, b: &Transform) -> Transform {
+    [
+        a[0] * b[0] + a[2] * b[1],
+        a[1] * b[0] + a[3] * b[1],
+        a[0] * b[2] + a[2] * b[3],
+        a[1] * b[2] + a[3] * b[3],
+        a[0] * b[4] + a[2] * b[5] + a[4],
+        a[1] * b[4] + a[3] * b[5] + a[5],
+    ]
+}
+
+fn apply(t: &Transform, p: [f32; 2]) -> [f32; 2] {
+    [
+        t[0] * p[0] + t[2] * p[1] + t[4],
+        t[1] * p[0] + t[3] * p[1] + t[5],
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn headless_test_path_data() {
+        let mut writer = PathWriter::new();
+        writer
+            .path_data("M10 10 h 5 v5 l-5,0 z m 1-1 L2 2 3 3")
+            .unwrap();
+        let mut expected = PathBuilder::new();
+        let _ = expected
+            .move_to([10.0, 10.0])
+            .line_to([15.0, 10.0])
+            .line_to([15.0, 15.0])
+            .line_to([10.0, 15.0])
+            .close()
+            .move_to([11.0, 9.0])
+            .line_to([2.0, 2.0])
+            .line_to([3.0, 3.0]);
+        assert_eq!(writer.path, expected);
+
+        let mut writer = PathWriter::new();
+        assert!(writer.path_data("10 10").is_err());
+        assert!(writer.path_data("M 0 0 L 1").is_err());
+    }
+
+    #[test]
+    fn headless_test_numbers() {
+        assert_eq!(
+            parse_numbers("1,2 -3.5.5e1-1e-1").unwrap(),
+            vec![1.0, 2.0, -3.5, 5.0, -0.1]
+        );
+        assert_eq!(parse_length("12px").unwrap(), 12.0);
+    }
+
+    #[test]
+    fn headless_test_arc_flags_without_separators() {
+        let mut writer = PathWriter::new();
+        writer.path_data("M0 0 a5 5 0 1110 0").unwrap();
+        assert_eq!(writer.current, Point2::new(10.0, 0.0));
+    }
+
+    #[test]
+    fn headless_test_arc_to_cubics() {
+        // A half circle from (0, 0) to (10, 0) through (5, -5).
+        let curves = arc_to_cubics(
+            Point2::new(0.0, 0.0),
+            Vector2::new(5.0, 5.0),
+            0.0,
+            false,
+            true,
+            Point2::new(10.0, 0.0),
+        )
+        .unwrap();
+        assert_eq!(curves.len(), 2);
+        assert!((curves[0][2] - Point2::new(5.0, -5.0)).norm() < 0.001);
+        assert_eq!(curves[1][2], Point2::new(10.0, 0.0));
+
+        // Zero radii degrade into a straight line.
+        assert!(arc_to_cubics(
+            Point2::new(0.0, 0.0),
+            Vector2::new(0.0, 5.0),
+            0.0,
+            false,
+            true,
+            Point2::new(10.0, 0.0),
+        )
+        .is_none());
+    }
+
+    #[test]
+    fn headless_test_colors() {
+        assert_eq!(parse_paint("none").unwrap(), None);
+        assert_eq!(
+            parse_paint("#f00").unwrap(),
+            Some(Color::from_rgb(255, 0, 0))
+        );
+        assert_eq!(
+            parse_paint("#00ff80").unwrap(),
+            Some(Color::from_rgb(0, 255, 128))
+        );
+        assert_eq!(
+            parse_paint("rgb(0, 100%, 0)").unwrap(),
+            Some(Color::new(0.0, 1.0, 0.0, 1.0))
+        );
+        assert_eq!(parse_paint("blue").unwrap(), Some(Color::from_rgb(0, 0, 255)));
+        assert_eq!(
+            parse_paint("CornflowerBlue").unwrap(),
+            Some(Color::from_rgb(100, 149, 237))
+        );
+        assert_eq!(
+            parse_paint("rgba(255, 0, 0, 0.5)").unwrap(),
+            Some(Color::new(1.0, 0.0, 0.0, 0.5))
+        );
+        assert_eq!(
+            parse_paint("rgb(0 0 255 / 50%)").unwrap(),
+            Some(Color::new(0.0, 0.0, 1.0, 0.5))
+        );
+        assert_eq!(
+            parse_paint("#0f08").unwrap(),
+            Some(Color::from_rgba(0, 255, 0, 136))
+        );
+        assert_eq!(parse_paint("transparent").unwrap(), None);
+        assert!(parse_paint("#12345").is_err());
+        assert!(parse_paint("rgba(1, 2)").is_err());
+        assert!(parse_paint("chartreuse-ish").is_err());
+        assert_eq!(paint_or_none("chartreuse-ish"), None);
+    }
+
+    #[test]
+    fn headless_test_named_colors_sorted() {
+        assert!(NAMED_COLORS.windows(2).all(|w| w[0].0 < w[1].0));
+    }
+
+    #[test]
+    fn headless_test_transforms() {
+        let t = parse_transform(Some("translate(10, 20) scale(2)")).unwrap();
+        assert_eq!(apply(&t, [1.0, 1.0]), [12.0, 22.0]);
+
+        let t = parse_transform(Some("rotate(90 5 5)")).unwrap();
+        let p = apply(&t, [10.0, 5.0]);
+        assert!((p[0] - 5.0).abs() < 0.001 && (p[1] - 10.0).abs() < 0.001);
+
+        assert_eq!(parse_transform(None).unwrap(), IDENTITY);
+        assert!(parse_transform(Some("wobble(3)")).is_err());
+    }
+
+    #[test]
+    fn headless_test_style_inheritance() {
+        let document = roxmltree::Document::parse(
+            r##"<svg><g fill="red" stroke-width="3" opacity="0.5">
+                <rect style="stroke: #00f; fill-opacity: 0.5" width="1" height="1"/>
+            </g></svg>"##,
+        )
+        .unwrap();
+        let group = document.root_element().first_element_child().unwrap();
+        let rect = group.first_element_child().unwrap();
+        let style = Style::default().inherit(group).unwrap();
+        let style = style.inherit(rect).unwrap();
+        assert_eq!(style.stroke, Some(Color::from_rgb(0, 0, 255)));
+        assert_eq!(style.stroke_width, 3.0);
+        assert_eq!(style.fill_color().unwrap().a, 0.25);
+        assert_eq!(style.stroke_color().unwrap().a, 0.5);
+    }
+}
@@ -0,0 +1,341 @@
+//! 1D, 2D array and 3D textures, for feeding shaders lookup tables
+//! and tile sets.
+
+use std::convert::TryFrom;
+use std::fmt;
+use std::io::Read;
+use std::path;
+
+use gfx::memory::Bind;
+use gfx::Factory;
+
+use crate::context::{Context, DebugId};
+use crate::error::{GameError, GameResult};
+use crate::graphics::codecs;
+use crate::graphics::*;
+
+/// The shape of a [`Texture`](struct.Texture.html).
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum TextureKind {
+    /// A single row of texels, for a `sampler1D` uniform.
+    D1,
+    /// A stack of same-sized 2D layers, for a `sampler2DArray` uniform.
+    D2Array,
+    /// A block of texels, for a `sampler3D` uniform.
+    D3,
+}
+
+/// A texture that can't be drawn directly, but can be sent to a
+/// [`Shader`](type.Shader.html) with
+/// [`Shader::send_texture()`](struct.ShaderGeneric.html#method.send_texture):
+/// a 1D palette, an array of 2D tiles or a 3D color lookup table.
+///
+/// Like an [`Image`](struct.Image.html), cloning it is cheap and
+/// shares the underlying texture.
+#[derive(Clone, PartialEq)]
+pub struct Texture {
+    pub(crate) texture: gfx::handle::RawShaderResourceView<gfx_device_gl::Resources>,
+    pub(crate) texture_handle: gfx::handle::RawTexture<gfx_device_gl::Resources>,
+    pub(crate) sampler_info: gfx::texture::SamplerInfo,
+    kind: TextureKind,
+    width: u16,
+    height: u16,
+    depth: u16,
+    pub(crate) debug_id: DebugId,
+}
+
+impl Texture {
+    /// Creates a texture of the given kind from a buffer of `u8` RGBA
+    /// values, laid out as for the constructor of that kind, whose
+    /// color space is given.  The other constructors assume sRGB data,
+    /// but lookup tables usually hold linear values.
+    pub fn from_rgba8(
+        ctx: &mut Context,
+        kind: TextureKind,
+        width: u16,
+        height: u16,
+        depth: u16,
+        rgba: &[u8],
+        color_space: ColorSpace,
+    ) -> GameResult<Self> {
+        Texture::make(ctx, kind, width, height, depth, rgba, color_space)
+    }
+
+    /// Creates a 1D texture from a buffer of `width` `u8` RGBA values.
+    pub fn new_1d(ctx: &mut Context, width: u16, rgba: &[u8]) -> GameResult<Self> {
+        Texture::make(ctx, TextureKind::D1, width, 1, 1, rgba, ColorSpace::Srgb)
+    }
+
+    /// Creates a 2D array texture with `layers` layers from a buffer of
+    /// `u8` RGBA values holding each layer in turn, laid out as for
+    /// [`Image::from_rgba8()`](struct.Image.html#method.from_rgba8).
+    pub fn new_2d_array(
+        ctx: &mut Context,
+        width: u16,
+        height: u16,
+        layers: u16,
+        rgba: &[u8],
+    ) -> GameResult<Self> {
+        Texture::make(
+            ctx,
+            TextureKind::D2Array,
+            width,
+            height,
+            layers,
+            rgba,
+            ColorSpace::Srgb,
+        )
+    }
+
+    /// Creates a 3D texture from a buffer of `u8` RGBA values holding
+    /// each `width` by `height` slice in turn, from front to back.
+    pub fn new_3d(
+        ctx: &mut Context,
+        width: u16,
+        height: u16,
+        depth: u16,
+        rgba: &[u8],
+    ) -> GameResult<Self> {
+        Texture::make(
+            ctx,
+            TextureKind::D3,
+            width,
+            height,
+            depth,
+            rgba,
+            ColorSpace::Srgb,
+        )
+    }
+
+    /// Loads a 1D texture from an image file, taking its pixels row by
+    /// row.  A palette is usually an image one pixel high.
+    pub fn new_1d_from_file<P: AsRef<path::Path>>(ctx: &mut Context, path: P) -> GameResult<Self> {
+        let (width, height, rgba) = load_file(ctx, path)?;
+        let texels = u32::from(width) * u32::from(height);
+        if texels > u32::from(u16::max_value()) {
+            return Err(GameError::ResourceLoadError(format!(
+                "Image of {} pixels is too large for a 1D texture",
+                texels
+            )));
+        }
+        Texture::new_1d(ctx, texels as u16, &rgba)
+    }
+
+    /// Loads a 2D array texture with one layer per image file.  All of
+    /// the images must be the same size.
+    pub fn new_2d_array_from_files<P: AsRef<path::Path>>(
+        ctx: &mut Context,
+        paths: &[P],
+    ) -> GameResult<Self> {
+        let layers = u16::try_from(paths.len()).map_err(|_| {
+            GameError::ResourceLoadError(format!(
+                "{} layers are too many for a texture array",
+                paths.len()
+            ))
+        })?;
+        let mut size = None;
+        let mut rgba = Vec::new();
+        for path in paths {
+            let (width, height, layer) = load_file(ctx, path)?;
+            let (first_width, first_height) = *size.get_or_insert((width, height));
+            if (first_width, first_height) != (width, height) {
+                return Err(GameError::ResourceLoadError(format!(
+                    "Texture array layers must all be {}x{}, but one is {}x{}",
+                    first_width, first_height, width, height
+                )));
+            }
+            rgba.extend(layer);
+        }
+        let (width, height) = size.ok_or_else(|| {
+            GameError::ResourceLoadError("A texture array needs at least one layer".to_string())
+        })?;
+        Texture::new_2d_array(ctx, width, height, layers, &rgba)
+    }
+
+    /// Loads a 3D texture from an image file holding its `depth` slices
+    /// side by side, left to right.  This is the usual layout of color
+    /// grading lookup tables, e.g. a 256x16 image for a 16x16x16 table,
+    /// which hold `ColorSpace::Linear` data.
+    pub fn new_3d_from_file<P: AsRef<path::Path>>(
+        ctx: &mut Context,
+        path: P,
+        depth: u16,
+        color_space: ColorSpace,
+    ) -> GameResult<Self> {
+        let (strip_width, height, strip) = load_file(ctx, path)?;
+        if depth == 0 || strip_width % depth != 0 {
+            return Err(GameError::ResourceLoadError(format!(
+                "An image {} pixels wide can't be split into {} slices",
+                strip_width, depth
+            )));
+        }
+        let width = strip_width / depth;
+        let slice_row_bytes = usize::from(width) * 4;
+        let mut rgba = Vec::with_capacity(strip.len());
+        for slice in 0..usize::from(depth) {
+            for row in strip.chunks_exact(usize::from(strip_width) * 4) {
+                let start = slice * slice_row_bytes;
+                rgba.extend_from_slice(&row[start..start + slice_row_bytes]);
+            }
+        }
+        Texture::make(
+            ctx,
+            TextureKind::D3,
+            width,
+            height,
+            depth,
+            &rgba,
+            color_space,
+        )
+    }
+
+    fn make(
+        ctx: &mut Context,
+        kind: TextureKind,
+        width: u16,
+        height: u16,
+        depth: u16,
+        rgba: &[u8],
+        color_space: ColorSpace,
+    ) -> GameResult<Self> {
+        if width == 0 || height == 0 || depth == 0 {
+            return Err(GameError::ResourceLoadError(format!(
+                "Tried to create a texture of size {}x{}x{}, each dimension must be >0",
+                width, height, depth
+            )));
+        }
+        let expected_bytes = usize::from(width) * usize::from(height) * usize::from(depth) * 4;
+        if rgba.len() != expected_bytes {
+            return Err(GameError::ResourceLoadError(format!(
+                "Tried to create a texture of size {}x{}x{}, but gave {} bytes of data (expected {})",
+                width,
+                height,
+                depth,
+                rgba.len(),
+                expected_bytes
+            )));
+        }
+        let debug_id = DebugId::get(ctx);
+        let gfx = &mut ctx.gfx_context;
+        let color_format = gfx.texture_format(color_space);
+        let gfx::format::Format(surface_format, channel_type) = color_format;
+        // When checking updates gfx counts the height of 1D textures as
+        // 0, and the layers of arrays as their depth.
+        let (texture_kind, update_height, update_depth) = match kind {
+            TextureKind::D1 => (gfx::texture::Kind::D1(width), 0, 0),
+            TextureKind::D2Array => {
+                let kind =
+                    gfx::texture::Kind::D2Array(width, height, depth, gfx::texture::AaMode::Single);
+                (kind, height, depth)
+            }
+            TextureKind::D3 => (gfx::texture::Kind::D3(width, height, depth), height, depth),
+        };
+        let info = gfx::texture::Info {
+            kind: texture_kind,
+            levels: 1,
+            format: surface_format,
+            bind: Bind::SHADER_RESOURCE | Bind::TRANSFER_DST,
+            usage: gfx::memory::Usage::Dynamic,
+        };
+        let texture_handle = gfx
+            .factory
+            .create_texture_raw(info, Some(channel_type), None)?;
+        let image_info = gfx::texture::RawImageInfo {
+            xoffset: 0,
+            yoffset: 0,
+            zoffset: 0,
+            width,
+            height: update_height,
+            depth: update_depth,
+            format: color_format,
+            mipmap: 0,
+        };
+        gfx.encoder
+            .update_texture_raw(&texture_handle, None, image_info, rgba)
+            .map_err(|e| GameError::RenderError(format!("Texture update error: {:?}", e)))?;
+        let resource_desc = gfx::texture::ResourceDesc {
+            channel: channel_type,
+            layer: None,
+            min: 0,
+            max: 0,
+            swizzle: gfx::format::Swizzle::new(),
+        };
+        let texture = gfx
+            .factory
+            .view_texture_as_shader_resource_raw(&texture_handle, resource_desc)?;
+        let mut sampler_info = gfx.default_sampler_info;
+        sampler_info.wrap_mode = (WrapMode::Clamp, WrapMode::Clamp, WrapMode::Clamp);
+        Ok(Texture {
+            texture,
+            texture_handle,
+            sampler_info,
+            kind,
+            width,
+            height,
+            depth,
+            debug_id,
+        })
+    }
+
+    /// Returns the shape of the texture.
+    pub fn kind(&self) -> TextureKind {
+        self.kind
+    }
+
+    /// Returns the width of the texture.
+    pub fn width(&self) -> u16 {
+        self.width
+    }
+
+    /// Returns the height of the texture, which is 1 for 1D textures.
+    pub fn height(&self) -> u16 {
+        self.height
+    }
+
+    /// Returns the number of layers of a 2D array texture, the depth of
+    /// a 3D texture, or 1 for 1D textures.
+    pub fn depth(&self) -> u16 {
+        self.depth
+    }
+
+    /// Get the filter mode for the texture.
+    pub fn filter(&self) -> FilterMode {
+        self.sampler_info.filter.into()
+    }
+
+    /// Set the filter mode for the texture.  It takes effect the next
+    /// time the texture is sent to a shader.
+    pub fn set_filter(&mut self, mode: FilterMode) {
+        self.sampler_info.filter = mode.into();
+    }
+
+    /// Gets the texture's `WrapMode` along the X, Y and Z axes.
+    pub fn wrap(&self) -> (WrapMode, WrapMode, WrapMode) {
+        self.sampler_info.wrap_mode
+    }
+
+    /// Sets the texture's `WrapMode` along the X, Y and Z axes.  Only
+    /// the axes the texture has matter; 2D array layers are never
+    /// wrapped.  It takes effect the next time the texture is sent to
+    /// a shader.  The default is `WrapMode::Clamp`.
+    pub fn set_wrap(&mut self, wrap_x: WrapMode, wrap_y: WrapMode, wrap_z: WrapMode) {
+        self.sampler_info.wrap_mode = (wrap_x, wrap_y, wrap_z);
+    }
+}
+
+impl fmt::Debug for Texture {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "<Texture: {:?} {}x{}x{}, {:p}, texture address {:p}, sampler: {:?}>",
+            self.kind, self.width, self.height, self.depth, self, &self.texture, &self.sampler_info
+        )
+    }
+}
+
+fn load_file<P: AsRef<path::Path>>(ctx: &mut Context, path: P) -> GameResult<(u16, u16, Vec<u8>)> {
+    let mut buf = Vec::new();
+    let mut reader = ctx.filesystem.open(path)?;
+    let _ = reader.read_to_end(&mut buf)?;
+    codecs::decode(&buf)
+}
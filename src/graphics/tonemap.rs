@@ -0,0 +1,154 @@
+//! Tonemapping, for drawing HDR canvases to the screen.
+
+use std::fmt;
+
+use crate::context::Context;
+use crate::error::{GameError, GameResult};
+use crate::graphics::*;
+
+gfx_defines! {
+    /// The constants of the tonemapping shader.
+    constant TonemapConsts {
+        exposure: f32 = "u_Exposure",
+    }
+}
+
+/// The curve a [`Tonemapper`](struct.Tonemapper.html) squeezes colors
+/// of any brightness into the 0 to 1 range with.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Tonemap {
+    /// `c / (1 + c)`: simple, and keeps colors as they are, but looks
+    /// a bit flat.
+    Reinhard,
+    /// A fit of the ACES filmic curve, with more contrast and
+    /// desaturated highlights like film.
+    Aces,
+}
+
+/// Draws an HDR [`Canvas`](type.Canvas.html), or anything else, with
+/// its colors scaled by an exposure and tonemapped to fit the 0 to 1
+/// range of the screen and 8-bit canvases.
+///
+/// The result is linear, so it should be drawn with
+/// [`WindowSetup::srgb`](../conf/struct.WindowSetup.html#structfield.srgb)
+/// on to have it encoded as sRGB for display.
+pub struct Tonemapper {
+    shader: Shader<TonemapConsts>,
+    tonemap: Tonemap,
+    exposure: f32,
+}
+
+impl Tonemapper {
+    /// Creates a tonemapper using the given curve, with an exposure of 1.
+    pub fn new(ctx: &mut Context, tonemap: Tonemap) -> GameResult<Self> {
+        let api = ctx.gfx_context.backend_spec.api();
+        let (vertex_source, pixel_source): (&[u8], &[u8]) = match api {
+            glutin::Api::OpenGl => (
+                include_bytes!("shader/basic_150.vert.glsl"),
+                include_bytes!("shader/tonemap_150.frag.glsl"),
+            ),
+            glutin::Api::OpenGlEs => (
+                include_bytes!("shader/tonemap_es300.vert.glsl"),
+                include_bytes!("shader/tonemap_es300.frag.glsl"),
+            ),
+            a => {
+                return Err(GameError::RenderError(format!(
+                    "Tonemapping isn't supported on {:?}",
+                    a
+                )))
+            }
+        };
+        let pixel_source = match tonemap {
+            Tonemap::Reinhard => pixel_source.to_vec(),
+            Tonemap::Aces => with_define(pixel_source, "TONEMAP_ACES"),
+        };
+        let exposure = 1.0;
+        let shader = Shader::from_u8(
+            ctx,
+            vertex_source,
+            &pixel_source,
+            TonemapConsts { exposure },
+            "Tonemap",
+            Some(&[BlendMode::Alpha, BlendMode::Replace, BlendMode::Add]),
+        )?;
+        Ok(Tonemapper {
+            shader,
+            tonemap,
+            exposure,
+        })
+    }
+
+    /// Returns the curve the tonemapper uses.
+    pub fn tonemap(&self) -> Tonemap {
+        self.tonemap
+    }
+
+    /// Returns the exposure colors are multiplied by before tonemapping.
+    pub fn exposure(&self) -> f32 {
+        self.exposure
+    }
+
+    /// Sets the exposure colors are multiplied by before tonemapping.
+    /// Higher values make the picture brighter.
+    pub fn set_exposure(&mut self, ctx: &mut Context, exposure: f32) -> GameResult {
+        self.shader.send(ctx, TonemapConsts { exposure })?;
+        self.exposure = exposure;
+        Ok(())
+    }
+
+    /// Draws `drawable` tonemapped, to the current canvas or the screen.
+    /// Its blend mode must be `Alpha`, `Replace` or `Add`.
+    pub fn draw<D, T>(&self, ctx: &mut Context, drawable: &D, params: T) -> GameResult
+    where
+        D: Drawable,
+        T: Into<DrawParam>,
+    {
+        let _lock = use_shader(ctx, &self.shader);
+        drawable.draw(ctx, params.into())
+    }
+}
+
+impl fmt::Debug for Tonemapper {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "<Tonemapper: {:?}, exposure {}, {:p}>",
+            self.tonemap, self.exposure, self
+        )
+    }
+}
+
+/// Inserts a `#define` after the `#version` line that has to come
+/// first in GLSL source.
+fn with_define(source: &[u8], name: &str) -> Vec<u8> {
+    let split = source
+        .iter()
+        .position(|&b| b == b'\n')
+        .map_or(source.len(), |i| i + 1);
+    let mut result = Vec::with_capacity(source.len() + name.len() + 9);
+    result.extend_from_slice(&source[..split]);
+    if split == source.len() && !source.ends_with(b"\n") {
+        result.push(b'\n');
+    }
+    result.extend_from_slice(format!("#define {}\n", name).as_bytes());
+    result.extend_from_slice(&source[split..]);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn headless_test_with_define() {
+        let source = b"#version 150 core\nvoid main() {}\n";
+        assert_eq!(
+            with_define(source, "FOO"),
+            b"#version 150 core\n#define FOO\nvoid main() {}\n".to_vec()
+        );
+        assert_eq!(
+            with_define(b"#version 100", "FOO"),
+            b"#version 100\n#define FOO\n".to_vec()
+        );
+    }
+}
@@ -431,7 +431,7 @@ impl From<LinearColor> for Color {
             if component <= 0.003_130_8 {
                 component * 12.92
             } else {
-                (1.0 + a) * component.powf(1.0 / 2.4)
+                (1.0 + a) * component.powf(1.0 / 2.4) - a
             }
         }
         Color {
@@ -449,6 +449,41 @@ impl From<LinearColor> for [f32; 4] {
     }
 }
 
+/// How the color values in an image or texture's data are encoded.
+///
+/// Pictures are almost always sRGB, but data that only happens to be
+/// stored in an image, such as a normal map or a lookup table, is
+/// linear and must not be converted when a shader samples it.  This
+/// only makes a difference when `WindowSetup::srgb` is on; otherwise
+/// no conversions happen at all.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum ColorSpace {
+    /// The data is sRGB encoded, like `Color`.
+    Srgb,
+    /// The data is linear and is sampled as it is.
+    Linear,
+}
+
+impl Default for ColorSpace {
+    fn default() -> Self {
+        ColorSpace::Srgb
+    }
+}
+
+/// How presenting a frame waits for the monitor's vertical blank, see
+/// [`set_vsync()`](fn.set_vsync.html).
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Vsync {
+    /// Frames are shown as soon as they are presented, which can tear.
+    Off,
+    /// Presenting waits for the vertical blank, so frames never tear
+    /// but late ones are shown a whole refresh later.
+    On,
+    /// Like `On` for frames that are in time, but late frames are shown
+    /// at once, tearing a little instead of stuttering.
+    Adaptive,
+}
+
 /// Specifies whether a mesh should be drawn
 /// filled or as an outline.
 #[derive(Debug, Copy, Clone)]
@@ -472,12 +507,25 @@ impl DrawMode {
 }
 
 /// Specifies what blending method to use when scaling up/down images.
+///
+/// The mipmapped modes only make a difference for images that have
+/// mipmaps, see [`Image::from_rgba8_mipmapped()`](struct.Image.html#method.from_rgba8_mipmapped);
+/// other images are filtered as with `Nearest` or `Linear`.
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
 pub enum FilterMode {
     /// Use linear interpolation (ie, smooth)
     Linear,
     /// Use nearest-neighbor interpolation (ie, pixelated)
     Nearest,
+    /// Use nearest-neighbor interpolation within the closest mipmap level
+    NearestMipmap,
+    /// Use linear interpolation within and between mipmap levels
+    Trilinear,
+    /// Use trilinear filtering, taking up to the given number of samples
+    /// (usually 2 to 16) along surfaces viewed at an angle or stretched
+    /// in one direction.  Drivers without anisotropic filtering treat
+    /// this as `Trilinear`.
+    Anisotropic(u8),
 }
 
 use gfx::texture::FilterMethod;
@@ -486,7 +534,10 @@ impl From<FilterMethod> for FilterMode {
     fn from(f: FilterMethod) -> Self {
         match f {
             FilterMethod::Scale => FilterMode::Nearest,
-            _other => FilterMode::Linear,
+            FilterMethod::Mipmap => FilterMode::NearestMipmap,
+            FilterMethod::Bilinear => FilterMode::Linear,
+            FilterMethod::Trilinear => FilterMode::Trilinear,
+            FilterMethod::Anisotropic(samples) => FilterMode::Anisotropic(samples),
         }
     }
 }
@@ -496,6 +547,9 @@ impl From<FilterMode> for FilterMethod {
         match f {
             FilterMode::Nearest => FilterMethod::Scale,
             FilterMode::Linear => FilterMethod::Bilinear,
+            FilterMode::NearestMipmap => FilterMethod::Mipmap,
+            FilterMode::Trilinear => FilterMethod::Trilinear,
+            FilterMode::Anisotropic(samples) => FilterMethod::Anisotropic(samples.max(1)),
         }
     }
 }
@@ -540,6 +594,16 @@ mod tests {
         assert_eq!(puce1, puce4);
     }
 
+    #[test]
+    fn headless_test_linear_color_round_trip() {
+        let color = Color::new(0.0, 0.25, 1.0, 0.5);
+        let back = Color::from(LinearColor::from(color));
+        assert_relative_eq!(back.r, color.r);
+        assert_relative_eq!(back.g, color.g, epsilon = 1e-5);
+        assert_relative_eq!(back.b, color.b, epsilon = 1e-5);
+        assert_relative_eq!(back.a, color.a);
+    }
+
     #[test]
     fn headless_test_rect_scaling() {
         let r1 = Rect::new(0.0, 0.0, 128.0, 128.0);
@@ -735,4 +799,21 @@ mod tests {
             assert_relative_eq!(r, expected);
         }
     }
+
+    #[test]
+    fn headless_test_filter_mode_conversions() {
+        let modes = [
+            FilterMode::Linear,
+            FilterMode::Nearest,
+            FilterMode::NearestMipmap,
+            FilterMode::Trilinear,
+            FilterMode::Anisotropic(8),
+        ];
+        for &mode in &modes {
+            let method: FilterMethod = mode.into();
+            assert_eq!(FilterMode::from(method), mode);
+        }
+        let method: FilterMethod = FilterMode::Anisotropic(0).into();
+        assert_eq!(method, FilterMethod::Anisotropic(1));
+    }
 }
@@ -0,0 +1,81 @@
+//! Extra windows, sharing images, meshes, shaders and everything else
+//! with the main one, for tools like inspectors and palettes.
+
+use winit::event_loop::EventLoopWindowTarget;
+/// Identifies a window in events and the functions of this module.
+pub use winit::window::WindowId;
+
+use crate::conf::WindowMode;
+use crate::context::Context;
+use crate::error::GameResult;
+use crate::graphics::canvas;
+
+/// Opens another window with the given title and mode, and returns its
+/// id.  It has the main window's
+/// [`WindowSetup`](../conf/struct.WindowSetup.html) otherwise.
+///
+/// Everything made with the `Context` can be drawn in any window.  Its
+/// events go to
+/// [`EventHandler::window_event()`](../event/trait.EventHandler.html#method.window_event),
+/// and it stays open until [`close_window()`](fn.close_window.html) is
+/// called, even when the user tries to close it.
+pub fn create_window(
+    ctx: &mut Context,
+    event_loop: &EventLoopWindowTarget<()>,
+    title: &str,
+    mode: WindowMode,
+) -> GameResult<WindowId> {
+    let mut window_setup = ctx.conf.window_setup.clone();
+    window_setup.title = title.to_string();
+    let id = ctx
+        .gfx_context
+        .create_window(event_loop, &window_setup, &mode)?;
+    let active = active_window(ctx);
+    set_active_window(ctx, id)?;
+    let result = ctx
+        .gfx_context
+        .set_window_mode(mode)
+        .and_then(|_| canvas::update_pixel_canvas(ctx));
+    set_active_window(ctx, active)?;
+    result.map(|_| id)
+}
+
+/// Closes a window opened with [`create_window()`](fn.create_window.html).
+/// If it was active, the main window becomes active.  The main window
+/// can't be closed this way.
+pub fn close_window(ctx: &mut Context, window: WindowId) -> GameResult {
+    ctx.gfx_context.close_window(window)?;
+    // It can't have the focus anymore.
+    ctx.timer_context.set_window_focused(window, false);
+    Ok(())
+}
+
+/// Makes the given window the active one, which everything is drawn
+/// to and [`present()`](fn.present.html) shows, like
+/// [`set_canvas(None)`](fn.set_canvas.html).  Its screen coordinates
+/// are fitted to it, unless they were set with
+/// [`set_screen_coordinates()`](fn.set_screen_coordinates.html) while
+/// it was active.
+///
+/// To draw several windows, draw and present each in turn, then
+/// activate the main window again: mouse positions are tracked in the
+/// active window, and functions like [`set_mode()`](fn.set_mode.html)
+/// and [`size()`](fn.size.html) act on it.
+pub fn set_active_window(ctx: &mut Context, window: WindowId) -> GameResult {
+    ctx.gfx_context.activate_window(window)
+}
+
+/// Returns the window that is currently drawn to.
+pub fn active_window(ctx: &Context) -> WindowId {
+    ctx.gfx_context.active_window()
+}
+
+/// Returns the window the `Context` was created with.
+pub fn main_window(ctx: &Context) -> WindowId {
+    ctx.gfx_context.main_window
+}
+
+/// Returns the ids of all open windows.
+pub fn windows(ctx: &Context) -> Vec<WindowId> {
+    ctx.gfx_context.window_ids()
+}
@@ -1,4 +1,5 @@
-//! Input handling modules for keyboard, mouse and gamepad.
+//! Input handling modules for keyboard, mouse, gamepad and touch.
 pub mod gamepad;
 pub mod keyboard;
 pub mod mouse;
+pub mod touch;
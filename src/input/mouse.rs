@@ -4,10 +4,14 @@ use crate::context::Context;
 use crate::error::GameError;
 use crate::error::GameResult;
 use crate::graphics;
-use crate::graphics::Point2;
+use crate::graphics::{Image, Point2};
 use std::collections::HashMap;
+use std::path;
 use winit::dpi;
 pub use winit::event::{MouseButton};
+/// The system cursor shapes, for
+/// [`set_cursor_type()`](fn.set_cursor_type.html).
+pub use winit::window::CursorIcon as CursorType;
 
 /// Stores state information for the mouse.
 #[derive(Clone, Debug)]
@@ -17,6 +21,9 @@ pub struct MouseContext {
     buttons_pressed: HashMap<MouseButton, bool>,
     cursor_grabbed: bool,
     cursor_hidden: bool,
+    cursor_type: CursorType,
+    cursor_image: Option<(Image, Point2)>,
+    cursor_in_window: bool,
 }
 
 impl MouseContext {
@@ -27,6 +34,9 @@ impl MouseContext {
             buttons_pressed: HashMap::new(),
             cursor_grabbed: false,
             cursor_hidden: false,
+            cursor_type: CursorType::Default,
+            cursor_image: None,
+            cursor_in_window: false,
         }
     }
 
@@ -34,6 +44,20 @@ impl MouseContext {
         self.last_position = p;
     }
 
+    pub(crate) fn set_cursor_in_window(&mut self, in_window: bool) {
+        self.cursor_in_window = in_window;
+    }
+
+    /// The image to draw as the cursor and where, if any.
+    pub(crate) fn cursor_image(&self) -> Option<(&Image, Point2)> {
+        match self.cursor_image {
+            Some((ref image, hotspot)) if self.cursor_in_window && !self.cursor_hidden => {
+                Some((image, self.last_position - hotspot.coords))
+            }
+            _ => None,
+        }
+    }
+
     pub(crate) fn set_last_delta(&mut self, p: Point2) {
         self.last_delta = p;
     }
@@ -74,28 +98,83 @@ pub fn cursor_hidden(ctx: &Context) -> bool {
 /// Set whether or not the mouse is hidden (invisible).
 pub fn set_cursor_hidden(ctx: &mut Context, hidden: bool) {
     ctx.mouse_context.cursor_hidden = hidden;
-    graphics::window(ctx).window().set_cursor_visible(!hidden)
+    update_cursor_visibility(ctx);
+}
+
+/// Get the system cursor shape shown over the window.
+pub fn cursor_type(ctx: &Context) -> CursorType {
+    ctx.mouse_context.cursor_type
+}
+
+/// Set the system cursor shape shown over the window, such as a hand
+/// over buttons or a text cursor over text fields.
+pub fn set_cursor_type(ctx: &mut Context, cursor_type: CursorType) {
+    ctx.mouse_context.cursor_type = cursor_type;
+    graphics::window(ctx).window().set_cursor_icon(cursor_type);
+}
+
+/// Replaces the system cursor with an image, or shows the system
+/// cursor again when given `None`.  `hotspot` is the pixel of the image
+/// that points at the mouse position.
+///
+/// The image is drawn in screen coordinates by
+/// [`graphics::present()`](../../graphics/fn.present.html), over
+/// everything else, so it scales with the rest of the game.  Since it
+/// is drawn once per frame, it lags behind the mouse a little more
+/// than the system cursor does.
+pub fn set_cursor_image<P>(ctx: &mut Context, image: Option<Image>, hotspot: P)
+where
+    P: Into<mint::Point2<f32>>,
+{
+    let hotspot = Point2::from(hotspot.into());
+    ctx.mouse_context.cursor_image = image.map(|image| (image, hotspot));
+    update_cursor_visibility(ctx);
+}
+
+/// Loads an image from the given path in the
+/// [filesystem](../../filesystem/index.html) and uses it as the cursor
+/// with [`set_cursor_image()`](fn.set_cursor_image.html).
+pub fn set_cursor_image_from_path<Q, P>(ctx: &mut Context, path: Q, hotspot: P) -> GameResult
+where
+    Q: AsRef<path::Path>,
+    P: Into<mint::Point2<f32>>,
+{
+    let image = Image::new(ctx, path)?;
+    set_cursor_image(ctx, Some(image), hotspot);
+    Ok(())
+}
+
+/// The system cursor is shown unless it's hidden or replaced by an
+/// image.
+fn update_cursor_visibility(ctx: &Context) {
+    let mouse = &ctx.mouse_context;
+    let visible = !mouse.cursor_hidden && mouse.cursor_image.is_none();
+    graphics::window(ctx).window().set_cursor_visible(visible)
 }
 
-/// Get the current position of the mouse cursor, in pixels.
+/// Get the current position of the mouse cursor.
 /// Complement to [`set_position()`](fn.set_position.html).
-/// Uses strictly window-only coordinates.
+/// With the default `ScalingMode::Window` this is in logical pixels
+/// from the window's top left corner, otherwise it is in the virtual
+/// resolution's screen coordinates, and may lie outside of them over
+/// the letterbox bars.
 pub fn position(ctx: &Context) -> mint::Point2<f32> {
     ctx.mouse_context.last_position.into()
 }
 
-/// Set the current position of the mouse cursor, in pixels.
-/// Uses strictly window-only coordinates.
+/// Set the current position of the mouse cursor, in the same
+/// coordinates as [`position()`](fn.position.html).
 pub fn set_position<P>(ctx: &mut Context, point: P) -> GameResult<()>
 where
     P: Into<mint::Point2<f32>>,
 {
     let mintpoint = point.into();
     ctx.mouse_context.last_position = Point2::from(mintpoint);
+    let window_position = ctx.gfx_context.screen_to_window(mintpoint.x, mintpoint.y);
     graphics::window(ctx).window()
-        .set_cursor_position(dpi::LogicalPosition {
-            x: f64::from(mintpoint.x),
-            y: f64::from(mintpoint.y),
+        .set_cursor_position(dpi::PhysicalPosition {
+            x: f64::from(window_position.x),
+            y: f64::from(window_position.y),
         })
         .map_err(|_| GameError::WindowError("Couldn't set mouse cursor position!".to_owned()))
 }
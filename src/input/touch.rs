@@ -0,0 +1,492 @@
+//! Touch screen input, with gestures recognized from it.
+//!
+//! Touches are tracked from the moment a finger goes down until it
+//! leaves the screen, in screen coordinates like the mouse.  From them,
+//! taps, long presses, swipes, pinches and two-finger pans are
+//! recognized and queued as [`Gesture`](enum.Gesture.html)s.
+//!
+//! Games written for the mouse can also get the first finger down as
+//! the left mouse button with
+//! [`set_mouse_emulation()`](fn.set_mouse_emulation.html).
+
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+pub use winit::event::TouchPhase;
+
+use crate::context::Context;
+use crate::graphics::context::GraphicsContext;
+use crate::graphics::Point2;
+
+/// A finger on the screen.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Touch {
+    /// Tells the touch apart from the others while it lasts.  Ids may
+    /// be reused once it has ended.
+    pub id: u64,
+    /// Where the touch is, in screen coordinates.
+    pub position: mint::Point2<f32>,
+    /// How hard the finger presses, from 0 to 1, on screens that can
+    /// tell.
+    pub pressure: Option<f32>,
+    /// Whether the touch just started, moved, ended or was cancelled.
+    pub phase: TouchPhase,
+}
+
+impl Touch {
+    /// Converts a winit touch on the active window to screen
+    /// coordinates.
+    pub(crate) fn from_winit(gfx: &GraphicsContext, touch: &winit::event::Touch) -> Self {
+        Touch {
+            id: touch.id,
+            position: gfx
+                .window_to_screen(touch.location.x as f32, touch.location.y as f32)
+                .into(),
+            pressure: touch.force.map(|force| force.normalized() as f32),
+            phase: touch.phase,
+        }
+    }
+}
+
+/// A gesture recognized from touches, returned by
+/// [`next_gesture()`](fn.next_gesture.html).  Positions and distances
+/// are in screen coordinates.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Gesture {
+    /// A finger went down and up again quickly, without moving.
+    Tap {
+        /// Where the screen was tapped.
+        position: mint::Point2<f32>,
+    },
+    /// A finger has been held down without moving for a while.  Sent
+    /// while it is still down, and followed by no tap.
+    LongPress {
+        /// Where the finger is.
+        position: mint::Point2<f32>,
+    },
+    /// A finger moved far and quickly before leaving the screen.
+    Swipe {
+        /// Where the finger went down.
+        start: mint::Point2<f32>,
+        /// How far it moved in all.
+        delta: mint::Vector2<f32>,
+    },
+    /// Two fingers moved closer together or further apart.
+    Pinch {
+        /// The point halfway between them.
+        center: mint::Point2<f32>,
+        /// How much their distance changed since the last pinch, as a
+        /// factor: above 1 when spreading, below 1 when pinching.
+        scale: f32,
+    },
+    /// Two fingers moved together.
+    Pan {
+        /// The point halfway between them.
+        center: mint::Point2<f32>,
+        /// How far that point moved since the last pan.
+        delta: mint::Vector2<f32>,
+    },
+}
+
+/// The thresholds gestures are recognized with.
+///
+/// Defaults:
+///
+/// ```rust
+/// # use ggez::input::touch::*;
+/// # use std::time::Duration;
+/// # fn main() { assert_eq!(
+/// GestureSettings {
+///     move_tolerance: 10.0,
+///     tap_time: Duration::from_millis(300),
+///     long_press_time: Duration::from_millis(500),
+///     swipe_distance: 50.0,
+///     swipe_time: Duration::from_millis(500),
+/// }
+/// # , GestureSettings::default()); }
+/// ```
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct GestureSettings {
+    /// How far a finger can move, in screen coordinates, and still tap
+    /// or long press.
+    pub move_tolerance: f32,
+    /// The longest a tap can take.
+    pub tap_time: Duration,
+    /// How long a finger has to be held still to long press.
+    pub long_press_time: Duration,
+    /// How far a finger has to move to swipe.
+    pub swipe_distance: f32,
+    /// The longest a swipe can take.
+    pub swipe_time: Duration,
+}
+
+impl Default for GestureSettings {
+    fn default() -> Self {
+        GestureSettings {
+            move_tolerance: 10.0,
+            tap_time: Duration::from_millis(300),
+            long_press_time: Duration::from_millis(500),
+            swipe_distance: 50.0,
+            swipe_time: Duration::from_millis(500),
+        }
+    }
+}
+
+/// The most gestures kept for `next_gesture()`.  Games that never ask
+/// for them, running their own loop, would otherwise collect a pinch
+/// and a pan for every two-finger move.
+const MAX_QUEUED_GESTURES: usize = 64;
+
+/// A touch along with what the gesture recognizer needs to know
+/// about it.
+#[derive(Debug, Copy, Clone)]
+struct TrackedTouch {
+    touch: Touch,
+    start: Point2,
+    start_time: Instant,
+    /// Whether it went further than `move_tolerance` from `start`.
+    moved: bool,
+    /// Whether another finger was down at some point, which rules out
+    /// one-finger gestures.
+    multi: bool,
+    long_pressed: bool,
+}
+
+/// Stores state information for the touch screen.
+#[derive(Debug, Clone, Default)]
+pub struct TouchContext {
+    touches: Vec<TrackedTouch>,
+    /// The first finger down when none were, until it leaves the
+    /// screen.
+    primary: Option<u64>,
+    gestures: VecDeque<Gesture>,
+    settings: GestureSettings,
+    mouse_emulation: bool,
+    /// Whether the last touch handled drove the mouse.
+    drove_mouse: bool,
+}
+
+impl TouchContext {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    /// Updates the touches and recognizes gestures from them.
+    pub(crate) fn handle_touch(&mut self, touch: Touch, now: Instant) {
+        if touch.phase == TouchPhase::Started && self.touches.is_empty() {
+            self.primary = Some(touch.id);
+        }
+        self.drove_mouse = self.mouse_emulation && self.primary == Some(touch.id);
+        match touch.phase {
+            TouchPhase::Started => {
+                let multi = !self.touches.is_empty();
+                for tracked in &mut self.touches {
+                    tracked.multi = true;
+                }
+                self.touches.push(TrackedTouch {
+                    touch,
+                    start: Point2::from(touch.position),
+                    start_time: now,
+                    moved: false,
+                    multi,
+                    long_pressed: false,
+                });
+            }
+            TouchPhase::Moved => {
+                let before = self.two_fingers();
+                let tolerance = self.settings.move_tolerance;
+                if let Some(tracked) = self.touches.iter_mut().find(|t| t.touch.id == touch.id) {
+                    tracked.touch = touch;
+                    if (Point2::from(touch.position) - tracked.start).norm() > tolerance {
+                        tracked.moved = true;
+                    }
+                }
+                if let (Some(before), Some(after)) = (before, self.two_fingers()) {
+                    self.two_finger_gestures(before, after);
+                }
+            }
+            TouchPhase::Ended | TouchPhase::Cancelled => {
+                // Ids may be reused, so a later finger with the same one
+                // mustn't count as primary while others are down.
+                if self.primary == Some(touch.id) {
+                    self.primary = None;
+                }
+                let index = self.touches.iter().position(|t| t.touch.id == touch.id);
+                if let Some(index) = index {
+                    let mut tracked = self.touches.remove(index);
+                    tracked.touch = touch;
+                    if touch.phase == TouchPhase::Ended {
+                        self.one_finger_gestures(&tracked, now);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Sends long presses for fingers held down long enough, and drops
+    /// the oldest gestures if too many are waiting.  Called once the
+    /// events of a frame are cleared.
+    pub(crate) fn update(&mut self, now: Instant) {
+        while self.gestures.len() > MAX_QUEUED_GESTURES {
+            let _ = self.gestures.pop_front();
+        }
+        let settings = self.settings;
+        for tracked in &mut self.touches {
+            if !tracked.moved
+                && !tracked.multi
+                && !tracked.long_pressed
+                && now.duration_since(tracked.start_time) >= settings.long_press_time
+            {
+                tracked.long_pressed = true;
+                self.gestures.push_back(Gesture::LongPress {
+                    position: tracked.touch.position,
+                });
+            }
+        }
+    }
+
+    fn one_finger_gestures(&mut self, tracked: &TrackedTouch, now: Instant) {
+        if tracked.multi || tracked.long_pressed {
+            return;
+        }
+        let duration = now.duration_since(tracked.start_time);
+        let delta = Point2::from(tracked.touch.position) - tracked.start;
+        if !tracked.moved && duration <= self.settings.tap_time {
+            self.gestures.push_back(Gesture::Tap {
+                position: tracked.touch.position,
+            });
+        } else if delta.norm() >= self.settings.swipe_distance
+            && duration <= self.settings.swipe_time
+        {
+            self.gestures.push_back(Gesture::Swipe {
+                start: tracked.start.into(),
+                delta: delta.into(),
+            });
+        }
+    }
+
+    fn two_finger_gestures(&mut self, before: (Point2, Point2), after: (Point2, Point2)) {
+        let center_before = nalgebra::center(&before.0, &before.1);
+        let center_after = nalgebra::center(&after.0, &after.1);
+        let distance_before = (before.1 - before.0).norm();
+        let distance_after = (after.1 - after.0).norm();
+        if distance_before > 0.0 && (distance_after - distance_before).abs() > std::f32::EPSILON {
+            self.gestures.push_back(Gesture::Pinch {
+                center: center_after.into(),
+                scale: distance_after / distance_before,
+            });
+        }
+        let delta = center_after - center_before;
+        if delta.norm() > std::f32::EPSILON {
+            self.gestures.push_back(Gesture::Pan {
+                center: center_after.into(),
+                delta: delta.into(),
+            });
+        }
+    }
+
+    /// The positions of the two fingers on the screen, if there are
+    /// exactly two.
+    fn two_fingers(&self) -> Option<(Point2, Point2)> {
+        match self.touches.as_slice() {
+            [a, b] => Some((a.touch.position.into(), b.touch.position.into())),
+            _ => None,
+        }
+    }
+
+    /// Whether the last touch handled drove the mouse, including the
+    /// primary one leaving the screen.
+    pub(crate) fn drove_mouse(&self) -> bool {
+        self.drove_mouse
+    }
+}
+
+/// Returns the fingers on the screen, in the order they went down.
+pub fn touches(ctx: &Context) -> Vec<Touch> {
+    ctx.touch_context
+        .touches
+        .iter()
+        .map(|tracked| tracked.touch)
+        .collect()
+}
+
+/// Returns the touch with the given id, if it's still on the screen.
+pub fn touch(ctx: &Context, id: u64) -> Option<Touch> {
+    ctx.touch_context
+        .touches
+        .iter()
+        .find(|tracked| tracked.touch.id == id)
+        .map(|tracked| tracked.touch)
+}
+
+/// Returns the primary touch, the first finger that went down while
+/// no other was, if it's still on the screen.
+pub fn primary_touch(ctx: &Context) -> Option<Touch> {
+    ctx.touch_context.primary.and_then(|id| touch(ctx, id))
+}
+
+/// Returns the next recognized gesture, if there is one.  The
+/// [`EventHandler`](../../event/trait.EventHandler.html) gets them with
+/// `gesture_event()`.  Only the latest gestures are kept, so call this
+/// every frame to get them all.
+pub fn next_gesture(ctx: &mut Context) -> Option<Gesture> {
+    ctx.touch_context.gestures.pop_front()
+}
+
+/// Returns the thresholds gestures are recognized with.
+pub fn gesture_settings(ctx: &Context) -> GestureSettings {
+    ctx.touch_context.settings
+}
+
+/// Changes the thresholds gestures are recognized with.
+pub fn set_gesture_settings(ctx: &mut Context, settings: GestureSettings) {
+    ctx.touch_context.settings = settings;
+}
+
+/// Returns whether the primary touch is turned into left mouse button
+/// presses and mouse motion.
+pub fn mouse_emulation(ctx: &Context) -> bool {
+    ctx.touch_context.mouse_emulation
+}
+
+/// Sets whether the primary touch is turned into left mouse button
+/// presses and mouse motion, for games made for the mouse.  The
+/// `mouse` module's state follows it, and
+/// [`event::handle_event()`](../../event/fn.handle_event.html) calls the
+/// `EventHandler`'s mouse button and motion callbacks for it after
+/// `touch_event()`.  Off by default.
+pub fn set_mouse_emulation(ctx: &mut Context, emulate: bool) {
+    ctx.touch_context.mouse_emulation = emulate;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn touch(id: u64, x: f32, y: f32, phase: TouchPhase) -> Touch {
+        Touch {
+            id,
+            position: mint::Point2 { x, y },
+            pressure: None,
+            phase,
+        }
+    }
+
+    fn after(start: Instant, millis: u64) -> Instant {
+        start + Duration::from_millis(millis)
+    }
+
+    #[test]
+    fn headless_test_one_finger_gestures() {
+        let mut tc = TouchContext::new();
+        let start = Instant::now();
+        tc.handle_touch(touch(0, 10.0, 10.0, TouchPhase::Started), start);
+        tc.handle_touch(touch(0, 12.0, 10.0, TouchPhase::Moved), after(start, 50));
+        tc.handle_touch(touch(0, 12.0, 10.0, TouchPhase::Ended), after(start, 100));
+        assert_eq!(
+            tc.gestures.pop_front(),
+            Some(Gesture::Tap {
+                position: mint::Point2 { x: 12.0, y: 10.0 }
+            })
+        );
+
+        tc.handle_touch(touch(1, 10.0, 10.0, TouchPhase::Started), start);
+        tc.handle_touch(touch(1, 110.0, 10.0, TouchPhase::Moved), after(start, 100));
+        tc.handle_touch(touch(1, 110.0, 10.0, TouchPhase::Ended), after(start, 200));
+        assert_eq!(
+            tc.gestures.pop_front(),
+            Some(Gesture::Swipe {
+                start: mint::Point2 { x: 10.0, y: 10.0 },
+                delta: mint::Vector2 { x: 100.0, y: 0.0 }
+            })
+        );
+
+        tc.handle_touch(touch(2, 10.0, 10.0, TouchPhase::Started), start);
+        tc.update(after(start, 400));
+        assert_eq!(tc.gestures.pop_front(), None);
+        tc.update(after(start, 600));
+        tc.handle_touch(touch(2, 10.0, 10.0, TouchPhase::Ended), after(start, 700));
+        assert_eq!(
+            tc.gestures.pop_front(),
+            Some(Gesture::LongPress {
+                position: mint::Point2 { x: 10.0, y: 10.0 }
+            })
+        );
+        assert_eq!(tc.gestures.pop_front(), None);
+    }
+
+    #[test]
+    fn headless_test_two_finger_gestures() {
+        let mut tc = TouchContext::new();
+        let start = Instant::now();
+        tc.handle_touch(touch(0, 0.0, 0.0, TouchPhase::Started), start);
+        tc.handle_touch(touch(1, 10.0, 0.0, TouchPhase::Started), start);
+        assert_eq!(tc.primary, Some(0));
+        tc.handle_touch(touch(1, 20.0, 0.0, TouchPhase::Moved), after(start, 50));
+        assert_eq!(
+            tc.gestures.pop_front(),
+            Some(Gesture::Pinch {
+                center: mint::Point2 { x: 10.0, y: 0.0 },
+                scale: 2.0
+            })
+        );
+        assert_eq!(
+            tc.gestures.pop_front(),
+            Some(Gesture::Pan {
+                center: mint::Point2 { x: 10.0, y: 0.0 },
+                delta: mint::Vector2 { x: 5.0, y: 0.0 }
+            })
+        );
+        tc.handle_touch(touch(1, 20.0, 0.0, TouchPhase::Ended), after(start, 100));
+        tc.handle_touch(touch(0, 0.0, 0.0, TouchPhase::Ended), after(start, 100));
+        // Neither finger taps, since the other was down too.
+        assert_eq!(tc.gestures.pop_front(), None);
+    }
+
+    #[test]
+    fn headless_test_gesture_queue_is_capped() {
+        let mut tc = TouchContext::new();
+        let start = Instant::now();
+        tc.handle_touch(touch(0, 0.0, 0.0, TouchPhase::Started), start);
+        tc.handle_touch(touch(1, 10.0, 0.0, TouchPhase::Started), start);
+        for i in 1..=100 {
+            let x = 10.0 + i as f32;
+            tc.handle_touch(touch(1, x, 0.0, TouchPhase::Moved), start);
+        }
+        assert_eq!(tc.gestures.len(), 200);
+        tc.update(start);
+        assert_eq!(tc.gestures.len(), MAX_QUEUED_GESTURES);
+        // The latest ones are kept.
+        assert_eq!(
+            tc.gestures.back(),
+            Some(&Gesture::Pan {
+                center: mint::Point2 { x: 55.0, y: 0.0 },
+                delta: mint::Vector2 { x: 0.5, y: 0.0 }
+            })
+        );
+    }
+
+    #[test]
+    fn headless_test_primary_touch() {
+        let mut tc = TouchContext::new();
+        tc.mouse_emulation = true;
+        let start = Instant::now();
+        tc.handle_touch(touch(0, 0.0, 0.0, TouchPhase::Started), start);
+        assert!(tc.drove_mouse());
+        tc.handle_touch(touch(1, 10.0, 0.0, TouchPhase::Started), start);
+        assert!(!tc.drove_mouse());
+        tc.handle_touch(touch(0, 0.0, 0.0, TouchPhase::Ended), start);
+        // The primary touch drives the mouse up as it leaves.
+        assert!(tc.drove_mouse());
+        assert_eq!(tc.primary, None);
+
+        // A finger reusing its id while another is down isn't primary.
+        tc.handle_touch(touch(0, 0.0, 0.0, TouchPhase::Started), start);
+        assert!(!tc.drove_mouse());
+        assert_eq!(tc.primary, None);
+        tc.handle_touch(touch(0, 0.0, 0.0, TouchPhase::Cancelled), start);
+        tc.handle_touch(touch(1, 10.0, 0.0, TouchPhase::Cancelled), start);
+        tc.handle_touch(touch(2, 0.0, 0.0, TouchPhase::Started), start);
+        assert_eq!(tc.primary, Some(2));
+    }
+}
@@ -103,6 +103,7 @@ extern crate log;
 pub extern crate mint;
 pub extern crate nalgebra;
 
+pub mod clipboard;
 pub mod conf;
 mod context;
 pub mod error;
@@ -2,19 +2,23 @@
 //!
 //! ggez does not try to do any framerate limitation by default. If
 //! you want to run at anything other than full-bore max speed all the
-//! time, call [`thread::yield_now()`](https://doc.rust-lang.org/std/thread/fn.yield_now.html)
-//! (or [`timer::yield_now()`](fn.yield_now.html) which does the same
-//! thing) to yield to the OS so it has a chance to breathe before continuing
-//! with your game. This should prevent it from using 100% CPU as much unless it
-//! really needs to.  Enabling vsync by setting
-//! [`conf.window_setup.vsync`](../conf/struct.WindowSetup.html#structfield.vsync)
-//! in your [`Conf`](../conf/struct.Conf.html) object is generally the best
-//! way to cap your displayed framerate.
+//! time, set a [`FrameLimit`](../conf/struct.FrameLimit.html) in your
+//! [`Conf`](../conf/struct.Conf.html) object or with
+//! [`set_frame_limit()`](fn.set_frame_limit.html), and
+//! [`graphics::present()`](../graphics/fn.present.html) will wait until
+//! the next frame is due, optionally at a lower rate while the window
+//! is in the background.  Enabling vsync by setting
+//! [`conf.window_setup.vsync`](../conf/struct.WindowSetup.html#structfield.vsync),
+//! or at runtime with [`graphics::set_vsync()`](../graphics/fn.set_vsync.html),
+//! is generally the best way to cap your displayed framerate, and the
+//! two can be combined.
 //!
 //! For a more detailed tutorial in how to handle frame timings in games,
 //! see <http://gafferongames.com/game-physics/fix-your-timestep/>
 
+use crate::conf::FrameLimit;
 use crate::context::Context;
+use crate::graphics::WindowId;
 
 use std::cmp;
 use std::f64;
@@ -94,6 +98,14 @@ pub struct TimeContext {
     frame_durations: LogBuffer<time::Duration>,
     residual_update_dt: time::Duration,
     frame_count: usize,
+    fixed_delta: Option<time::Duration>,
+    /// Whether one of the game's windows has the focus, for the
+    /// frame limiter's `background_fps`.
+    pub(crate) focused: bool,
+    /// The windows that `Focused` events said have the focus.
+    focused_windows: Vec<WindowId>,
+    /// When the frame limiter last let a frame end.
+    last_frame_due: Option<time::Instant>,
 }
 
 // How many frames we log update times for.
@@ -109,9 +121,24 @@ impl TimeContext {
             frame_durations: LogBuffer::new(TIME_LOG_FRAMES, initial_dt),
             residual_update_dt: time::Duration::from_secs(0),
             frame_count: 0,
+            fixed_delta: None,
+            focused: true,
+            focused_windows: Vec::new(),
+            last_frame_due: None,
         }
     }
 
+    /// Records a window gaining or losing the focus.  The game counts
+    /// as focused while any of its windows is, so moving the focus
+    /// between them doesn't matter.
+    pub(crate) fn set_window_focused(&mut self, window: WindowId, focused: bool) {
+        self.focused_windows.retain(|&w| w != window);
+        if focused {
+            self.focused_windows.push(window);
+        }
+        self.focused = !self.focused_windows.is_empty();
+    }
+
     /// Update the state of the `TimeContext` to record that
     /// another frame has taken place.  Necessary for the FPS
     /// tracking and [`check_update_time()`](fn.check_update_time.html)
@@ -121,7 +148,7 @@ impl TimeContext {
     /// [`event::run()`](../event/fn.run.html) will do it for you.
     pub fn tick(&mut self) {
         let now = time::Instant::now();
-        let time_since_last = now - self.last_instant;
+        let time_since_last = self.fixed_delta.unwrap_or(now - self.last_instant);
         self.frame_durations.push(time_since_last);
         self.last_instant = now;
         self.frame_count += 1;
@@ -183,7 +210,7 @@ pub fn f64_to_duration(t: f64) -> time::Duration {
 /// frame should be to match the given fps.
 ///
 /// Approximately.
-fn fps_as_duration(fps: u32) -> time::Duration {
+pub(crate) fn fps_as_duration(fps: u32) -> time::Duration {
     let target_dt_seconds = 1.0 / f64::from(fps);
     f64_to_duration(target_dt_seconds)
 }
@@ -262,6 +289,80 @@ pub fn remaining_update_time(ctx: &mut Context) -> time::Duration {
     ctx.timer_context.residual_update_dt
 }
 
+/// Makes every frame count as exactly `delta` long, no matter how much
+/// time really passed, or goes back to measuring real time with `None`.
+///
+/// This affects [`delta()`](fn.delta.html), [`fps()`](fn.fps.html) and
+/// [`check_update_time()`](fn.check_update_time.html), so a game driven
+/// by them runs deterministically, one fixed step per frame.  Useful for
+/// recording gameplay or replaying input; see
+/// [`graphics::start_recording()`](../graphics/fn.start_recording.html).
+pub fn set_fixed_delta(ctx: &mut Context, delta: Option<time::Duration>) {
+    ctx.timer_context.fixed_delta = delta;
+}
+
+/// Returns the fixed frame length set with
+/// [`set_fixed_delta()`](fn.set_fixed_delta.html), if any.
+pub fn fixed_delta(ctx: &Context) -> Option<time::Duration> {
+    ctx.timer_context.fixed_delta
+}
+
+/// Returns the frame limiter settings.
+pub fn frame_limit(ctx: &Context) -> FrameLimit {
+    ctx.conf.frame_limit
+}
+
+/// Changes the frame limiter settings, which are first taken from
+/// [`Conf::frame_limit`](../conf/struct.Conf.html#structfield.frame_limit).
+pub fn set_frame_limit(ctx: &mut Context, frame_limit: FrameLimit) {
+    ctx.conf.frame_limit = frame_limit;
+}
+
+/// Waits until the next frame is due under the frame limit, sleeping
+/// most of the time and spinning for the rest, which is more accurate.
+/// Called at the end of `graphics::present()` for the main window.
+pub(crate) fn limit_frame_rate(ctx: &mut Context) {
+    let limit = ctx.conf.frame_limit;
+    let tc = &mut ctx.timer_context;
+    let fps = if !tc.focused && limit.background_fps > 0.0 {
+        limit.background_fps
+    } else {
+        limit.target_fps
+    };
+    if fps <= 0.0 {
+        tc.last_frame_due = None;
+        return;
+    }
+    let now = time::Instant::now();
+    let due = next_frame_due(tc.last_frame_due, now, f64_to_duration(1.0 / fps));
+    tc.last_frame_due = Some(due);
+    let spin = if limit.spin_ms > 0.0 {
+        f64_to_duration(limit.spin_ms / 1000.0)
+    } else {
+        time::Duration::from_secs(0)
+    };
+    if due > now + spin {
+        thread::sleep(due - (now + spin));
+    }
+    while time::Instant::now() < due {
+        thread::yield_now();
+    }
+}
+
+/// When the frame after the one due at `last_due` is due: a frame
+/// later, or now if the game fell more than a frame behind, so that it
+/// doesn't rush through frames to catch up.
+fn next_frame_due(
+    last_due: Option<time::Instant>,
+    now: time::Instant,
+    frame: time::Duration,
+) -> time::Instant {
+    match last_due {
+        Some(last_due) if last_due + frame * 2 >= now => last_due + frame,
+        _ => now,
+    }
+}
+
 /// Pauses the current thread for the target duration.
 /// Just calls [`std::thread::sleep()`](https://doc.rust-lang.org/std/thread/fn.sleep.html)
 /// so it's as accurate as that is (which is usually not very).
@@ -284,3 +385,23 @@ pub fn yield_now() {
 pub fn ticks(ctx: &Context) -> usize {
     ctx.timer_context.frame_count
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn headless_test_next_frame_due() {
+        let frame = time::Duration::from_millis(10);
+        let start = time::Instant::now();
+        assert_eq!(next_frame_due(None, start, frame), start);
+        // On time or a little late, frames stay on the schedule.
+        let now = start + time::Duration::from_millis(3);
+        assert_eq!(next_frame_due(Some(start), now, frame), start + frame);
+        let now = start + time::Duration::from_millis(15);
+        assert_eq!(next_frame_due(Some(start), now, frame), start + frame);
+        // Far behind, the schedule starts over.
+        let now = start + time::Duration::from_millis(25);
+        assert_eq!(next_frame_due(Some(start), now, frame), now);
+    }
+}